@@ -0,0 +1,492 @@
+//! Forced alignment of a known transcript to audio
+//!
+//! The audio is decoded with token timestamps while the decoder is limited to
+//! the tokens of the reference, so timings come from the known words. The
+//! decoded words are then matched to the reference words with an edit-distance
+//! alignment. Matched words take the decoded timings, and words the decoder
+//! skipped (for example when it ended a window early) are interpolated between
+//! their neighbours.
+
+use crate::{
+    error::Result,
+    hallucination::HallucinationFilter,
+    whisper::{
+        sync::SyncWhisperTranscriber, SyncTranscriptionResult, TranscriptionSegment,
+        TranscriptionStats, WhisperTranscriber, WordTimestamp,
+    },
+    AudioProcessor, TranscriptionConfig,
+};
+use std::path::Path;
+
+/// A single step of an edit-distance alignment between two sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditOp {
+    /// Reference and hypothesis items are equal
+    Match { reference: usize, hypothesis: usize },
+    /// Reference item was recognized as a different hypothesis item
+    Substitute { reference: usize, hypothesis: usize },
+    /// Reference item is missing from the hypothesis
+    Delete { reference: usize },
+    /// Hypothesis item does not appear in the reference
+    Insert { hypothesis: usize },
+}
+
+/// Blocks up to this many matrix cells are aligned with full back-pointers
+const FULL_MATRIX_CELLS: usize = 1 << 16;
+
+/// Compute a minimum edit-distance alignment between two sequences
+///
+/// Operations are returned in sequence order. Ties prefer matches and
+/// substitutions over insertions and deletions. Long sequences are split
+/// with Hirschberg's algorithm, so memory stays linear in their length.
+pub fn align_sequences<T: PartialEq>(reference: &[T], hypothesis: &[T]) -> Vec<EditOp> {
+    let mut ops = Vec::with_capacity(reference.len().max(hypothesis.len()));
+    hirschberg(reference, hypothesis, (0, 0), &mut ops);
+    ops
+}
+
+/// Align a block of both sequences starting at `offset`, appending to `ops`
+fn hirschberg<T: PartialEq>(
+    reference: &[T],
+    hypothesis: &[T],
+    offset: (usize, usize),
+    ops: &mut Vec<EditOp>,
+) {
+    let (n, m) = (reference.len(), hypothesis.len());
+    if n < 2 || (n + 1) * (m + 1) <= FULL_MATRIX_CELLS {
+        align_block(reference, hypothesis, offset, ops);
+        return;
+    }
+
+    // Split the hypothesis where the best path crosses the middle reference row
+    let middle = n / 2;
+    let forward = last_cost_row(&reference[..middle], hypothesis, false);
+    let backward = last_cost_row(&reference[middle..], hypothesis, true);
+    let split = (0..=m)
+        .min_by_key(|&j| forward[j] + backward[m - j])
+        .unwrap_or(0);
+
+    hirschberg(&reference[..middle], &hypothesis[..split], offset, ops);
+    hirschberg(
+        &reference[middle..],
+        &hypothesis[split..],
+        (offset.0 + middle, offset.1 + split),
+        ops,
+    );
+}
+
+/// Edit distances from all of `reference` to each prefix of `hypothesis`
+///
+/// With `reversed`, both sequences are read back to front, so entry `j` is
+/// the distance to the last `j` hypothesis items.
+fn last_cost_row<T: PartialEq>(reference: &[T], hypothesis: &[T], reversed: bool) -> Vec<usize> {
+    let (n, m) = (reference.len(), hypothesis.len());
+    let at = |len: usize, i: usize| if reversed { len - 1 - i } else { i };
+
+    let mut previous: Vec<usize> = (0..=m).collect();
+    let mut current = vec![0; m + 1];
+    for i in 1..=n {
+        current[0] = i;
+        for j in 1..=m {
+            let cost = usize::from(reference[at(n, i - 1)] != hypothesis[at(m, j - 1)]);
+            current[j] = (previous[j - 1] + cost)
+                .min(previous[j] + 1)
+                .min(current[j - 1] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous
+}
+
+/// Align a small block with a full back-pointer matrix
+fn align_block<T: PartialEq>(
+    reference: &[T],
+    hypothesis: &[T],
+    (reference_offset, hypothesis_offset): (usize, usize),
+    ops: &mut Vec<EditOp>,
+) {
+    const DIAGONAL: u8 = 0;
+    const UP: u8 = 1;
+    const LEFT: u8 = 2;
+
+    let (n, m) = (reference.len(), hypothesis.len());
+    let width = m + 1;
+
+    // Only the back-pointers are kept for the full matrix, costs use two rows
+    let mut back = vec![DIAGONAL; (n + 1) * width];
+    let mut previous: Vec<usize> = (0..=m).collect();
+    let mut current = vec![0; width];
+    back[1..width].fill(LEFT);
+
+    for i in 1..=n {
+        current[0] = i;
+        back[i * width] = UP;
+        for j in 1..=m {
+            let cost = usize::from(reference[i - 1] != hypothesis[j - 1]);
+            let diagonal = previous[j - 1] + cost;
+            let up = previous[j] + 1;
+            let left = current[j - 1] + 1;
+
+            (current[j], back[i * width + j]) = if diagonal <= up && diagonal <= left {
+                (diagonal, DIAGONAL)
+            } else if up <= left {
+                (up, UP)
+            } else {
+                (left, LEFT)
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let first = ops.len();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match back[i * width + j] {
+            DIAGONAL => {
+                i -= 1;
+                j -= 1;
+                let (reference_index, hypothesis_index) =
+                    (reference_offset + i, hypothesis_offset + j);
+                ops.push(if reference[i] == hypothesis[j] {
+                    EditOp::Match {
+                        reference: reference_index,
+                        hypothesis: hypothesis_index,
+                    }
+                } else {
+                    EditOp::Substitute {
+                        reference: reference_index,
+                        hypothesis: hypothesis_index,
+                    }
+                });
+            }
+            UP => {
+                i -= 1;
+                ops.push(EditOp::Delete {
+                    reference: reference_offset + i,
+                });
+            }
+            _ => {
+                j -= 1;
+                ops.push(EditOp::Insert {
+                    hypothesis: hypothesis_offset + j,
+                });
+            }
+        }
+    }
+    ops[first..].reverse();
+}
+
+/// Normalize a word for comparison (lowercase, letters, digits and apostrophes only)
+pub fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '\'')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Assign timings from recognized words to the words of a reference transcript
+///
+/// Reference words keep their original spelling and punctuation. Words with no
+/// recognized counterpart are spread evenly over the gap between their aligned
+/// neighbours (or the start/end of the audio).
+pub fn align_words(
+    reference: &str,
+    recognized: &[WordTimestamp],
+    audio_duration: f64,
+) -> Vec<WordTimestamp> {
+    let reference_words: Vec<&str> = reference.split_whitespace().collect();
+    let reference_norm: Vec<String> = reference_words.iter().map(|w| normalize_word(w)).collect();
+    let recognized_norm: Vec<String> = recognized.iter().map(|w| normalize_word(&w.word)).collect();
+
+    let mut timings: Vec<Option<(f64, f64, Option<f32>)>> = vec![None; reference_words.len()];
+    for op in align_sequences(&reference_norm, &recognized_norm) {
+        match op {
+            EditOp::Match {
+                reference,
+                hypothesis,
+            } => {
+                let word = &recognized[hypothesis];
+                timings[reference] = Some((word.start, word.end, word.confidence));
+            }
+            EditOp::Substitute {
+                reference,
+                hypothesis,
+            } => {
+                let word = &recognized[hypothesis];
+                timings[reference] = Some((word.start, word.end, None));
+            }
+            EditOp::Delete { .. } | EditOp::Insert { .. } => {}
+        }
+    }
+
+    // Fill runs of unaligned words by interpolation
+    let mut i = 0;
+    while i < timings.len() {
+        if timings[i].is_some() {
+            i += 1;
+            continue;
+        }
+        let run_start = i;
+        while i < timings.len() && timings[i].is_none() {
+            i += 1;
+        }
+
+        let gap_start = run_start
+            .checked_sub(1)
+            .and_then(|prev| timings[prev])
+            .map_or(0.0, |(_, end, _)| end);
+        let gap_end = timings
+            .get(i)
+            .copied()
+            .flatten()
+            .map_or(audio_duration, |(start, _, _)| start)
+            .max(gap_start);
+        let count = (i - run_start) as f64;
+        let at = |k: usize| gap_start + (gap_end - gap_start) * k as f64 / count;
+
+        for (k, timing) in timings[run_start..i].iter_mut().enumerate() {
+            *timing = Some((at(k), at(k + 1), None));
+        }
+    }
+
+    reference_words
+        .into_iter()
+        .zip(timings)
+        .map(|(word, timing)| {
+            let (start, end, confidence) = timing.unwrap_or_default();
+            WordTimestamp {
+                word: word.to_string(),
+                start,
+                end,
+                confidence,
+            }
+        })
+        .collect()
+}
+
+/// Group aligned words into segments, one per sentence or transcript line
+pub fn build_segments(reference: &str, words: &[WordTimestamp]) -> Vec<TranscriptionSegment> {
+    let mut segments = Vec::new();
+    let mut index = 0;
+
+    for line in reference.lines() {
+        let line_words = line.split_whitespace().count();
+        let line_end = (index + line_words).min(words.len());
+
+        let mut start = index;
+        for i in index..line_end {
+            let ends_sentence = words[i].word.ends_with(['.', '?', '!']);
+            if ends_sentence || i + 1 == line_end {
+                segments.push(segment_from_words(&words[start..=i]));
+                start = i + 1;
+            }
+        }
+        index = line_end;
+    }
+
+    segments
+}
+
+fn segment_from_words(words: &[WordTimestamp]) -> TranscriptionSegment {
+    let text = words
+        .iter()
+        .map(|w| w.word.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    TranscriptionSegment {
+        text: format!(" {}", text),
+        start: words.first().map_or(0.0, |w| w.start),
+        end: words.last().map_or(0.0, |w| w.end),
+        confidence: None,
+        words: Some(words.to_vec()),
+        no_speech_prob: None,
+        hallucination: None,
+        speaker_turn_next: false,
+        tokens: None,
+    }
+}
+
+/// Align a known transcript to an audio file
+///
+/// Decoding uses the same path as [`transcribe_file_sync`](crate::transcribe_file_sync)
+/// with word timestamps enabled, constrained to the transcript and without
+/// the cache. The returned result contains the reference text with word- and
+/// segment-level timings.
+pub async fn align_file<P: AsRef<Path>>(
+    audio_path: P,
+    transcript: &str,
+    config: Option<TranscriptionConfig>,
+) -> Result<SyncTranscriptionResult> {
+    let mut config = config.unwrap_or_default();
+    config.output_format.word_timestamps = true;
+    // Repeats in the transcript are real, they must not be dropped
+    config.hallucinations = HallucinationFilter::default();
+
+    let transcriber = SyncWhisperTranscriber::from_config(config)
+        .await?
+        .with_forced_text(transcript);
+    let audio_data = AudioProcessor::new()?
+        .load_audio(audio_path.as_ref())
+        .await?;
+    let recognized = transcriber.transcribe(audio_data).await?;
+    let recognized_words: Vec<WordTimestamp> = recognized
+        .segments
+        .iter()
+        .flat_map(|segment| segment.words.iter().flatten().cloned())
+        .collect();
+
+    let words = align_words(
+        transcript,
+        &recognized_words,
+        recognized.audio_duration as f64,
+    );
+    let segments = build_segments(transcript, &words);
+    let text = segments.iter().map(|s| s.text.as_str()).collect::<String>();
+
+    let stats = TranscriptionStats::new(
+        recognized.processing_time,
+        recognized.audio_duration,
+        segments.len(),
+        words.len(),
+    );
+
+    Ok(SyncTranscriptionResult {
+        text,
+        language: recognized.language,
+        segments,
+        processing_time: recognized.processing_time,
+        audio_duration: recognized.audio_duration,
+        stats,
+        model: recognized.model,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn word(text: &str, start: f64, end: f64) -> WordTimestamp {
+        WordTimestamp {
+            word: text.to_string(),
+            start,
+            end,
+            confidence: Some(0.9),
+        }
+    }
+
+    #[test]
+    fn test_align_sequences() {
+        let reference = ["a", "b", "c", "d"];
+        let hypothesis = ["a", "x", "c", "e", "d"];
+
+        assert_eq!(
+            align_sequences(&reference, &hypothesis),
+            vec![
+                EditOp::Match {
+                    reference: 0,
+                    hypothesis: 0
+                },
+                EditOp::Substitute {
+                    reference: 1,
+                    hypothesis: 1
+                },
+                EditOp::Match {
+                    reference: 2,
+                    hypothesis: 2
+                },
+                EditOp::Insert { hypothesis: 3 },
+                EditOp::Match {
+                    reference: 3,
+                    hypothesis: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_sequences_empty() {
+        let empty: [&str; 0] = [];
+        assert_eq!(
+            align_sequences(&["a"], &empty),
+            vec![EditOp::Delete { reference: 0 }]
+        );
+        assert_eq!(
+            align_sequences(&empty, &["a"]),
+            vec![EditOp::Insert { hypothesis: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_align_sequences_long() {
+        // Large enough to be split, with one edit of each kind
+        let reference: Vec<usize> = (0..2000).collect();
+        let mut hypothesis = reference.clone();
+        hypothesis.remove(1500);
+        hypothesis.insert(700, 9999);
+        hypothesis[100] = 8888;
+
+        let ops = align_sequences(&reference, &hypothesis);
+        let edits: Vec<_> = ops
+            .iter()
+            .filter(|op| !matches!(op, EditOp::Match { .. }))
+            .collect();
+        assert_eq!(
+            edits,
+            vec![
+                &EditOp::Substitute {
+                    reference: 100,
+                    hypothesis: 100
+                },
+                &EditOp::Insert { hypothesis: 700 },
+                &EditOp::Delete { reference: 1500 },
+            ]
+        );
+        assert_eq!(ops.len(), 2001);
+    }
+
+    #[test]
+    fn test_normalize_word() {
+        assert_eq!(normalize_word("Country,"), "country");
+        assert_eq!(normalize_word("Don't!"), "don't");
+    }
+
+    #[test]
+    fn test_align_words_keeps_reference_spelling() {
+        let recognized = vec![
+            word("and", 0.0, 0.5),
+            word("so", 0.5, 1.0),
+            word("my", 1.0, 1.5),
+            word("fellow", 1.5, 2.0),
+        ];
+        let words = align_words("And so, my fellow", &recognized, 3.0);
+
+        let texts: Vec<_> = words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(texts, vec!["And", "so,", "my", "fellow"]);
+        assert_eq!(words[2].start, 1.0);
+        assert_eq!(words[2].confidence, Some(0.9));
+    }
+
+    #[test]
+    fn test_align_words_interpolates_missing() {
+        let recognized = vec![word("ask", 1.0, 2.0), word("you", 4.0, 5.0)];
+        let words = align_words("ask what can you", &recognized, 6.0);
+
+        assert_eq!((words[1].start, words[1].end), (2.0, 3.0));
+        assert_eq!((words[2].start, words[2].end), (3.0, 4.0));
+        assert_eq!(words[1].confidence, None);
+    }
+
+    #[test]
+    fn test_build_segments_splits_sentences_and_lines() {
+        let reference = "Ask not. What your\ncountry can do";
+        let words = align_words(reference, &[], 6.0);
+        let segments = build_segments(reference, &words);
+
+        let texts: Vec<_> = segments.iter().map(|s| s.text.trim()).collect();
+        assert_eq!(texts, vec!["Ask not.", "What your", "country can do"]);
+        assert_eq!(segments[0].start, 0.0);
+        assert_eq!(segments[2].end, 6.0);
+    }
+}
@@ -1,8 +1,10 @@
 //! Audio processing functionality using FFmpeg
 
 use crate::error::{Result, WhisperError};
+use crate::progress::{Progress, ProgressCallback, ProgressStage};
 use ffmpeg_next as ffmpeg;
 use futures::Stream;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -21,6 +23,52 @@ pub struct AudioData {
     pub duration: f32,
 }
 
+impl AudioData {
+    /// Extract the samples between `start` and `end` (in seconds) as a new clip
+    pub fn slice(&self, start: f64, end: f64) -> AudioData {
+        let to_index = |seconds: f64| {
+            ((seconds.max(0.0) * self.sample_rate as f64) as usize).min(self.samples.len())
+        };
+        let (from, to) = (to_index(start), to_index(end));
+        let samples = self.samples[from..to.max(from)].to_vec();
+        let duration = samples.len() as f32 / self.sample_rate as f32;
+
+        AudioData {
+            samples,
+            sample_rate: self.sample_rate,
+            duration,
+        }
+    }
+}
+
+/// Container format for exported audio clips
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipFormat {
+    /// 16-bit PCM WAV
+    #[default]
+    Wav,
+    /// Lossless FLAC
+    Flac,
+}
+
+impl ClipFormat {
+    /// File extension for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ClipFormat::Wav => "wav",
+            ClipFormat::Flac => "flac",
+        }
+    }
+
+    fn codec_id(&self) -> ffmpeg::codec::Id {
+        match self {
+            ClipFormat::Wav => ffmpeg::codec::Id::PCM_S16LE,
+            ClipFormat::Flac => ffmpeg::codec::Id::FLAC,
+        }
+    }
+}
+
 /// Audio chunk for streaming processing (10 seconds)
 #[derive(Debug, Clone)]
 pub struct AudioChunk {
@@ -84,8 +132,7 @@ pub struct AudioProcessor {}
 impl AudioProcessor {
     /// Create a new audio processor
     pub fn new() -> Result<Self> {
-        ffmpeg::init()
-            .map_err(|e| WhisperError::FFmpeg(format!("Failed to initialize FFmpeg: {}", e)))?;
+        ffmpeg::init().map_err(|e| WhisperError::ffmpeg("Failed to initialize FFmpeg", e))?;
 
         // Set FFmpeg log level to quiet to suppress output
         unsafe {
@@ -102,10 +149,24 @@ impl AudioProcessor {
         // Run FFmpeg processing in a blocking task to avoid blocking the async runtime
         task::spawn_blocking(move || {
             let mut processor = AudioProcessor::new()?;
-            processor.load_audio_sync(&path)
+            processor.load_audio_sync(&path, None)
         })
-        .await
-        .map_err(|e| WhisperError::AudioProcessing(format!("Task join error: {}", e)))?
+        .await?
+    }
+
+    /// Load an audio file, reporting how much of it has been decoded
+    pub async fn load_audio_with_progress<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        progress: ProgressCallback,
+    ) -> Result<AudioData> {
+        let path = path.as_ref().to_path_buf();
+
+        task::spawn_blocking(move || {
+            let mut processor = AudioProcessor::new()?;
+            processor.load_audio_sync(&path, Some(&progress))
+        })
+        .await?
     }
 
     /// Stream audio file as chunks for real-time processing
@@ -126,45 +187,182 @@ impl AudioProcessor {
 
             if let Err(e) = processor.stream_audio_sync(&path, tx) {
                 // Error will already be sent through channel if possible
-                warn!("Audio streaming failed: {}", e);
+                warn!("Audio streaming failed: {}", e.report());
             }
         });
 
         Ok(AudioStream::new(rx))
     }
 
+    /// Encode audio data to a WAV or FLAC file (16-bit mono)
+    pub async fn write_clip<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        audio: AudioData,
+        format: ClipFormat,
+    ) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+
+        task::spawn_blocking(move || {
+            let mut processor = AudioProcessor::new()?;
+            processor.write_clip_sync(&path, &audio, format)
+        })
+        .await?
+    }
+
+    /// Synchronous clip encoding implementation
+    fn write_clip_sync(
+        &mut self,
+        path: &Path,
+        audio: &AudioData,
+        format: ClipFormat,
+    ) -> Result<()> {
+        let sample_format = ffmpeg::format::Sample::I16(ffmpeg::format::sample::Type::Packed);
+        let time_base = (1, audio.sample_rate as i32);
+
+        let mut octx = ffmpeg::format::output(&path)
+            .map_err(|e| WhisperError::ffmpeg("Failed to create output file", e))?;
+
+        let codec = ffmpeg::encoder::find(format.codec_id()).ok_or_else(|| {
+            WhisperError::UnsupportedCodec {
+                codec: format.extension().to_string(),
+            }
+        })?;
+        let global_header = octx
+            .format()
+            .flags()
+            .contains(ffmpeg::format::flag::Flags::GLOBAL_HEADER);
+
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .audio()
+            .map_err(|e| WhisperError::ffmpeg("Failed to create audio encoder", e))?;
+        encoder.set_rate(audio.sample_rate as i32);
+        encoder.set_channel_layout(ffmpeg::channel_layout::ChannelLayout::MONO);
+        encoder.set_format(sample_format);
+        encoder.set_time_base(time_base);
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+
+        let mut encoder = encoder
+            .open_as(codec)
+            .map_err(|e| WhisperError::ffmpeg("Failed to open audio encoder", e))?;
+
+        {
+            let mut stream = octx
+                .add_stream(codec)
+                .map_err(|e| WhisperError::ffmpeg("Failed to add output stream", e))?;
+            stream.set_time_base(time_base);
+            stream.set_parameters(&encoder);
+        }
+
+        octx.write_header()
+            .map_err(|e| WhisperError::ffmpeg("Failed to write header", e))?;
+
+        // PCM encoders accept any frame size, others (FLAC) dictate one
+        let frame_size = match encoder.frame_size() {
+            0 => 4096,
+            size => size as usize,
+        };
+
+        let mut pts = 0i64;
+        for chunk in audio.samples.chunks(frame_size) {
+            let mut frame = ffmpeg::frame::Audio::new(
+                sample_format,
+                chunk.len(),
+                ffmpeg::channel_layout::ChannelLayout::MONO,
+            );
+            frame.set_rate(audio.sample_rate);
+            frame.set_pts(Some(pts));
+
+            for (dst, &src) in frame.plane_mut::<i16>(0).iter_mut().zip(chunk) {
+                *dst = (src.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            }
+
+            encoder
+                .send_frame(&frame)
+                .map_err(|e| WhisperError::ffmpeg("Failed to encode frame", e))?;
+            Self::write_encoded_packets(&mut encoder, &mut octx, time_base)?;
+
+            pts += chunk.len() as i64;
+        }
+
+        encoder
+            .send_eof()
+            .map_err(|e| WhisperError::ffmpeg("Failed to flush encoder", e))?;
+        Self::write_encoded_packets(&mut encoder, &mut octx, time_base)?;
+
+        octx.write_trailer()
+            .map_err(|e| WhisperError::ffmpeg("Failed to write trailer", e))?;
+
+        Ok(())
+    }
+
+    /// Drain encoded packets from the encoder into the output container
+    fn write_encoded_packets(
+        encoder: &mut ffmpeg::encoder::Audio,
+        octx: &mut ffmpeg::format::context::Output,
+        time_base: (i32, i32),
+    ) -> Result<()> {
+        let stream_time_base = octx
+            .stream(0)
+            .map(|stream| stream.time_base())
+            .ok_or_else(|| WhisperError::AudioProcessing("Output stream missing".to_string()))?;
+
+        let mut packet = ffmpeg::Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts(time_base, stream_time_base);
+            packet
+                .write_interleaved(octx)
+                .map_err(|e| WhisperError::ffmpeg("Failed to write packet", e))?;
+        }
+
+        Ok(())
+    }
+
     /// Synchronous audio loading implementation
-    fn load_audio_sync(&mut self, path: &Path) -> Result<AudioData> {
+    fn load_audio_sync(
+        &mut self,
+        path: &Path,
+        progress: Option<&ProgressCallback>,
+    ) -> Result<AudioData> {
         // Validate file exists
         if !path.exists() {
-            return Err(WhisperError::AudioProcessing(format!(
-                "Audio file not found: {}",
-                path.display()
-            )));
+            return Err(WhisperError::AudioNotFound {
+                path: path.to_path_buf(),
+            });
         }
 
         // Open input file
         let mut ictx = ffmpeg::format::input(&path)
-            .map_err(|e| WhisperError::FFmpeg(format!("Failed to open audio file: {}", e)))?;
+            .map_err(|e| WhisperError::ffmpeg("Failed to open audio file", e))?;
 
         // Find the audio stream
         let input = ictx
             .streams()
             .best(ffmpeg::media::Type::Audio)
-            .ok_or_else(|| WhisperError::AudioProcessing("No audio stream found".to_string()))?;
+            .ok_or_else(|| WhisperError::NoAudioStream {
+                path: path.to_path_buf(),
+            })?;
 
         let stream_index = input.index();
+        let codec_id = input.parameters().id();
+        if ffmpeg::decoder::find(codec_id).is_none() {
+            return Err(WhisperError::UnsupportedCodec {
+                codec: format!("{:?}", codec_id).to_lowercase(),
+            });
+        }
 
         // Get decoder
         let context_decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())
-            .map_err(|e| {
-                WhisperError::FFmpeg(format!("Failed to create decoder context: {}", e))
-            })?;
+            .map_err(|e| WhisperError::ffmpeg("Failed to create decoder context", e))?;
 
         let mut decoder = context_decoder
             .decoder()
             .audio()
-            .map_err(|e| WhisperError::FFmpeg(format!("Failed to get audio decoder: {}", e)))?;
+            .map_err(|e| WhisperError::ffmpeg("Failed to get audio decoder", e))?;
 
         let mut samples = Vec::new();
         let mut frame = ffmpeg::frame::Audio::empty();
@@ -176,6 +374,15 @@ impl AudioProcessor {
         let mut last_channel_layout: Option<ffmpeg::channel_layout::ChannelLayout> = None;
         let mut last_rate: Option<u32> = None;
 
+        // Progress is measured on the decoded audio, against the container
+        // duration (in AV_TIME_BASE units, microseconds). Unknown durations
+        // only get the final update.
+        let total_duration = ictx.duration() as f64 / 1_000_000.0;
+        let mut reported = 0.0f32;
+        if let Some(progress) = progress {
+            progress(Progress::new(ProgressStage::Decoding, 0.0));
+        }
+
         // Process packets with error resilience
         for (stream, packet) in ictx.packets() {
             if stream.index() == stream_index {
@@ -194,6 +401,16 @@ impl AudioProcessor {
                                 &mut last_rate,
                             )?;
                         }
+
+                        if let Some(progress) = progress.filter(|_| total_duration > 0.0) {
+                            let decoded = samples.len() as f64 / 16000.0;
+                            let fraction = (decoded / total_duration).min(1.0) as f32;
+                            // Report whole percents only
+                            if fraction - reported >= 0.01 {
+                                reported = fraction;
+                                progress(Progress::new(ProgressStage::Decoding, fraction));
+                            }
+                        }
                     }
                     Err(ffmpeg_next::Error::InvalidData) => {
                         // Log the error but continue processing - skip corrupted packets
@@ -201,10 +418,7 @@ impl AudioProcessor {
                         continue;
                     }
                     Err(e) => {
-                        return Err(WhisperError::FFmpeg(format!(
-                            "Failed to send packet to decoder: {}",
-                            e
-                        )))
+                        return Err(WhisperError::ffmpeg("Failed to send packet to decoder", e))
                     }
                 }
             }
@@ -238,6 +452,10 @@ impl AudioProcessor {
             ));
         }
 
+        if let Some(progress) = progress {
+            progress(Progress::new(ProgressStage::Decoding, 1.0));
+        }
+
         let duration = samples.len() as f32 / 16000.0;
 
         Ok(AudioData {
@@ -255,34 +473,41 @@ impl AudioProcessor {
     ) -> Result<()> {
         // Validate file exists
         if !path.exists() {
-            let error =
-                WhisperError::AudioProcessing(format!("Audio file not found: {}", path.display()));
+            let error = WhisperError::AudioNotFound {
+                path: path.to_path_buf(),
+            };
             let _ = tx.send(Err(error.clone()));
             return Err(error);
         }
 
         // Open input file
         let mut ictx = ffmpeg::format::input(&path)
-            .map_err(|e| WhisperError::FFmpeg(format!("Failed to open audio file: {}", e)))?;
+            .map_err(|e| WhisperError::ffmpeg("Failed to open audio file", e))?;
 
         // Find the audio stream
         let input = ictx
             .streams()
             .best(ffmpeg::media::Type::Audio)
-            .ok_or_else(|| WhisperError::AudioProcessing("No audio stream found".to_string()))?;
+            .ok_or_else(|| WhisperError::NoAudioStream {
+                path: path.to_path_buf(),
+            })?;
 
         let stream_index = input.index();
+        let codec_id = input.parameters().id();
+        if ffmpeg::decoder::find(codec_id).is_none() {
+            return Err(WhisperError::UnsupportedCodec {
+                codec: format!("{:?}", codec_id).to_lowercase(),
+            });
+        }
 
         // Get decoder
         let context_decoder = ffmpeg::codec::context::Context::from_parameters(input.parameters())
-            .map_err(|e| {
-                WhisperError::FFmpeg(format!("Failed to create decoder context: {}", e))
-            })?;
+            .map_err(|e| WhisperError::ffmpeg("Failed to create decoder context", e))?;
 
         let mut decoder = context_decoder
             .decoder()
             .audio()
-            .map_err(|e| WhisperError::FFmpeg(format!("Failed to get audio decoder: {}", e)))?;
+            .map_err(|e| WhisperError::ffmpeg("Failed to get audio decoder", e))?;
 
         let mut chunk_samples = Vec::new();
         let mut frame = ffmpeg::frame::Audio::empty();
@@ -313,7 +538,7 @@ impl AudioProcessor {
                                 &mut last_channel_layout,
                                 &mut last_rate,
                             ) {
-                                warn!("Failed to process frame, skipping: {}", e);
+                                warn!("Failed to process frame, skipping: {}", e.report());
                                 continue;
                             }
 
@@ -345,10 +570,7 @@ impl AudioProcessor {
                         continue;
                     }
                     Err(e) => {
-                        let error = WhisperError::FFmpeg(format!(
-                            "Failed to send packet to decoder: {}",
-                            e
-                        ));
+                        let error = WhisperError::ffmpeg("Failed to send packet to decoder", e);
                         let _ = tx.send(Err(error.clone()));
                         return Err(error);
                     }
@@ -370,7 +592,7 @@ impl AudioProcessor {
                         &mut last_channel_layout,
                         &mut last_rate,
                     ) {
-                        warn!("Failed to process final frame, skipping: {}", e);
+                        warn!("Failed to process final frame, skipping: {}", e.report());
                         continue;
                     }
                     chunk_samples.extend_from_slice(&frame_samples);
@@ -452,9 +674,7 @@ impl AudioProcessor {
                         ffmpeg::channel_layout::ChannelLayout::MONO,
                         16000,
                     )
-                    .map_err(|e| {
-                        WhisperError::FFmpeg(format!("Failed to create resampler: {}", e))
-                    })?,
+                    .map_err(|e| WhisperError::ffmpeg("Failed to create resampler", e))?,
                 );
 
                 // Update our tracking variables
@@ -613,9 +833,7 @@ impl AudioProcessor {
                         ffmpeg::channel_layout::ChannelLayout::MONO,
                         16000,
                     )
-                    .map_err(|e| {
-                        WhisperError::FFmpeg(format!("Failed to create resampler: {}", e))
-                    })?,
+                    .map_err(|e| WhisperError::ffmpeg("Failed to create resampler", e))?,
                 );
 
                 // Update our tracking variables
@@ -718,3 +936,70 @@ impl AudioProcessor {
         Ok(())
     }
 }
+
+/// Decoder for raw Opus packets, producing 16 kHz mono samples
+///
+/// Each call to [`OpusDecoder::decode`] takes a single Opus packet, as sent by
+/// WebRTC or `MediaRecorder`, without an Ogg or WebM container around it.
+pub struct OpusDecoder {
+    decoder: ffmpeg::decoder::Audio,
+    frame: ffmpeg::frame::Audio,
+    resampled: ffmpeg::frame::Audio,
+    resampler: Option<ffmpeg::software::resampling::context::Context>,
+    last_format: Option<ffmpeg::format::Sample>,
+    last_channel_layout: Option<ffmpeg::channel_layout::ChannelLayout>,
+    last_rate: Option<u32>,
+}
+
+// SAFETY: the FFmpeg contexts are owned by the decoder and only used through
+// `&mut self`, so moving the decoder to another thread is sound.
+unsafe impl Send for OpusDecoder {}
+
+impl OpusDecoder {
+    /// Create a decoder
+    pub fn new() -> Result<Self> {
+        AudioProcessor::new()?;
+
+        let codec = ffmpeg::decoder::find(ffmpeg::codec::Id::OPUS).ok_or_else(|| {
+            WhisperError::UnsupportedCodec {
+                codec: "opus".to_string(),
+            }
+        })?;
+        let decoder = ffmpeg::codec::Context::new_with_codec(codec)
+            .decoder()
+            .audio()
+            .map_err(|e| WhisperError::ffmpeg("Failed to open Opus decoder", e))?;
+
+        Ok(Self {
+            decoder,
+            frame: ffmpeg::frame::Audio::empty(),
+            resampled: ffmpeg::frame::Audio::empty(),
+            resampler: None,
+            last_format: None,
+            last_channel_layout: None,
+            last_rate: None,
+        })
+    }
+
+    /// Decode one packet
+    pub fn decode(&mut self, packet: &[u8]) -> Result<Vec<f32>> {
+        self.decoder
+            .send_packet(&ffmpeg::Packet::copy(packet))
+            .map_err(|e| WhisperError::ffmpeg("Failed to decode Opus packet", e))?;
+
+        let mut samples = Vec::new();
+        while self.decoder.receive_frame(&mut self.frame).is_ok() {
+            AudioProcessor::process_audio_frame(
+                &self.frame,
+                &mut samples,
+                &mut self.resampled,
+                &mut self.resampler,
+                &mut self.last_format,
+                &mut self.last_channel_layout,
+                &mut self.last_rate,
+            )?;
+        }
+
+        Ok(samples)
+    }
+}
@@ -0,0 +1,229 @@
+//! Benchmarks of model loading and inference speed
+//!
+//! Each run loads a model with a given thread count and GPU setting, times
+//! the steps of one 30 second window (mel spectrogram, encoder, decoder) on a
+//! fresh state, then transcribes the whole benchmark audio for the real-time
+//! factor. The window timings follow whisper.cpp's own `bench` tool.
+
+use crate::audio::AudioData;
+use crate::dev::SystemInfo;
+use crate::error::{Result, WhisperError};
+use crate::whisper::{
+    load_model, sync::SyncWhisperTranscriber, SharedModel, TranscriptionStats,
+    WhisperTranscriber as _,
+};
+use crate::{ModelManager, TranscriptionConfig};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::task;
+
+/// Samples in one whisper window
+const WINDOW_SAMPLES: usize = 30 * 16000;
+
+/// Tokens decoded one at a time to time the decoder
+const DECODE_STEPS: usize = 32;
+
+/// Tokens decoded at once to time batch decoding (as for prompts)
+const BATCH_TOKENS: usize = 64;
+
+/// Timings of one model, thread count and GPU setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    /// Model file
+    pub model: PathBuf,
+
+    /// Threads used for inference
+    pub threads: usize,
+
+    /// Whether GPU acceleration was requested
+    pub use_gpu: bool,
+
+    /// Model load time in milliseconds
+    pub load_ms: f64,
+
+    /// Mel spectrogram time for a 30 second window, in milliseconds
+    pub mel_ms: f64,
+
+    /// Encoder time for a 30 second window, in milliseconds
+    pub encode_ms: f64,
+
+    /// Decoder time per token when decoding one token at a time, in milliseconds
+    pub decode_ms: f64,
+
+    /// Decoder time per token when decoding a batch of tokens, in milliseconds
+    pub batch_decode_ms: f64,
+
+    /// Statistics of the full transcription of the benchmark audio
+    pub stats: TranscriptionStats,
+}
+
+impl BenchResult {
+    /// Time spent on one 30 second window with a typical number of decoded tokens
+    pub fn window_ms(&self, tokens: usize) -> f64 {
+        self.mel_ms + self.encode_ms + self.decode_ms * tokens as f64
+    }
+}
+
+/// Results of a benchmark with the machine they were measured on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// Hardware and acceleration support
+    pub system: SystemInfo,
+
+    /// Length of the transcribed audio in seconds
+    pub audio_duration: f32,
+
+    /// Whether the audio was generated rather than read from a file
+    pub synthetic: bool,
+
+    pub results: Vec<BenchResult>,
+}
+
+/// Generate `seconds` of a speech-like modulated tone
+///
+/// Real recordings give more representative real-time factors, as decoding
+/// time depends on what is said.
+pub fn synthetic_audio(seconds: f32) -> AudioData {
+    let count = (seconds.max(1.0) * 16000.0) as usize;
+    let samples = (0..count)
+        .map(|i| {
+            let t = i as f32 / 16000.0;
+            // A 4 Hz envelope, roughly the rate of syllables
+            let envelope = 0.5 + 0.5 * (t * 4.0 * std::f32::consts::TAU).sin();
+            let voice = (t * 220.0 * std::f32::consts::TAU).sin()
+                + 0.5 * (t * 440.0 * std::f32::consts::TAU).sin();
+            0.2 * envelope * voice
+        })
+        .collect();
+
+    AudioData {
+        samples,
+        sample_rate: 16000,
+        duration: count as f32 / 16000.0,
+    }
+}
+
+/// Benchmark the model of `config` with its thread count and GPU setting
+pub async fn run(config: &TranscriptionConfig, audio: &AudioData) -> Result<BenchResult> {
+    let model = config
+        .model_path
+        .clone()
+        .ok_or_else(|| WhisperError::Configuration("No model to benchmark".to_string()))?;
+    let threads = config.num_threads.unwrap_or(4).max(1);
+
+    let start = Instant::now();
+    let context = Arc::new(load_model(config, &ModelManager::new()?).await?);
+    let load_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let window = {
+        let mut window = audio.samples[..audio.samples.len().min(WINDOW_SAMPLES)].to_vec();
+        window.resize(WINDOW_SAMPLES, 0.0);
+        window
+    };
+    let timings = {
+        let context = context.clone();
+        task::spawn_blocking(move || time_window(&context, &window, threads)).await??
+    };
+
+    let model_handle = SharedModel { context };
+    let result =
+        SyncWhisperTranscriber::with_model(&model_handle, config.clone().with_threads(threads))
+            .transcribe(audio.clone())
+            .await?;
+
+    Ok(BenchResult {
+        model,
+        threads,
+        use_gpu: config.use_gpu,
+        load_ms,
+        mel_ms: timings.mel_ms,
+        encode_ms: timings.encode_ms,
+        decode_ms: timings.decode_ms,
+        batch_decode_ms: timings.batch_decode_ms,
+        stats: result.stats,
+    })
+}
+
+struct WindowTimings {
+    mel_ms: f64,
+    encode_ms: f64,
+    decode_ms: f64,
+    batch_decode_ms: f64,
+}
+
+/// Time each step of one window on a fresh state
+fn time_window(
+    context: &whisper_rs::WhisperContext,
+    window: &[f32],
+    threads: usize,
+) -> Result<WindowTimings> {
+    let mut state = context
+        .create_state()
+        .map_err(|e| WhisperError::whisper("Failed to create state", e))?;
+
+    let start = Instant::now();
+    state
+        .pcm_to_mel(window, threads)
+        .map_err(|e| WhisperError::whisper("Failed to compute mel spectrogram", e))?;
+    let mel_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let start = Instant::now();
+    state
+        .encode(0, threads)
+        .map_err(|e| WhisperError::whisper("Failed to run encoder", e))?;
+    let encode_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    // The token values do not matter for timing
+    let start = Instant::now();
+    for n_past in 0..DECODE_STEPS {
+        state
+            .decode(&[0], n_past, threads)
+            .map_err(|e| WhisperError::whisper("Failed to run decoder", e))?;
+    }
+    let decode_ms = start.elapsed().as_secs_f64() * 1000.0 / DECODE_STEPS as f64;
+
+    let start = Instant::now();
+    state
+        .decode(&[0; BATCH_TOKENS], 0, threads)
+        .map_err(|e| WhisperError::whisper("Failed to run decoder", e))?;
+    let batch_decode_ms = start.elapsed().as_secs_f64() * 1000.0 / BATCH_TOKENS as f64;
+
+    Ok(WindowTimings {
+        mel_ms,
+        encode_ms,
+        decode_ms,
+        batch_decode_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_audio() {
+        let audio = synthetic_audio(2.5);
+        assert_eq!(audio.samples.len(), 40000);
+        assert_eq!(audio.duration, 2.5);
+        assert!(audio.samples.iter().all(|s| s.abs() <= 0.3));
+        assert!(audio.samples.iter().any(|s| s.abs() > 0.1));
+    }
+
+    #[test]
+    fn test_window_time() {
+        let result = BenchResult {
+            model: PathBuf::from("ggml-base.bin"),
+            threads: 4,
+            use_gpu: false,
+            load_ms: 120.0,
+            mel_ms: 10.0,
+            encode_ms: 500.0,
+            decode_ms: 2.0,
+            batch_decode_ms: 0.5,
+            stats: TranscriptionStats::new(1.0, 30.0, 3, 60),
+        };
+        assert_eq!(result.window_ms(100), 710.0);
+    }
+}
@@ -0,0 +1,474 @@
+//! Cache of transcription results
+//!
+//! When [`TranscriptionConfig::cache`] is set, results are stored as JSON files
+//! in the purr cache directory and reused when the same audio is transcribed
+//! again with the same model and settings. The key hashes the audio file bytes,
+//! the model file and the config fields that change the result. Model hashes
+//! are remembered by path, size and modification time so that large models are
+//! hashed only once.
+
+use crate::error::{Result, WhisperError};
+use crate::hallucination::HallucinationFilter;
+use crate::model::download::hash_file;
+use crate::state_file::write_atomic;
+use crate::text::TextProcessing;
+use crate::whisper::{ChunkStatus, StreamingChunk, SyncTranscriptionResult, TranscriptionSegment};
+use crate::{ModelManager, TranscriptionConfig};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, warn};
+
+/// Bumped when cached results are no longer compatible
+const CACHE_VERSION: u32 = 1;
+
+/// Name of the model hash store in the cache directory
+const MODEL_HASHES_FILE: &str = "models.json";
+
+/// How the cached result was produced
+///
+/// Streaming results only have one segment per chunk, so they are kept apart
+/// from full transcriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    Sync,
+    Streaming,
+}
+
+/// Config fields and file hashes that make up a cache key
+#[derive(Serialize)]
+struct CacheKey<'a> {
+    version: u32,
+    mode: CacheMode,
+    audio: &'a str,
+    model: &'a str,
+    language: Option<&'a str>,
+    translate: bool,
+    sample_rate: u32,
+    max_duration: Option<f32>,
+    temperature: f32,
+    beam_size: Option<usize>,
+    prompt: Option<&'a str>,
+    word_timestamps: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    full_details: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    speaker_turns: bool,
+    processors: usize,
+    hallucinations: Option<&'a HallucinationFilter>,
+    text_processing: Option<&'a TextProcessing>,
+}
+
+/// Hash of a model file and the file metadata it was computed for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ModelHash {
+    size: u64,
+    modified: u64,
+    sha256: String,
+}
+
+/// Transcription results stored on disk
+#[derive(Debug, Clone)]
+pub struct TranscriptCache {
+    dir: PathBuf,
+}
+
+impl TranscriptCache {
+    /// Open the cache in the purr cache directory
+    pub fn new() -> Result<Self> {
+        let project_dirs = ProjectDirs::from("dev.gaetans", "", "purr").ok_or_else(|| {
+            WhisperError::Configuration("Failed to get XDG directories".to_string())
+        })?;
+
+        Ok(Self::with_dir(project_dirs.cache_dir().join("transcripts")))
+    }
+
+    /// Open a cache stored in a custom directory
+    pub fn with_dir<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Directory of the cache
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Key of the transcription of `audio_path` with `config`
+    pub async fn key(
+        &self,
+        audio_path: &Path,
+        config: &TranscriptionConfig,
+        mode: CacheMode,
+    ) -> Result<String> {
+        let model_path = match config.model_path {
+            Some(ref path) => path.clone(),
+            None => ModelManager::new()?.find_default_model().await?,
+        };
+
+        let audio_path = audio_path.to_path_buf();
+        let audio =
+            tokio::task::spawn_blocking(move || hash_file::<sha2::Sha256>(&audio_path)).await??;
+        let model = self.model_hash(&model_path).await?;
+
+        let key = CacheKey {
+            version: CACHE_VERSION,
+            mode,
+            audio: &audio,
+            model: &model,
+            language: config.language.as_deref(),
+            translate: config.translate,
+            sample_rate: config.sample_rate,
+            max_duration: config.max_duration,
+            temperature: config.temperature,
+            beam_size: config.beam_size,
+            prompt: config.prompt.as_deref(),
+            word_timestamps: config.output_format.word_timestamps,
+            full_details: config.output_format.full_details,
+            speaker_turns: config.speaker_turns,
+            processors: config.processors.max(1),
+            // Keep keys of unfiltered results unchanged
+            hallucinations: Some(&config.hallucinations).filter(|h| h.is_enabled()),
+            text_processing: Some(&config.text_processing).filter(|t| t.is_enabled()),
+        };
+        let key = serde_json::to_vec(&key)
+            .map_err(|e| WhisperError::Unknown(format!("Failed to serialize cache key: {}", e)))?;
+
+        Ok(hex(&sha2::Sha256::digest(key)))
+    }
+
+    /// Cached result for a key
+    ///
+    /// Unreadable entries are treated as missing.
+    pub fn get(&self, key: &str) -> Option<SyncTranscriptionResult> {
+        let path = self.entry_path(key);
+        let contents = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                warn!("Ignoring corrupt cache entry {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Store a result
+    pub fn put(&self, key: &str, result: &SyncTranscriptionResult) -> Result<()> {
+        let contents = serde_json::to_string(result).map_err(|e| {
+            WhisperError::Unknown(format!("Failed to serialize transcription: {}", e))
+        })?;
+        write_atomic(&self.entry_path(key), contents.as_bytes())
+    }
+
+    /// Remove every cached result, returning the number of entries and bytes freed
+    pub fn clear(&self) -> Result<(usize, u64)> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        let mut freed = 0;
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            freed += entry.metadata()?.len();
+            std::fs::remove_file(entry.path())?;
+            if entry.file_name() != MODEL_HASHES_FILE {
+                removed += 1;
+            }
+        }
+
+        Ok((removed, freed))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// SHA-256 of a model file, reusing the stored hash while the file is unchanged
+    async fn model_hash(&self, path: &Path) -> Result<String> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| WhisperError::io(format!("Failed to read model {}", path.display()), e))?;
+        let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let store_path = self.dir.join(MODEL_HASHES_FILE);
+        let mut hashes: BTreeMap<String, ModelHash> = std::fs::read_to_string(&store_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        let key = path.to_string_lossy().into_owned();
+        if let Some(hash) = hashes.get(&key) {
+            if hash.size == size && hash.modified == modified {
+                return Ok(hash.sha256.clone());
+            }
+        }
+
+        debug!("Hashing model {} for the transcript cache", path.display());
+        let model_path = path.to_path_buf();
+        let sha256 =
+            tokio::task::spawn_blocking(move || hash_file::<sha2::Sha256>(&model_path)).await??;
+
+        hashes.insert(
+            key,
+            ModelHash {
+                size,
+                modified,
+                sha256: sha256.clone(),
+            },
+        );
+        // Remembering the hash is only an optimization
+        match serde_json::to_string_pretty(&hashes) {
+            Ok(contents) => {
+                if let Err(e) = write_atomic(&store_path, contents.as_bytes()) {
+                    debug!("Failed to store model hash: {}", e);
+                }
+            }
+            Err(e) => debug!("Failed to serialize model hashes: {}", e),
+        }
+
+        Ok(sha256)
+    }
+}
+
+/// Open the cache and compute the key of a transcription
+///
+/// The cache is best effort: failures are logged and disable it for this
+/// transcription.
+pub(crate) async fn prepare(
+    audio_path: &Path,
+    config: &TranscriptionConfig,
+    mode: CacheMode,
+) -> Option<(TranscriptCache, String)> {
+    let cache = match TranscriptCache::new() {
+        Ok(cache) => cache,
+        Err(e) => {
+            warn!("Transcript cache disabled: {}", e.report());
+            return None;
+        }
+    };
+
+    match cache.key(audio_path, config, mode).await {
+        Ok(key) => Some((cache, key)),
+        Err(e) => {
+            warn!("Transcript cache disabled: {}", e.report());
+            None
+        }
+    }
+}
+
+/// Turn a cached result back into streaming chunks, one per segment
+pub fn result_to_chunks(result: &SyncTranscriptionResult) -> Vec<StreamingChunk> {
+    if result.segments.is_empty() {
+        return vec![StreamingChunk {
+            text: String::new(),
+            start: 0.0,
+            end: result.audio_duration as f64,
+            is_final: true,
+            chunk_index: 0,
+            final_stats: Some(result.stats.clone()),
+            status: ChunkStatus::Ok,
+            error: None,
+        }];
+    }
+
+    let last = result.segments.len() - 1;
+    result
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(index, segment)| StreamingChunk {
+            text: segment.text.clone(),
+            start: segment.start,
+            end: segment.end,
+            is_final: index == last,
+            chunk_index: index,
+            final_stats: (index == last).then(|| result.stats.clone()),
+            status: ChunkStatus::Ok,
+            error: None,
+        })
+        .collect()
+}
+
+/// Build a result from the chunks of a complete streaming transcription
+///
+/// Returns `None` if the last chunk, which carries the statistics, is missing
+/// or if any chunk failed, as the transcript would have gaps.
+pub fn chunks_to_result(
+    chunks: &[StreamingChunk],
+    language: Option<String>,
+) -> Option<SyncTranscriptionResult> {
+    if chunks.iter().any(StreamingChunk::is_failed) {
+        return None;
+    }
+    let stats = chunks.last()?.final_stats.clone()?;
+    let text = chunks
+        .iter()
+        .map(|chunk| chunk.text.as_str())
+        .collect::<String>()
+        .trim()
+        .to_string();
+    let segments = chunks
+        .iter()
+        .filter(|chunk| !chunk.text.trim().is_empty())
+        .map(|chunk| TranscriptionSegment {
+            text: chunk.text.clone(),
+            start: chunk.start,
+            end: chunk.end,
+            confidence: None,
+            words: None,
+            no_speech_prob: None,
+            hallucination: None,
+            speaker_turn_next: false,
+            tokens: None,
+        })
+        .collect();
+
+    Some(SyncTranscriptionResult {
+        text,
+        language,
+        segments,
+        processing_time: stats.processing_time,
+        audio_duration: stats.audio_duration,
+        stats,
+        model: None,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whisper::TranscriptionStats;
+
+    fn result() -> SyncTranscriptionResult {
+        let segments = vec![
+            TranscriptionSegment {
+                text: " Hello".to_string(),
+                start: 0.0,
+                end: 1.0,
+                confidence: None,
+                words: None,
+                no_speech_prob: None,
+                hallucination: None,
+                speaker_turn_next: false,
+                tokens: None,
+            },
+            TranscriptionSegment {
+                text: " world.".to_string(),
+                start: 1.0,
+                end: 2.0,
+                confidence: None,
+                words: None,
+                no_speech_prob: None,
+                hallucination: None,
+                speaker_turn_next: false,
+                tokens: None,
+            },
+        ];
+        let stats = TranscriptionStats::new(0.5, 2.0, 2, 2);
+        SyncTranscriptionResult {
+            text: "Hello world.".to_string(),
+            language: Some("en".to_string()),
+            segments,
+            processing_time: 0.5,
+            audio_duration: 2.0,
+            stats,
+            model: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_depends_on_audio_model_and_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TranscriptCache::with_dir(dir.path().join("cache"));
+        let audio = dir.path().join("audio.wav");
+        let model = dir.path().join("model.bin");
+        std::fs::write(&audio, b"audio").unwrap();
+        std::fs::write(&model, b"model").unwrap();
+
+        let config = TranscriptionConfig::new().with_model_path(&model);
+        let key = cache.key(&audio, &config, CacheMode::Sync).await.unwrap();
+
+        // Stable, and unaffected by settings that do not change the result
+        let same = config.clone().with_threads(3).with_gpu(false);
+        assert_eq!(
+            cache.key(&audio, &same, CacheMode::Sync).await.unwrap(),
+            key
+        );
+
+        let other_config = config.clone().with_language("fr");
+        let streaming = cache.key(&audio, &config, CacheMode::Streaming).await;
+        assert_ne!(
+            cache
+                .key(&audio, &other_config, CacheMode::Sync)
+                .await
+                .unwrap(),
+            key
+        );
+        assert_ne!(streaming.unwrap(), key);
+
+        std::fs::write(&audio, b"other audio").unwrap();
+        assert_ne!(
+            cache.key(&audio, &config, CacheMode::Sync).await.unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn test_put_get_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TranscriptCache::with_dir(dir.path());
+        assert!(cache.get("abc").is_none());
+
+        cache.put("abc", &result()).unwrap();
+        let cached = cache.get("abc").unwrap();
+        assert_eq!(cached.text, "Hello world.");
+        assert_eq!(cached.segments.len(), 2);
+
+        let (removed, freed) = cache.clear().unwrap();
+        assert_eq!(removed, 1);
+        assert!(freed > 0);
+        assert!(cache.get("abc").is_none());
+    }
+
+    #[test]
+    fn test_chunks_roundtrip() {
+        let chunks = result_to_chunks(&result());
+        assert_eq!(chunks.len(), 2);
+        assert!(!chunks[0].is_final && chunks[0].final_stats.is_none());
+        assert!(chunks[1].is_final && chunks[1].final_stats.is_some());
+
+        let rebuilt = chunks_to_result(&chunks, Some("en".to_string())).unwrap();
+        assert_eq!(rebuilt.text, "Hello world.");
+        assert_eq!(rebuilt.segments.len(), 2);
+        assert_eq!(rebuilt.stats.word_count, 2);
+
+        // Incomplete streams are not cached
+        assert!(chunks_to_result(&chunks[..1], None).is_none());
+
+        // Nor are streams with gaps
+        let mut failed = chunks.clone();
+        failed[0].text.clear();
+        failed[0].status = ChunkStatus::Failed;
+        failed[0].error = Some("Transcription failed for chunk 0".to_string());
+        assert!(failed[0].is_failed());
+        assert!(chunks_to_result(&failed, None).is_none());
+    }
+}
@@ -1,56 +1,87 @@
 //! Configuration options for transcription
 
+use crate::document::ParagraphOptions;
+use crate::hallucination::{HallucinationAction, HallucinationFilter};
+use crate::text::TextProcessing;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 /// Configuration for transcription operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TranscriptionConfig {
     /// Path to the Whisper model file
     pub model_path: Option<PathBuf>,
-    
+
     /// Language code (e.g., "en", "es", "fr")
     pub language: Option<String>,
-    
+
     /// Translate to English (like whisper.cpp --translate flag)
     pub translate: bool,
-    
+
     /// Use GPU acceleration if available
     pub use_gpu: bool,
-    
+
     /// Number of threads to use
     pub num_threads: Option<usize>,
-    
+
+    /// Number of regions of a long file transcribed in parallel, each with
+    /// its own state and `num_threads` threads
+    pub processors: usize,
+
     /// Audio sample rate to convert to
     pub sample_rate: u32,
-    
+
     /// Maximum audio duration in seconds
     pub max_duration: Option<f32>,
-    
+
     /// Temperature for sampling (0.0 = deterministic)
     pub temperature: f32,
-    
+
     /// Beam size for beam search
     pub beam_size: Option<usize>,
-    
+
+    /// Initial prompt to guide the style and vocabulary of the transcription
+    pub prompt: Option<String>,
+
+    /// Mark speaker turns in segments (needs a TinyDiarize model)
+    pub speaker_turns: bool,
+
+    /// Flag or drop repetition loops and text made up over silence
+    pub hallucinations: HallucinationFilter,
+
+    /// Trimming, inverse text normalization and masking of the transcript text
+    pub text_processing: TextProcessing,
+
+    /// Reuse the cached result of an identical transcription
+    pub cache: bool,
+
     /// Output format options
     pub output_format: OutputFormat,
-    
+
     /// Enable verbose debug output
     pub verbose: bool,
 }
 
 /// Output format options
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OutputFormat {
     /// Include timestamps in the output
     pub include_timestamps: bool,
-    
+
     /// Include word-level timestamps
     pub word_timestamps: bool,
-    
+
     /// Include confidence scores
     pub include_confidence: bool,
+
+    /// Grouping of sentences into paragraphs in Markdown and HTML transcripts
+    pub paragraphs: ParagraphOptions,
+
+    /// Keep every token and the model hyperparameters, as in whisper.cpp's
+    /// full JSON output
+    pub full_details: bool,
 }
 
 impl Default for TranscriptionConfig {
@@ -61,10 +92,16 @@ impl Default for TranscriptionConfig {
             translate: false,
             use_gpu: true,
             num_threads: None, // Use system default
+            processors: 1,
             sample_rate: 16000, // Whisper's preferred sample rate
             max_duration: None,
             temperature: 0.0,
             beam_size: None,
+            prompt: None,
+            speaker_turns: false,
+            hallucinations: HallucinationFilter::default(),
+            text_processing: TextProcessing::default(),
+            cache: false,
             output_format: OutputFormat::default(),
             verbose: false,
         }
@@ -77,6 +114,8 @@ impl Default for OutputFormat {
             include_timestamps: true,
             word_timestamps: false,
             include_confidence: false,
+            paragraphs: ParagraphOptions::default(),
+            full_details: false,
         }
     }
 }
@@ -86,46 +125,76 @@ impl TranscriptionConfig {
     pub fn new() -> Self {
         Self::default()
     }
-    
+
     /// Set the model path
     pub fn with_model_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
         self.model_path = Some(path.into());
         self
     }
-    
+
     /// Set the language
     pub fn with_language<S: Into<String>>(mut self, language: S) -> Self {
         self.language = Some(language.into());
         self
     }
-    
+
     /// Enable or disable GPU acceleration
     pub fn with_gpu(mut self, use_gpu: bool) -> Self {
         self.use_gpu = use_gpu;
         self
     }
-    
+
     /// Set the number of threads
     pub fn with_threads(mut self, threads: usize) -> Self {
         self.num_threads = Some(threads);
         self
     }
-    
+
+    /// Set the number of regions transcribed in parallel
+    pub fn with_processors(mut self, processors: usize) -> Self {
+        self.processors = processors.max(1);
+        self
+    }
+
+    /// Set what to do with hallucinated segments
+    pub fn with_hallucinations(mut self, action: HallucinationAction) -> Self {
+        self.hallucinations.action = action;
+        self
+    }
+
+    /// Set how the transcript text is post-processed
+    pub fn with_text_processing(mut self, text_processing: TextProcessing) -> Self {
+        self.text_processing = text_processing;
+        self
+    }
+
     /// Set the sample rate
     pub fn with_sample_rate(mut self, rate: u32) -> Self {
         self.sample_rate = rate;
         self
     }
-    
+
     /// Enable or disable verbose output
     pub fn with_verbose(mut self, verbose: bool) -> Self {
         self.verbose = verbose;
         self
     }
-    
+
     /// Enable or disable translation to English
     pub fn with_translate(mut self, translate: bool) -> Self {
         self.translate = translate;
         self
     }
+
+    /// Set the initial prompt
+    pub fn with_prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    /// Enable or disable the transcript cache
+    pub fn with_cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
 }
@@ -0,0 +1,301 @@
+//! Configuration files and profiles
+//!
+//! Transcription settings can be stored in TOML files instead of being passed
+//! on the command line every time. Two files are read, the later one taking
+//! precedence:
+//!
+//! 1. `config.toml` in the purr config directory (user settings)
+//! 2. `purr.toml` in the current directory or its closest parent (project settings)
+//!
+//! Top-level keys are [`TranscriptionConfig`] fields. Named profiles live in
+//! `[profiles.<name>]` tables and are applied on top of them when selected:
+//!
+//! ```toml
+//! language = "en"
+//! num_threads = 8
+//!
+//! [output_format]
+//! include_timestamps = true
+//!
+//! [profiles.podcast]
+//! model = "large-v3-turbo"
+//! beam_size = 5
+//! ```
+//!
+//! `model` (or `model_path`) accepts a model name or a path. Relative paths are
+//! resolved against the directory of the file that sets them.
+
+use crate::config::TranscriptionConfig;
+use crate::error::{Result, WhisperError};
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+
+/// Name of the user config file in the config directory
+pub const USER_CONFIG_FILE: &str = "config.toml";
+
+/// Name of the project config file
+pub const PROJECT_CONFIG_FILE: &str = "purr.toml";
+
+/// Table holding the named profiles
+const PROFILES_KEY: &str = "profiles";
+
+/// Merged contents of the configuration files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    values: toml::Table,
+    sources: Vec<PathBuf>,
+}
+
+impl ConfigFile {
+    /// Load the user config and the project config of the current directory
+    pub fn load() -> Result<Self> {
+        let project = Self::find_project_config(&std::env::current_dir()?);
+        let paths: Vec<PathBuf> = Self::user_config_path()
+            .into_iter()
+            .filter(|path| path.exists())
+            .chain(project)
+            .collect();
+
+        Self::from_files(&paths)
+    }
+
+    /// Load and merge config files, later files overriding earlier ones
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let mut config = Self::default();
+        for path in paths {
+            let path = path.as_ref();
+            let values = read_file(path)?;
+            merge(&mut config.values, values);
+            config.sources.push(path.to_path_buf());
+        }
+        Ok(config)
+    }
+
+    /// Location of the user config file
+    pub fn user_config_path() -> Option<PathBuf> {
+        ProjectDirs::from("dev.gaetans", "", "purr")
+            .map(|dirs| dirs.config_dir().join(USER_CONFIG_FILE))
+    }
+
+    /// Find `purr.toml` in `dir` or its closest parent
+    pub fn find_project_config(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Files the configuration was read from, lowest precedence first
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Names of the defined profiles
+    pub fn profiles(&self) -> Vec<String> {
+        match self.values.get(PROFILES_KEY) {
+            Some(toml::Value::Table(profiles)) => profiles.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Apply the file values, then the given profile, on top of `base`
+    pub fn resolve(
+        &self,
+        base: TranscriptionConfig,
+        profile: Option<&str>,
+    ) -> Result<TranscriptionConfig> {
+        let mut values = match toml::Value::try_from(base) {
+            Ok(toml::Value::Table(values)) => values,
+            Ok(_) => toml::Table::new(),
+            Err(e) => {
+                return Err(WhisperError::Configuration(format!(
+                    "Failed to serialize configuration: {}",
+                    e
+                )))
+            }
+        };
+
+        let mut file_values = self.values.clone();
+        let profiles = file_values.remove(PROFILES_KEY);
+        merge(&mut values, file_values);
+
+        if let Some(name) = profile {
+            let profile = profiles
+                .as_ref()
+                .and_then(|profiles| profiles.get(name))
+                .and_then(|profile| profile.as_table())
+                .ok_or_else(|| {
+                    let available = self.profiles();
+                    WhisperError::Configuration(format!(
+                        "Unknown profile '{}' (available: {})",
+                        name,
+                        if available.is_empty() {
+                            "none".to_string()
+                        } else {
+                            available.join(", ")
+                        }
+                    ))
+                })?;
+            merge(&mut values, profile.clone());
+        }
+
+        toml::Value::Table(values)
+            .try_into()
+            .map_err(|e| WhisperError::Configuration(format!("Invalid configuration: {}", e)))
+    }
+}
+
+/// Read a config file, resolving relative model paths against its directory
+fn read_file(path: &Path) -> Result<toml::Table> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        WhisperError::io(format!("Failed to read config file {}", path.display()), e)
+    })?;
+    let mut values: toml::Table = toml::from_str(&contents).map_err(|e| {
+        WhisperError::Configuration(format!("Invalid config file {}: {}", path.display(), e))
+    })?;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    normalize_model(&mut values, dir);
+    if let Some(toml::Value::Table(profiles)) = values.get_mut(PROFILES_KEY) {
+        for (_, profile) in profiles.iter_mut() {
+            if let toml::Value::Table(profile) = profile {
+                normalize_model(profile, dir);
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Store `model` as `model_path`, making paths relative to `dir` absolute
+///
+/// Values that do not name an existing file are kept as is, they are model names.
+fn normalize_model(values: &mut toml::Table, dir: &Path) {
+    if let Some(model) = values.remove("model") {
+        values.insert("model_path".to_string(), model);
+    }
+
+    if let Some(toml::Value::String(model)) = values.get_mut("model_path") {
+        let path = dir.join(&*model);
+        if Path::new(model).is_relative() && path.exists() {
+            *model = path.to_string_lossy().into_owned();
+        }
+    }
+}
+
+/// Recursively merge `overlay` into `base`, overlay values winning
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_project_overrides_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = write(
+            dir.path(),
+            "config.toml",
+            "language = \"fr\"\nnum_threads = 4\n\n[output_format]\nword_timestamps = true\n",
+        );
+        let project = write(
+            dir.path(),
+            "purr.toml",
+            "language = \"en\"\n\n[output_format]\ninclude_timestamps = false\n",
+        );
+
+        let file = ConfigFile::from_files(&[user, project]).unwrap();
+        let config = file.resolve(TranscriptionConfig::new(), None).unwrap();
+
+        assert_eq!(config.language.as_deref(), Some("en"));
+        assert_eq!(config.num_threads, Some(4));
+        assert!(config.output_format.word_timestamps);
+        assert!(!config.output_format.include_timestamps);
+        assert_eq!(config.sample_rate, 16000);
+        assert_eq!(file.sources().len(), 2);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "purr.toml",
+            "language = \"en\"\ntemperature = 0.2\n\n[profiles.podcast]\nmodel = \"large-v3-turbo\"\nbeam_size = 5\n",
+        );
+        let file = ConfigFile::from_files(&[path]).unwrap();
+        assert_eq!(file.profiles(), vec!["podcast".to_string()]);
+
+        let config = file
+            .resolve(TranscriptionConfig::new(), Some("podcast"))
+            .unwrap();
+        assert_eq!(config.model_path, Some(PathBuf::from("large-v3-turbo")));
+        assert_eq!(config.beam_size, Some(5));
+        assert_eq!(config.language.as_deref(), Some("en"));
+        assert_eq!(config.temperature, 0.2);
+
+        let config = file.resolve(TranscriptionConfig::new(), None).unwrap();
+        assert_eq!(config.model_path, None);
+
+        let err = file
+            .resolve(TranscriptionConfig::new(), Some("meeting"))
+            .unwrap_err();
+        assert!(err.to_string().contains("podcast"));
+    }
+
+    #[test]
+    fn test_relative_model_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = write(dir.path(), "tuned.bin", "");
+        let path = write(
+            dir.path(),
+            "purr.toml",
+            "model = \"tuned.bin\"\n\n[profiles.fast]\nmodel = \"tiny\"\n",
+        );
+        let file = ConfigFile::from_files(&[path]).unwrap();
+
+        let config = file.resolve(TranscriptionConfig::new(), None).unwrap();
+        assert_eq!(config.model_path, Some(model));
+
+        let config = file
+            .resolve(TranscriptionConfig::new(), Some("fast"))
+            .unwrap();
+        assert_eq!(config.model_path, Some(PathBuf::from("tiny")));
+    }
+
+    #[test]
+    fn test_find_project_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(ConfigFile::find_project_config(&nested), None);
+
+        let path = write(dir.path(), PROJECT_CONFIG_FILE, "");
+        assert_eq!(ConfigFile::find_project_config(&nested), Some(path));
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "purr.toml", "num_threads = \"many\"\n");
+        let file = ConfigFile::from_files(&[path]).unwrap();
+        assert!(file.resolve(TranscriptionConfig::new(), None).is_err());
+
+        let path = write(dir.path(), "broken.toml", "language = \n");
+        assert!(ConfigFile::from_files(&[path]).is_err());
+    }
+}
@@ -0,0 +1,460 @@
+//! Readable transcripts grouped into sentences and paragraphs
+//!
+//! Whisper segments follow its 30 second windows rather than the structure of
+//! the speech. Segments are split into sentences at terminal punctuation, and
+//! sentences are grouped into paragraphs at long pauses, speaker turns and
+//! after a maximum number of sentences. Sentence times are interpolated from
+//! the position of the sentence in its segment.
+
+use crate::whisper::TranscriptionSegment;
+use serde::{Deserialize, Serialize};
+
+/// Closing quotes and brackets that may follow the end of a sentence
+const CLOSING: &[char] = &['"', '\'', ')', ']', '»', '”', '’'];
+
+/// Common abbreviations whose period does not end a sentence
+const ABBREVIATIONS: &[&str] = &[
+    "mr.", "mrs.", "ms.", "dr.", "prof.", "st.", "jr.", "sr.", "vs.", "e.g.", "i.e.", "cf.",
+    "approx.",
+];
+
+/// Paragraph grouping options
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParagraphOptions {
+    /// Silence between sentences that starts a new paragraph (seconds)
+    pub pause: f64,
+
+    /// Most sentences in a paragraph
+    pub max_sentences: usize,
+}
+
+impl Default for ParagraphOptions {
+    fn default() -> Self {
+        Self {
+            pause: 2.0,
+            max_sentences: 5,
+        }
+    }
+}
+
+/// A sentence with its approximate times
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sentence {
+    pub text: String,
+
+    /// Start time in seconds
+    pub start: f64,
+
+    /// End time in seconds
+    pub end: f64,
+
+    /// Whether another speaker talks next
+    pub speaker_turn_next: bool,
+}
+
+/// Consecutive sentences of one speaker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paragraph {
+    pub sentences: Vec<Sentence>,
+
+    /// Whether the paragraph starts with a new speaker
+    pub speaker_turn: bool,
+}
+
+impl Paragraph {
+    /// Start time in seconds
+    pub fn start(&self) -> f64 {
+        self.sentences.first().map_or(0.0, |s| s.start)
+    }
+
+    /// End time in seconds
+    pub fn end(&self) -> f64 {
+        self.sentences.last().map_or(0.0, |s| s.end)
+    }
+
+    /// Sentences joined with spaces
+    pub fn text(&self) -> String {
+        self.sentences
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Split segments into sentences
+///
+/// A sentence also ends at a segment followed by a speaker turn or a pause
+/// of at least `pause` seconds, even without punctuation.
+pub fn sentences(segments: &[TranscriptionSegment], pause: f64) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut start = 0.0;
+
+    for (index, segment) in segments.iter().enumerate() {
+        let text = segment.text.as_str();
+        let chars = text.chars().count().max(1) as f64;
+        let time_at = |offset: usize| {
+            let before = text[..offset].chars().count() as f64;
+            segment.start + (segment.end - segment.start) * before / chars
+        };
+
+        let mut piece_start = 0;
+        for end in sentence_ends(text) {
+            let piece = &text[piece_start..end];
+            if current.trim().is_empty() {
+                start = time_at(piece_start + (piece.len() - piece.trim_start().len()));
+            }
+            append(&mut current, piece);
+            sentences.push(Sentence {
+                text: current.trim().to_string(),
+                start,
+                end: time_at(end),
+                speaker_turn_next: false,
+            });
+            current.clear();
+            piece_start = end;
+        }
+
+        let rest = &text[piece_start..];
+        if !rest.trim().is_empty() {
+            if current.trim().is_empty() {
+                start = time_at(piece_start + (rest.len() - rest.trim_start().len()));
+            }
+            append(&mut current, rest);
+        }
+
+        let pause_next = segments
+            .get(index + 1)
+            .is_some_and(|next| next.start - segment.end >= pause);
+        if segment.speaker_turn_next || pause_next || index + 1 == segments.len() {
+            if !current.trim().is_empty() {
+                sentences.push(Sentence {
+                    text: current.trim().to_string(),
+                    start,
+                    end: segment.end,
+                    speaker_turn_next: false,
+                });
+                current.clear();
+            }
+            if let Some(last) = sentences.last_mut() {
+                last.speaker_turn_next |= segment.speaker_turn_next;
+            }
+        }
+    }
+
+    sentences
+}
+
+/// Append a piece of a sentence, separated by a space from the previous segment
+fn append(sentence: &mut String, piece: &str) {
+    if !sentence.is_empty()
+        && !sentence.ends_with(char::is_whitespace)
+        && !piece.starts_with(char::is_whitespace)
+    {
+        sentence.push(' ');
+    }
+    sentence.push_str(piece);
+}
+
+/// Byte offsets just after each sentence-ending punctuation mark of a text
+fn sentence_ends(text: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let terminal = matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？');
+        if !terminal {
+            continue;
+        }
+
+        let mut end = i + c.len_utf8();
+        while let Some(&(j, next)) = chars.peek() {
+            if matches!(next, '.' | '!' | '?') || CLOSING.contains(&next) {
+                end = j + next.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        // "3.5" and "Mr. Smith" do not end a sentence
+        let ends_sentence = match chars.peek() {
+            None => true,
+            Some(&(_, next)) => {
+                (next.is_whitespace() || !c.is_ascii()) && !is_abbreviation(&text[..end])
+            }
+        };
+        if ends_sentence && !text[..i].trim().is_empty() {
+            ends.push(end);
+        }
+    }
+
+    ends
+}
+
+/// Whether a text ends with one of the [`ABBREVIATIONS`]
+fn is_abbreviation(text: &str) -> bool {
+    let word = text
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric());
+    ABBREVIATIONS
+        .iter()
+        .any(|abbreviation| word.eq_ignore_ascii_case(abbreviation))
+}
+
+/// Group segments into paragraphs
+pub fn paragraphs(segments: &[TranscriptionSegment], options: &ParagraphOptions) -> Vec<Paragraph> {
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    let mut speaker_turn = false;
+
+    for sentence in sentences(segments, options.pause) {
+        let turn_next = sentence.speaker_turn_next;
+        let new_paragraph = match paragraphs.last() {
+            None => true,
+            Some(last) => {
+                speaker_turn
+                    || sentence.start - last.end() >= options.pause
+                    || last.sentences.len() >= options.max_sentences.max(1)
+            }
+        };
+
+        if new_paragraph {
+            paragraphs.push(Paragraph {
+                sentences: vec![sentence],
+                speaker_turn,
+            });
+        } else if let Some(last) = paragraphs.last_mut() {
+            last.sentences.push(sentence);
+        }
+        speaker_turn = turn_next;
+    }
+
+    paragraphs
+}
+
+/// Short timestamp for headings and links (`MM:SS`, or `H:MM:SS` past an hour)
+fn short_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    let (hours, minutes, secs) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}", minutes, secs)
+    }
+}
+
+/// Id of the anchor of the paragraph at `index`, starting at `seconds`
+///
+/// The index keeps ids unique when paragraphs start within the same second.
+fn anchor(index: usize, seconds: f64) -> String {
+    format!("p{}-t{}", index + 1, seconds.max(0.0) as u64)
+}
+
+/// Render paragraphs as Markdown
+///
+/// With `timestamps`, each paragraph starts with its time, linking to an
+/// anchor on the paragraph. Paragraphs with a new speaker start with a dash.
+pub fn to_markdown(paragraphs: &[Paragraph], timestamps: bool) -> String {
+    paragraphs
+        .iter()
+        .enumerate()
+        .map(|(index, paragraph)| {
+            let mut line = String::new();
+            if timestamps {
+                let id = anchor(index, paragraph.start());
+                line.push_str(&format!(
+                    "<a id=\"{}\"></a>[{}](#{}) ",
+                    id,
+                    short_timestamp(paragraph.start()),
+                    id
+                ));
+            }
+            if paragraph.speaker_turn {
+                line.push_str("— ");
+            }
+            line.push_str(&escape_markdown(&paragraph.text()));
+            line.push('\n');
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render paragraphs as a standalone HTML document
+///
+/// With `timestamps`, each paragraph gets an id and starts with a link to it.
+/// Paragraphs with a new speaker have the `speaker-turn` class.
+pub fn to_html(paragraphs: &[Paragraph], title: &str, timestamps: bool) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n",
+        escape_html(title)
+    );
+
+    for (index, paragraph) in paragraphs.iter().enumerate() {
+        let class = if paragraph.speaker_turn {
+            " class=\"speaker-turn\""
+        } else {
+            ""
+        };
+        let text = escape_html(&paragraph.text());
+        if timestamps {
+            let id = anchor(index, paragraph.start());
+            html.push_str(&format!(
+                "<p id=\"{}\"{}><a class=\"timestamp\" href=\"#{}\">{}</a> {}</p>\n",
+                id,
+                class,
+                id,
+                short_timestamp(paragraph.start()),
+                text
+            ));
+        } else {
+            html.push_str(&format!("<p{}>{}</p>\n", class, text));
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, start: f64, end: f64) -> TranscriptionSegment {
+        TranscriptionSegment {
+            text: text.to_string(),
+            start,
+            end,
+            confidence: None,
+            words: None,
+            no_speech_prob: None,
+            hallucination: None,
+            speaker_turn_next: false,
+            tokens: None,
+        }
+    }
+
+    #[test]
+    fn test_sentences() {
+        let segments = [
+            segment(" Hello there. How are", 0.0, 2.0),
+            segment(" you? It costs 3.5 dollars", 2.0, 4.0),
+            segment(" and that's it", 6.0, 7.0),
+        ];
+        let sentences = sentences(&segments, 2.0);
+        let texts: Vec<_> = sentences.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Hello there.",
+                "How are you?",
+                "It costs 3.5 dollars",
+                "and that's it"
+            ]
+        );
+        // Times are interpolated from the character position in the segment
+        assert!((sentences[0].start - 2.0 / 21.0).abs() < 1e-9);
+        assert!((sentences[1].start - 28.0 / 21.0).abs() < 1e-9);
+        assert!((sentences[1].end - (2.0 + 10.0 / 26.0)).abs() < 1e-9);
+        assert_eq!(sentences[2].end, 4.0);
+    }
+
+    #[test]
+    fn test_sentence_ends_skip_abbreviations() {
+        let text = "Ask Dr. Smith, e.g. about it. Then (i.e. later) go.";
+        let ends: Vec<_> = sentence_ends(text)
+            .into_iter()
+            .map(|end| &text[..end])
+            .collect();
+        assert_eq!(
+            ends,
+            vec![
+                "Ask Dr. Smith, e.g. about it.",
+                "Ask Dr. Smith, e.g. about it. Then (i.e. later) go."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let mut turn = segment(" Any questions?", 4.0, 5.0);
+        turn.speaker_turn_next = true;
+        let segments = [
+            segment(" First point. Second point.", 0.0, 3.0),
+            segment(" Third point.", 3.0, 4.0),
+            turn,
+            segment(" Yes, one.", 5.0, 6.0),
+            segment(" Later on.", 10.0, 11.0),
+        ];
+        let options = ParagraphOptions {
+            max_sentences: 3,
+            ..Default::default()
+        };
+        let paragraphs = paragraphs(&segments, &options);
+        let texts: Vec<_> = paragraphs.iter().map(|p| p.text()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "First point. Second point. Third point.",
+                "Any questions?",
+                "Yes, one.",
+                "Later on."
+            ]
+        );
+        assert!(paragraphs[2].speaker_turn);
+        assert!(!paragraphs[3].speaker_turn);
+    }
+
+    #[test]
+    fn test_markdown_and_html() {
+        let segments = [
+            segment(" Hello *world*.", 0.0, 1.0),
+            segment(" Bye <3", 75.0, 76.0),
+        ];
+        let paragraphs = paragraphs(&segments, &ParagraphOptions::default());
+
+        assert_eq!(
+            to_markdown(&paragraphs, false),
+            "Hello \\*world\\*.\n\nBye \\<3\n"
+        );
+        assert_eq!(
+            to_markdown(&paragraphs[1..], true),
+            "<a id=\"p1-t75\"></a>[01:15](#p1-t75) Bye \\<3\n"
+        );
+        assert!(to_html(&paragraphs, "Talk", true).contains(
+            "<p id=\"p2-t75\"><a class=\"timestamp\" href=\"#p2-t75\">01:15</a> Bye &lt;3</p>"
+        ));
+
+        // Paragraphs starting within the same second get their own ids
+        let options = ParagraphOptions {
+            max_sentences: 1,
+            ..Default::default()
+        };
+        let split = super::paragraphs(&[segment(" One. Two.", 3.0, 3.8)], &options);
+        let html = to_html(&split, "Talk", true);
+        assert!(html.contains("id=\"p1-t3\""), "{}", html);
+        assert!(html.contains("id=\"p2-t3\""), "{}", html);
+    }
+}
@@ -1,83 +1,289 @@
 //! Error types for the purr-core library
 
-use thiserror::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Main error type for purr operations
-#[derive(Error, Debug, Clone)]
+///
+/// Failures callers may want to handle have their own variant with structured
+/// fields. Errors from other libraries are kept as the `source` of the error;
+/// sources that cannot be cloned are shared behind an [`Arc`], but
+/// [`source`](std::error::Error::source) returns the error itself. Messages
+/// leave out the source, use [`WhisperError::report`] or an error reporter
+/// such as `anyhow`'s `{:#}` to show the causes too.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum WhisperError {
-    #[error("Audio processing error: {0}")]
+    /// No model file was found in any of the searched locations
+    ModelNotFound {
+        searched: Vec<PathBuf>,
+    },
+
+    /// A model file is not a usable ggml model
+    InvalidModel(String),
+
+    /// The audio file does not exist
+    AudioNotFound {
+        path: PathBuf,
+    },
+
+    /// The file contains no audio stream
+    NoAudioStream {
+        path: PathBuf,
+    },
+
+    /// FFmpeg has no decoder or encoder for this codec
+    UnsupportedCodec {
+        codec: String,
+    },
+
+    /// A server answered a download with an error status
+    DownloadFailed {
+        status: u16,
+        url: String,
+    },
+
+    /// A request failed before a response was received, or while reading it
+    Network {
+        url: String,
+        source: Arc<reqwest::Error>,
+    },
+
+    /// A downloaded file does not match its expected checksum
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// The operation was cancelled by the user
+    Cancelled,
+
     AudioProcessing(String),
 
-    #[error("Whisper error: {0}")]
-    Whisper(String),
+    /// An FFmpeg call failed
+    FFmpeg {
+        context: String,
+        source: ffmpeg_next::Error,
+    },
 
-    #[error("IO error: {0}")]
-    Io(String),
+    /// A whisper.cpp call failed
+    Whisper {
+        context: String,
+        source: whisper_rs::WhisperError,
+    },
 
-    #[error("FFmpeg error: {0}")]
-    FFmpeg(String),
+    /// A file system operation failed
+    Io {
+        context: String,
+        source: Arc<std::io::Error>,
+    },
 
-    #[error("Configuration error: {0}")]
     Configuration(String),
 
-    #[error("Transcription error: {0}")]
     Transcription(String),
 
-    #[error("GPU acceleration error: {0}")]
     GpuAcceleration(String),
 
-    #[error("Unknown error: {0}")]
+    /// A background task panicked or was aborted
+    Task(Arc<tokio::task::JoinError>),
+
     Unknown(String),
 }
 
 /// Result type alias for purr operations
 pub type Result<T> = std::result::Result<T, WhisperError>;
 
+impl WhisperError {
+    /// An FFmpeg failure, described by what was being done
+    pub fn ffmpeg<S: Into<String>>(context: S, source: ffmpeg_next::Error) -> Self {
+        WhisperError::FFmpeg {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// A whisper.cpp failure, described by what was being done
+    pub fn whisper<S: Into<String>>(context: S, source: whisper_rs::WhisperError) -> Self {
+        WhisperError::Whisper {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// A file system failure, described by what was being done
+    pub fn io<S: Into<String>>(context: S, source: std::io::Error) -> Self {
+        WhisperError::Io {
+            context: context.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// A failed request to `url`
+    pub fn network<S: Into<String>>(url: S, source: reqwest::Error) -> Self {
+        WhisperError::Network {
+            url: url.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    /// The message followed by the messages of its sources
+    ///
+    /// For places that show a single string, such as logs and API responses.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            report.push_str(": ");
+            report.push_str(&error.to_string());
+            source = error.source();
+        }
+        report
+    }
+}
+
+impl std::fmt::Display for WhisperError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WhisperError::ModelNotFound { searched } => write!(
+                f,
+                "No Whisper model found (searched: {})",
+                display_paths(searched)
+            ),
+            WhisperError::InvalidModel(message) => write!(f, "Invalid model file: {}", message),
+            WhisperError::AudioNotFound { path } => {
+                write!(f, "Audio file not found: {}", path.display())
+            }
+            WhisperError::NoAudioStream { path } => {
+                write!(f, "No audio stream found in {}", path.display())
+            }
+            WhisperError::UnsupportedCodec { codec } => write!(f, "Unsupported codec: {}", codec),
+            WhisperError::DownloadFailed { status, url } => {
+                write!(f, "Failed to download {}: HTTP {}", url, status)
+            }
+            WhisperError::Network { url, .. } => write!(f, "Request to {} failed", url),
+            WhisperError::ChecksumMismatch {
+                url,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum mismatch for {}: expected {}, got {}",
+                url, expected, actual
+            ),
+            WhisperError::Cancelled => write!(f, "Operation cancelled"),
+            WhisperError::AudioProcessing(message) => {
+                write!(f, "Audio processing error: {}", message)
+            }
+            WhisperError::FFmpeg { context, .. }
+            | WhisperError::Whisper { context, .. }
+            | WhisperError::Io { context, .. } => f.write_str(context),
+            WhisperError::Configuration(message) => write!(f, "Configuration error: {}", message),
+            WhisperError::Transcription(message) => write!(f, "Transcription error: {}", message),
+            WhisperError::GpuAcceleration(message) => {
+                write!(f, "GPU acceleration error: {}", message)
+            }
+            WhisperError::Task(_) => write!(f, "Background task failed"),
+            WhisperError::Unknown(message) => write!(f, "Unknown error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for WhisperError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WhisperError::Network { source, .. } => Some(&**source),
+            WhisperError::FFmpeg { source, .. } => Some(source),
+            WhisperError::Whisper { source, .. } => Some(source),
+            WhisperError::Io { source, .. } => Some(&**source),
+            WhisperError::Task(source) => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    if paths.is_empty() {
+        return "nothing".to_string();
+    }
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl From<ffmpeg_next::Error> for WhisperError {
     fn from(err: ffmpeg_next::Error) -> Self {
-        WhisperError::FFmpeg(err.to_string())
+        WhisperError::ffmpeg("FFmpeg error", err)
     }
 }
 
 impl From<std::io::Error> for WhisperError {
     fn from(err: std::io::Error) -> Self {
-        WhisperError::Io(err.to_string())
+        WhisperError::io("IO error", err)
     }
 }
 
 impl From<whisper_rs::WhisperError> for WhisperError {
     fn from(err: whisper_rs::WhisperError) -> Self {
-        WhisperError::Whisper(err.to_string())
+        WhisperError::whisper("Whisper error", err)
+    }
+}
+
+impl From<tokio::task::JoinError> for WhisperError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        WhisperError::Task(Arc::new(err))
     }
 }
 
 impl PartialEq for WhisperError {
+    /// Errors are equal when they are the same variant with the same message
     fn eq(&self, other: &Self) -> bool {
-        match self {
-            WhisperError::AudioProcessing(msg) => {
-                matches!(other, WhisperError::AudioProcessing(o) if msg == o)
-            }
-            WhisperError::Whisper(msg) => {
-                matches!(other, WhisperError::Whisper(o) if msg == o)
-            }
-            WhisperError::Io(err) => {
-                matches!(other, WhisperError::Io(e) if err == e)
-            }
-            WhisperError::FFmpeg(msg) => {
-                matches!(other, WhisperError::FFmpeg(o) if msg == o)
-            }
-            WhisperError::Configuration(msg) => {
-                matches!(other, WhisperError::Configuration(o) if msg == o)
-            }
-            WhisperError::Transcription(msg) => {
-                matches!(other, WhisperError::Transcription(o) if msg == o)
-            }
-            WhisperError::GpuAcceleration(msg) => {
-                matches!(other, WhisperError::GpuAcceleration(o) if msg == o)
-            }
-            WhisperError::Unknown(msg) => {
-                matches!(other, WhisperError::Unknown(o) if msg == o)
-            }
-        }
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && self.to_string() == other.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_are_kept() {
+        use std::error::Error as _;
+
+        let error = WhisperError::io(
+            "Failed to read model",
+            std::io::Error::new(std::io::ErrorKind::NotFound, "missing"),
+        );
+        assert_eq!(error.to_string(), "Failed to read model");
+        assert_eq!(error.report(), "Failed to read model: missing");
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+
+        // Cloning shares the source
+        assert_eq!(error.clone(), error);
+    }
+
+    #[test]
+    fn test_structured_messages() {
+        let error = WhisperError::ModelNotFound {
+            searched: vec![PathBuf::from("a.bin"), PathBuf::from("b.bin")],
+        };
+        assert_eq!(
+            error.to_string(),
+            "No Whisper model found (searched: a.bin, b.bin)"
+        );
+        assert!(matches!(
+            WhisperError::DownloadFailed {
+                status: 404,
+                url: "https://example.com/model.bin".to_string()
+            },
+            WhisperError::DownloadFailed { status: 404, .. }
+        ));
     }
 }
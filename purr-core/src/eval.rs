@@ -0,0 +1,465 @@
+//! Word and character error rates of transcripts against references
+//!
+//! Both texts are normalized first so that formatting differences (case,
+//! punctuation, digits against spelled-out numbers) do not count as errors.
+//! Error counts come from a minimum edit-distance alignment, like
+//! [`align_sequences`](crate::align::align_sequences), but only two rows of
+//! counts are kept so hour-long transcripts can be scored character by
+//! character.
+
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+use std::path::PathBuf;
+
+/// Text normalization applied to references and transcripts before scoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Normalization {
+    /// Compare in lowercase
+    pub lowercase: bool,
+
+    /// Drop punctuation, keeping apostrophes inside words
+    pub remove_punctuation: bool,
+
+    /// Spell out numbers in English words ("25%" becomes "twenty five percent")
+    pub spell_numbers: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            remove_punctuation: true,
+            spell_numbers: true,
+        }
+    }
+}
+
+/// Normalize a text for scoring, collapsing whitespace
+pub fn normalize(text: &str, options: &Normalization) -> String {
+    let mut text = if options.spell_numbers {
+        spell_numbers(text)
+    } else {
+        text.to_string()
+    };
+    if options.lowercase {
+        text = text.to_lowercase();
+    }
+    if options.remove_punctuation {
+        text = text
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '\'' || c.is_whitespace() {
+                    c
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        // Quotes are not part of words
+        return text
+            .split_whitespace()
+            .map(|word| word.trim_matches('\''))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Replace numbers with English words
+///
+/// Digit groups may use commas as thousands separators and a period for
+/// decimals, whose digits are read one by one.
+fn spell_numbers(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c == '%' {
+            out.push_str(" percent ");
+            continue;
+        }
+        if !c.is_ascii_digit() {
+            out.push(c);
+            continue;
+        }
+
+        let mut end = start + 1;
+        while let Some(&(i, c)) = chars.peek() {
+            if !(c.is_ascii_digit() || c == ',' || c == '.') {
+                break;
+            }
+            end = i + c.len_utf8();
+            chars.next();
+        }
+
+        // Sentence punctuation after a number is not part of it
+        let number = &text[start..end];
+        let trimmed = number.trim_end_matches([',', '.']);
+        let parts: Vec<String> = trimmed
+            .replace(',', "")
+            .split('.')
+            .enumerate()
+            .map(|(index, part)| {
+                if index == 0 {
+                    match part.parse::<u64>() {
+                        Ok(value) => number_words(value),
+                        Err(_) => digit_words(part),
+                    }
+                } else {
+                    digit_words(part)
+                }
+            })
+            .collect();
+
+        out.push(' ');
+        out.push_str(&parts.join(" point "));
+        out.push(' ');
+        out.push_str(&number[trimmed.len()..]);
+    }
+
+    out
+}
+
+pub(crate) const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+pub(crate) const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+pub(crate) const SCALES: [(u64, &str); 6] = [
+    (1_000_000_000_000_000_000, "quintillion"),
+    (1_000_000_000_000_000, "quadrillion"),
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+/// English words for an integer ("one hundred twenty three")
+fn number_words(value: u64) -> String {
+    if value == 0 {
+        return ONES[0].to_string();
+    }
+
+    let mut words = Vec::new();
+    let mut rest = value;
+    for (scale, name) in SCALES {
+        if rest >= scale {
+            words.push(hundreds_words(rest / scale));
+            words.push(name.to_string());
+            rest %= scale;
+        }
+    }
+    if rest > 0 {
+        words.push(hundreds_words(rest));
+    }
+    words.join(" ")
+}
+
+/// English words for a number below a thousand
+fn hundreds_words(value: u64) -> String {
+    let mut words = Vec::new();
+    if value >= 100 {
+        words.push(ONES[(value / 100) as usize]);
+        words.push("hundred");
+    }
+    match value % 100 {
+        0 => {}
+        rest if rest < 20 => words.push(ONES[rest as usize]),
+        rest => {
+            words.push(TENS[(rest / 10) as usize]);
+            if rest % 10 > 0 {
+                words.push(ONES[(rest % 10) as usize]);
+            }
+        }
+    }
+    words.join(" ")
+}
+
+/// Digits read one by one ("05" becomes "zero five")
+fn digit_words(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|digit| ONES[digit as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Outcome of aligning a hypothesis with a reference
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCounts {
+    /// Items recognized correctly
+    pub hits: usize,
+    /// Items recognized as something else
+    pub substitutions: usize,
+    /// Reference items missing from the hypothesis
+    pub deletions: usize,
+    /// Hypothesis items missing from the reference
+    pub insertions: usize,
+}
+
+impl ErrorCounts {
+    /// Number of items in the reference
+    pub fn reference_len(&self) -> usize {
+        self.hits + self.substitutions + self.deletions
+    }
+
+    /// Total number of edits
+    pub fn errors(&self) -> usize {
+        self.substitutions + self.deletions + self.insertions
+    }
+
+    /// Edits per reference item
+    ///
+    /// Can exceed 1 when the hypothesis has many insertions. An empty
+    /// reference scores 0 against an empty hypothesis and 1 otherwise.
+    pub fn rate(&self) -> f64 {
+        match self.reference_len() {
+            0 => f64::from(u8::from(self.insertions > 0)),
+            len => self.errors() as f64 / len as f64,
+        }
+    }
+}
+
+impl AddAssign for ErrorCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.hits += other.hits;
+        self.substitutions += other.substitutions;
+        self.deletions += other.deletions;
+        self.insertions += other.insertions;
+    }
+}
+
+/// Count the edits of a minimum edit-distance alignment
+///
+/// Ties prefer matches and substitutions over insertions and deletions, as
+/// in [`align_sequences`](crate::align::align_sequences).
+pub fn count_errors<T: PartialEq>(reference: &[T], hypothesis: &[T]) -> ErrorCounts {
+    let mut previous: Vec<ErrorCounts> = (0..=hypothesis.len())
+        .map(|insertions| ErrorCounts {
+            insertions,
+            ..Default::default()
+        })
+        .collect();
+    let mut current = previous.clone();
+
+    for (i, reference_item) in reference.iter().enumerate() {
+        current[0] = ErrorCounts {
+            deletions: i + 1,
+            ..Default::default()
+        };
+        for (j, hypothesis_item) in hypothesis.iter().enumerate() {
+            let mut diagonal = previous[j];
+            if reference_item == hypothesis_item {
+                diagonal.hits += 1;
+            } else {
+                diagonal.substitutions += 1;
+            }
+            let mut up = previous[j + 1];
+            up.deletions += 1;
+            let mut left = current[j];
+            left.insertions += 1;
+
+            current[j + 1] =
+                if diagonal.errors() <= up.errors() && diagonal.errors() <= left.errors() {
+                    diagonal
+                } else if up.errors() <= left.errors() {
+                    up
+                } else {
+                    left
+                };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[hypothesis.len()]
+}
+
+/// Word and character errors of a transcript
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Score {
+    pub words: ErrorCounts,
+    /// Character errors, spaces between words included
+    pub chars: ErrorCounts,
+}
+
+impl Score {
+    /// Word error rate
+    pub fn wer(&self) -> f64 {
+        self.words.rate()
+    }
+
+    /// Character error rate
+    pub fn cer(&self) -> f64 {
+        self.chars.rate()
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Self) {
+        self.words += other.words;
+        self.chars += other.chars;
+    }
+}
+
+/// Score a transcript against its reference
+pub fn score(reference: &str, hypothesis: &str, options: &Normalization) -> Score {
+    let reference = normalize(reference, options);
+    let hypothesis = normalize(hypothesis, options);
+
+    let reference_words: Vec<&str> = reference.split(' ').filter(|w| !w.is_empty()).collect();
+    let hypothesis_words: Vec<&str> = hypothesis.split(' ').filter(|w| !w.is_empty()).collect();
+    let reference_chars: Vec<char> = reference.chars().collect();
+    let hypothesis_chars: Vec<char> = hypothesis.chars().collect();
+
+    Score {
+        words: count_errors(&reference_words, &hypothesis_words),
+        chars: count_errors(&reference_chars, &hypothesis_chars),
+    }
+}
+
+/// Score of one audio file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileScore {
+    pub audio: PathBuf,
+    pub reference: PathBuf,
+    pub score: Score,
+}
+
+/// Scores of an evaluation set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub normalization: Normalization,
+    pub files: Vec<FileScore>,
+    /// Errors summed over all files, so long files weigh more
+    pub total: Score,
+}
+
+impl EvalReport {
+    /// Build a report, summing the scores of all files
+    pub fn new(normalization: Normalization, files: Vec<FileScore>) -> Self {
+        let mut total = Score::default();
+        for file in &files {
+            total += file.score;
+        }
+        Self {
+            normalization,
+            files,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let options = Normalization::default();
+        assert_eq!(
+            normalize("Hello, World! It's 25% of 1,200.5.", &options),
+            "hello world it's twenty five percent of one thousand two hundred point five"
+        );
+        assert_eq!(
+            normalize("'Quoted'  well-known 3.05", &options),
+            "quoted well known three point zero five"
+        );
+
+        let options = Normalization {
+            lowercase: false,
+            remove_punctuation: false,
+            spell_numbers: false,
+        };
+        assert_eq!(
+            normalize(" Hello,   World 42 ", &options),
+            "Hello, World 42"
+        );
+    }
+
+    #[test]
+    fn test_number_words() {
+        assert_eq!(number_words(0), "zero");
+        assert_eq!(number_words(13), "thirteen");
+        assert_eq!(number_words(90), "ninety");
+        assert_eq!(number_words(2024), "two thousand twenty four");
+        assert_eq!(number_words(1_000_305), "one million three hundred five");
+    }
+
+    #[test]
+    fn test_count_errors() {
+        let counts = count_errors(&["a", "b", "c", "d"], &["a", "x", "c", "d", "e"]);
+        assert_eq!(
+            counts,
+            ErrorCounts {
+                hits: 3,
+                substitutions: 1,
+                deletions: 0,
+                insertions: 1,
+            }
+        );
+        assert_eq!(counts.rate(), 0.5);
+
+        let counts = count_errors(&["a", "b", "c"], &["a", "c"]);
+        assert_eq!(counts.deletions, 1);
+        assert_eq!(counts.errors(), 1);
+
+        assert_eq!(count_errors::<&str>(&[], &[]).rate(), 0.0);
+        assert_eq!(count_errors(&[], &["a"]).rate(), 1.0);
+    }
+
+    #[test]
+    fn test_score() {
+        let score = score(
+            "The cat sat on the mat.",
+            "the cat sat on a mat",
+            &Normalization::default(),
+        );
+        assert_eq!(score.words.substitutions, 1);
+        assert!((score.wer() - 1.0 / 6.0).abs() < 1e-9);
+        // "the" -> "a": one substitution and two deletions
+        assert_eq!(score.chars.errors(), 3);
+
+        let report = EvalReport::new(
+            Normalization::default(),
+            vec![
+                FileScore {
+                    audio: PathBuf::from("a.wav"),
+                    reference: PathBuf::from("a.txt"),
+                    score,
+                },
+                FileScore {
+                    audio: PathBuf::from("b.wav"),
+                    reference: PathBuf::from("b.txt"),
+                    score,
+                },
+            ],
+        );
+        assert_eq!(report.total.words.reference_len(), 12);
+        assert_eq!(report.total.words.errors(), 2);
+    }
+}
@@ -0,0 +1,458 @@
+//! Detection of hallucinated and repeated segments
+//!
+//! Whisper tends to invent text over silence and music ("Thanks for
+//! watching!") and sometimes gets stuck repeating a phrase. This pass looks
+//! for repetition loops, segments without speech and phrases known to be
+//! made up, then flags or drops them.
+//!
+//! whisper-rs does not expose whisper.cpp's no-speech probability for our own
+//! states, so it is estimated with the energy VAD as the share of the segment
+//! that contains no detected speech.
+//!
+//! Segments passed to a segment callback as they are decoded only go
+//! through the text checks, see [`HallucinationFilter::filter_callback`].
+
+use crate::audio::AudioData;
+use crate::eval::{normalize, Normalization};
+use crate::vad::{detect_speech, SpeechRegion, VadConfig};
+use crate::whisper::sync::SegmentCallback;
+use crate::whisper::TranscriptionSegment;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Phrases whisper is known to produce over silence, music and applause
+const KNOWN_PHRASES: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thanks for watching and see you next time",
+    "thank you so much for watching",
+    "please subscribe",
+    "please subscribe to my channel",
+    "like and subscribe",
+    "don't forget to like and subscribe",
+    "see you in the next video",
+    "subtitles by the amara.org community",
+    "subtitles by steamteam",
+    "transcription by castingwords",
+    "sous-titres réalisés par la communauté d'amara.org",
+    "untertitel der amara.org-community",
+    "untertitel im auftrag des zdf",
+];
+
+/// Why a segment is considered a hallucination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum HallucinationKind {
+    /// A phrase or segment repeated over and over
+    Repetition,
+    /// Text over audio without detected speech
+    NoSpeech,
+    /// A phrase from the list of known hallucinations
+    KnownPhrase,
+}
+
+/// What to do with hallucinated segments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HallucinationAction {
+    /// Keep segments as transcribed
+    #[default]
+    Off,
+    /// Keep segments and mark them with [`TranscriptionSegment::hallucination`]
+    Flag,
+    /// Remove segments, and cut repetition loops inside a segment down to
+    /// one occurrence
+    Drop,
+}
+
+/// Hallucination filter settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallucinationFilter {
+    /// What to do with detected hallucinations
+    pub action: HallucinationAction,
+
+    /// Most consecutive occurrences of a phrase or segment before it counts
+    /// as a repetition loop
+    pub max_repeats: usize,
+
+    /// Longest phrase checked for repetition, in words
+    pub max_ngram: usize,
+
+    /// Estimated no-speech probability above which a segment is a hallucination
+    pub no_speech_threshold: f32,
+
+    /// Segments made up of only one of these phrases are hallucinations,
+    /// compared ignoring case and punctuation
+    pub phrases: Vec<String>,
+}
+
+impl Default for HallucinationFilter {
+    fn default() -> Self {
+        Self {
+            action: HallucinationAction::Off,
+            max_repeats: 3,
+            max_ngram: 8,
+            no_speech_threshold: 0.8,
+            phrases: KNOWN_PHRASES.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+impl HallucinationFilter {
+    /// Whether the filter changes anything
+    pub fn is_enabled(&self) -> bool {
+        self.action != HallucinationAction::Off
+    }
+
+    /// Flag or drop hallucinated segments
+    ///
+    /// Without `audio`, no-speech probabilities are left as they are and
+    /// only the text is checked.
+    pub fn apply(
+        &self,
+        mut segments: Vec<TranscriptionSegment>,
+        audio: Option<&AudioData>,
+    ) -> Vec<TranscriptionSegment> {
+        if !self.is_enabled() {
+            return segments;
+        }
+
+        if let Some(audio) = audio {
+            let speech = detect_speech(&audio.samples, audio.sample_rate, &VadConfig::default());
+            for segment in &mut segments {
+                segment.no_speech_prob = Some(no_speech_prob(segment, &speech));
+            }
+        }
+
+        let phrases: Vec<String> = self.phrases.iter().map(|p| comparable(p)).collect();
+        let texts: Vec<String> = segments.iter().map(|s| comparable(&s.text)).collect();
+
+        // Segments repeated more than `max_repeats` times in a row keep their first copy
+        let mut repeated = vec![false; segments.len()];
+        let mut run_start = 0;
+        for i in 1..=texts.len() {
+            if i < texts.len() && !texts[i].is_empty() && texts[i] == texts[run_start] {
+                continue;
+            }
+            if i - run_start > self.max_repeats {
+                repeated[run_start + 1..i].fill(true);
+            }
+            run_start = i;
+        }
+
+        let mut kept = Vec::with_capacity(segments.len());
+        for (i, segment) in segments.into_iter().enumerate() {
+            let looped = repetition_mask(&segment.text, self.max_ngram, self.max_repeats);
+            let kind = if !texts[i].is_empty() && phrases.contains(&texts[i]) {
+                Some(HallucinationKind::KnownPhrase)
+            } else if segment
+                .no_speech_prob
+                .is_some_and(|p| p > self.no_speech_threshold)
+            {
+                Some(HallucinationKind::NoSpeech)
+            } else if repeated[i] || looped.is_some() {
+                Some(HallucinationKind::Repetition)
+            } else {
+                None
+            };
+
+            match kind {
+                Some(kind) => kept.extend(self.resolve(segment, kind, looped, repeated[i])),
+                None => kept.push(segment),
+            }
+        }
+
+        kept
+    }
+
+    /// Wrap a segment callback so that it only sees what the filter keeps
+    ///
+    /// Segments are checked one by one as they are decoded, without the
+    /// audio, so only the text checks run: known phrases, loops inside a
+    /// segment and repeated segments.
+    /// A repeated segment is caught from its `max_repeats + 1`th copy on,
+    /// the earlier copies have already been passed on.
+    pub fn filter_callback(&self, on_segment: SegmentCallback) -> SegmentCallback {
+        if !self.is_enabled() {
+            return on_segment;
+        }
+
+        let filter = self.clone();
+        let phrases: Vec<String> = self.phrases.iter().map(|p| comparable(p)).collect();
+        // Text of the previous segment and how many times in a row it came
+        let previous = Mutex::new((String::new(), 0usize));
+        Arc::new(move |segment: TranscriptionSegment| {
+            let text = comparable(&segment.text);
+            let copies = {
+                let mut previous = previous.lock().unwrap_or_else(|e| e.into_inner());
+                if !text.is_empty() && previous.0 == text {
+                    previous.1 += 1;
+                } else {
+                    *previous = (text.clone(), 1);
+                }
+                previous.1
+            };
+
+            let repeated = copies > filter.max_repeats;
+            let looped = repetition_mask(&segment.text, filter.max_ngram, filter.max_repeats);
+            let kind = if !text.is_empty() && phrases.contains(&text) {
+                Some(HallucinationKind::KnownPhrase)
+            } else if repeated || looped.is_some() {
+                Some(HallucinationKind::Repetition)
+            } else {
+                None
+            };
+
+            let kept = match kind {
+                Some(kind) => filter.resolve(segment, kind, looped, repeated),
+                None => Some(segment),
+            };
+            if let Some(segment) = kept {
+                on_segment(segment);
+            }
+        })
+    }
+
+    /// Flag, fix or drop a hallucinated segment according to the action
+    ///
+    /// `looped` is the repetition mask of its text and `repeated` whether
+    /// it repeats the previous segments.
+    fn resolve(
+        &self,
+        mut segment: TranscriptionSegment,
+        kind: HallucinationKind,
+        looped: Option<Vec<bool>>,
+        repeated: bool,
+    ) -> Option<TranscriptionSegment> {
+        debug!(
+            "Hallucination ({}) at {:.2}s: {}",
+            kind,
+            segment.start,
+            segment.text.trim()
+        );
+
+        match self.action {
+            HallucinationAction::Off => Some(segment),
+            HallucinationAction::Flag => {
+                segment.hallucination = Some(kind);
+                Some(segment)
+            }
+            HallucinationAction::Drop => match looped {
+                Some(keep) if kind == HallucinationKind::Repetition && !repeated => {
+                    collapse(&mut segment, &keep);
+                    Some(segment)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Lowercase text without punctuation, for comparisons
+fn comparable(text: &str) -> String {
+    normalize(
+        text,
+        &Normalization {
+            lowercase: true,
+            remove_punctuation: true,
+            spell_numbers: false,
+        },
+    )
+}
+
+/// Share of the segment not covered by detected speech
+fn no_speech_prob(segment: &TranscriptionSegment, speech: &[SpeechRegion]) -> f32 {
+    let duration = segment.end - segment.start;
+    if duration <= 0.0 {
+        return 0.0;
+    }
+
+    let covered: f64 = speech
+        .iter()
+        .map(|region| (region.end.min(segment.end) - region.start.max(segment.start)).max(0.0))
+        .sum();
+    (1.0 - covered / duration).clamp(0.0, 1.0) as f32
+}
+
+/// Find n-grams repeated more than `max_repeats` times in a row
+///
+/// Returns which words of the text to keep to leave a single occurrence of
+/// each loop, or `None` if there is no loop.
+fn repetition_mask(text: &str, max_ngram: usize, max_repeats: usize) -> Option<Vec<bool>> {
+    let words: Vec<String> = text.split_whitespace().map(comparable).collect();
+    let mut keep = vec![true; words.len()];
+    let mut found = false;
+
+    let mut i = 0;
+    while i < words.len() {
+        let mut skipped = 0;
+        for n in 1..=max_ngram.min((words.len() - i) / 2) {
+            let gram = &words[i..i + n];
+            let mut count = 1;
+            while i + (count + 1) * n <= words.len() && words[i + count * n..][..n] == *gram {
+                count += 1;
+            }
+            if count > max_repeats {
+                keep[i + n..i + count * n].fill(false);
+                skipped = count * n;
+                found = true;
+                break;
+            }
+        }
+        i += skipped.max(1);
+    }
+
+    found.then_some(keep)
+}
+
+/// Remove the words of a segment that are not kept
+fn collapse(segment: &mut TranscriptionSegment, keep: &[bool]) {
+    let leading = segment.text.starts_with(' ');
+    let words: Vec<&str> = segment
+        .text
+        .split_whitespace()
+        .zip(keep)
+        .filter(|(_, &keep)| keep)
+        .map(|(word, _)| word)
+        .collect();
+    segment.text = format!("{}{}", if leading { " " } else { "" }, words.join(" "));
+
+    // Word timestamps follow the same whitespace boundaries
+    if let Some(ref mut words) = segment.words {
+        if words.len() == keep.len() {
+            let mut keep = keep.iter();
+            words.retain(|_| *keep.next().unwrap_or(&true));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, start: f64, end: f64) -> TranscriptionSegment {
+        TranscriptionSegment {
+            text: text.to_string(),
+            start,
+            end,
+            confidence: None,
+            words: None,
+            no_speech_prob: None,
+            hallucination: None,
+            speaker_turn_next: false,
+            tokens: None,
+        }
+    }
+
+    fn filter(action: HallucinationAction) -> HallucinationFilter {
+        HallucinationFilter {
+            action,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_known_phrases_and_repeated_segments() {
+        let segments = vec![
+            segment(" Hello there.", 0.0, 2.0),
+            segment(" Okay.", 2.0, 3.0),
+            segment(" Okay.", 3.0, 4.0),
+            segment(" Okay.", 4.0, 5.0),
+            segment(" Okay.", 5.0, 6.0),
+            segment(" Thanks for watching!", 6.0, 8.0),
+        ];
+
+        let flagged = filter(HallucinationAction::Flag).apply(segments.clone(), None);
+        let kinds: Vec<_> = flagged.iter().map(|s| s.hallucination).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                None,
+                None,
+                Some(HallucinationKind::Repetition),
+                Some(HallucinationKind::Repetition),
+                Some(HallucinationKind::Repetition),
+                Some(HallucinationKind::KnownPhrase),
+            ]
+        );
+
+        let dropped = filter(HallucinationAction::Drop).apply(segments.clone(), None);
+        let texts: Vec<_> = dropped.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec![" Hello there.", " Okay."]);
+
+        let kept = filter(HallucinationAction::Off).apply(segments, None);
+        assert_eq!(kept.len(), 6);
+    }
+
+    #[test]
+    fn test_repetition_loop_inside_segment() {
+        let text = " I said that we should go, we should go, we should go, we should go now.";
+        let segments = vec![segment(text, 0.0, 5.0)];
+
+        let flagged = filter(HallucinationAction::Flag).apply(segments.clone(), None);
+        assert_eq!(
+            flagged[0].hallucination,
+            Some(HallucinationKind::Repetition)
+        );
+
+        let dropped = filter(HallucinationAction::Drop).apply(segments, None);
+        assert_eq!(dropped[0].text, " I said that we should go, now.");
+        assert_eq!(dropped[0].hallucination, None);
+
+        // A few repetitions are normal speech
+        let segments = vec![segment(" No, no, no.", 0.0, 1.0)];
+        let kept = filter(HallucinationAction::Drop).apply(segments, None);
+        assert_eq!(kept[0].text, " No, no, no.");
+    }
+
+    #[test]
+    fn test_filter_callback() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let on_segment: SegmentCallback =
+            Arc::new(move |segment: TranscriptionSegment| sink.lock().unwrap().push(segment.text));
+
+        let callback = filter(HallucinationAction::Drop).filter_callback(on_segment);
+        for text in [
+            " Hello there.",
+            " Okay.",
+            " Okay.",
+            " Okay.",
+            " Okay.",
+            " Okay.",
+            " Go, go, go, go, go now.",
+            " Thanks for watching!",
+        ] {
+            callback(segment(text, 0.0, 1.0));
+        }
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![" Hello there.", " Okay.", " Okay.", " Okay.", " Go, now."]
+        );
+    }
+
+    #[test]
+    fn test_no_speech() {
+        // One second of tone, then two seconds of silence
+        let mut samples: Vec<f32> = (0..16000)
+            .map(|i| 0.3 * (i as f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin())
+            .collect();
+        samples.resize(48000, 0.0);
+        let audio = AudioData {
+            samples,
+            sample_rate: 16000,
+            duration: 3.0,
+        };
+
+        let segments = vec![segment(" Hello.", 0.0, 1.0), segment(" Bye.", 1.0, 3.0)];
+        let flagged = filter(HallucinationAction::Flag).apply(segments, Some(&audio));
+        assert!(flagged[0].no_speech_prob.unwrap() < 0.2);
+        assert!(flagged[1].no_speech_prob.unwrap() > 0.8);
+        assert_eq!(flagged[0].hallucination, None);
+        assert_eq!(flagged[1].hallucination, Some(HallucinationKind::NoSpeech));
+    }
+}
@@ -2,50 +2,142 @@
 //!
 //! This library provides audio transcription functionality using whisper.cpp and FFmpeg.
 
+pub mod align;
 pub mod audio;
+pub mod bench;
+pub mod cache;
 pub mod config;
+pub mod config_file;
 pub mod dev;
+pub mod document;
 pub mod error;
+pub mod eval;
+pub mod hallucination;
+pub mod live;
 pub mod math;
 pub mod model;
+pub mod output;
+pub mod progress;
+pub mod queue;
+pub mod split;
+mod state_file;
+pub mod text;
+pub mod vad;
 pub mod whisper;
 
-pub use audio::{AudioChunk, AudioProcessor, AudioStream};
+pub use audio::{AudioChunk, AudioProcessor, AudioStream, ClipFormat};
+pub use cache::TranscriptCache;
 pub use config::TranscriptionConfig;
+pub use config_file::ConfigFile;
 pub use dev::{list_devices, Device, SystemInfo};
 pub use error::{Result, WhisperError};
+use futures::StreamExt as _;
+pub use hallucination::{HallucinationAction, HallucinationFilter, HallucinationKind};
 pub use model::{ModelManager, WhisperModel};
+pub use progress::{Progress, ProgressCallback, ProgressStage};
+pub use text::TextProcessing;
 use tokio::try_join;
-use tracing::info;
+use tracing::{info, warn};
 pub use whisper::logging::install_logging_hooks;
 
+use crate::cache::CacheMode;
 use crate::whisper::{
     streaming::StreamWhisperTranscriber, sync::SyncWhisperTranscriber, WhisperTranscriber,
 };
 
 // Re-export public types from whisper module for CLI
 pub use whisper::streaming::StreamingTranscriptionResult;
-pub use whisper::{StreamingChunk, SyncTranscriptionResult};
+pub use whisper::sync::SegmentCallback;
+pub use whisper::{ChunkStatus, SharedModel, StreamingChunk, SyncTranscriptionResult};
 
 /// High-level transcription function
 pub async fn transcribe_file_sync<P: AsRef<std::path::Path>>(
     audio_path: P,
     config: Option<TranscriptionConfig>,
+) -> Result<SyncTranscriptionResult> {
+    transcribe_file(audio_path.as_ref(), config, None, None).await
+}
+
+/// Transcribe a file, reporting the progress of decoding and transcription
+///
+/// Cached results are returned without any progress update.
+pub async fn transcribe_file_sync_with_progress<P: AsRef<std::path::Path>>(
+    audio_path: P,
+    config: Option<TranscriptionConfig>,
+    progress: ProgressCallback,
+) -> Result<SyncTranscriptionResult> {
+    transcribe_file(audio_path.as_ref(), config, Some(progress), None).await
+}
+
+/// Transcribe a file, passing each segment to `on_segment` as soon as it is decoded
+///
+/// The whole file is transcribed at once, as with [`transcribe_file_sync`],
+/// so segments keep their full context. Cached results replay their
+/// segments through the callback.
+pub async fn transcribe_file_sync_with_segments<P: AsRef<std::path::Path>>(
+    audio_path: P,
+    config: Option<TranscriptionConfig>,
+    on_segment: SegmentCallback,
+) -> Result<SyncTranscriptionResult> {
+    transcribe_file(audio_path.as_ref(), config, None, Some(on_segment)).await
+}
+
+async fn transcribe_file(
+    audio_path: &std::path::Path,
+    config: Option<TranscriptionConfig>,
+    progress: Option<ProgressCallback>,
+    on_segment: Option<SegmentCallback>,
 ) -> Result<SyncTranscriptionResult> {
     let config = config.unwrap_or_default();
 
+    let cache = if config.cache {
+        cache::prepare(audio_path, &config, CacheMode::Sync).await
+    } else {
+        None
+    };
+    if let Some((ref cache, ref key)) = cache {
+        if let Some(result) = cache.get(key) {
+            info!("Using cached transcription");
+            if let Some(on_segment) = on_segment {
+                for segment in &result.segments {
+                    on_segment(segment.clone());
+                }
+            }
+            return Ok(result);
+        }
+    }
+
     // Initialize transcriber
-    let transcriber = SyncWhisperTranscriber::from_config(config).await?;
+    let mut transcriber = SyncWhisperTranscriber::from_config(config).await?;
+    if let Some(on_segment) = on_segment {
+        transcriber = transcriber.with_segment_callback(on_segment);
+    }
 
-    info!("Transcribing audio file: {:?}", audio_path.as_ref());
+    info!("Transcribing audio file: {:?}", audio_path);
     // Process audio
     let mut audio_processor = AudioProcessor::new()?;
-    let audio_data = audio_processor.load_audio(audio_path).await?;
+    let audio_data = match progress {
+        Some(progress) => {
+            transcriber = transcriber.with_progress(progress.clone());
+            audio_processor
+                .load_audio_with_progress(audio_path, progress)
+                .await?
+        }
+        None => audio_processor.load_audio(audio_path).await?,
+    };
 
     info!("Audio data loaded, starting transcription...");
 
     // Transcribe
-    transcriber.transcribe(audio_data).await
+    let result = transcriber.transcribe(audio_data).await?;
+
+    if let Some((cache, key)) = cache {
+        if let Err(e) = cache.put(&key, &result) {
+            warn!("Failed to cache transcription: {}", e.report());
+        }
+    }
+
+    Ok(result)
 }
 
 /// True streaming transcription function that processes audio in chunks
@@ -55,6 +147,22 @@ pub async fn transcribe_file_stream<P: AsRef<std::path::Path>>(
 ) -> Result<StreamingTranscriptionResult> {
     let config = config.unwrap_or_default();
 
+    let cache = if config.cache {
+        cache::prepare(audio_path.as_ref(), &config, CacheMode::Streaming).await
+    } else {
+        None
+    };
+    if let Some((ref cache, ref key)) = cache {
+        if let Some(result) = cache.get(key) {
+            info!("Using cached transcription");
+            let chunks = cache::result_to_chunks(&result).into_iter().map(Ok);
+            return Ok(StreamingTranscriptionResult::from_stream(
+                futures::stream::iter(chunks),
+            ));
+        }
+    }
+    let language = config.language.clone();
+
     info!(
         "Starting real-time streaming transcription for: {:?}",
         audio_path.as_ref()
@@ -69,5 +177,27 @@ pub async fn transcribe_file_stream<P: AsRef<std::path::Path>>(
     info!("Audio stream created, starting transcription...");
 
     // Start streaming transcription (consumes both transcriber and stream)
-    transcriber.transcribe(audio_stream).await
+    let stream = transcriber.transcribe(audio_stream).await?;
+
+    let Some((cache, key)) = cache else {
+        return Ok(stream);
+    };
+
+    // Store the result once the last chunk has gone through
+    let mut chunks = Vec::new();
+    Ok(StreamingTranscriptionResult::from_stream(stream.map(
+        move |chunk| {
+            if let Ok(ref chunk) = chunk {
+                chunks.push(chunk.clone());
+                if chunk.final_stats.is_some() {
+                    if let Some(result) = cache::chunks_to_result(&chunks, language.clone()) {
+                        if let Err(e) = cache.put(&key, &result) {
+                            warn!("Failed to cache transcription: {}", e.report());
+                        }
+                    }
+                }
+            }
+            chunk
+        },
+    )))
 }
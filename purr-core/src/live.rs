@@ -0,0 +1,130 @@
+//! Decoding of live audio received in small frames (e.g. over a WebSocket)
+
+use crate::audio::OpusDecoder;
+use crate::error::{Result, WhisperError};
+use serde::{Deserialize, Serialize};
+
+/// Encoding of live audio frames
+///
+/// PCM must already be 16 kHz mono; Opus packets are resampled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveEncoding {
+    /// 16-bit signed little-endian PCM
+    #[default]
+    PcmS16le,
+    /// 32-bit float little-endian PCM
+    PcmF32le,
+    /// One raw Opus packet per frame
+    Opus,
+}
+
+impl LiveEncoding {
+    /// Bytes per PCM sample (`None` for compressed encodings)
+    fn sample_size(self) -> Option<usize> {
+        match self {
+            LiveEncoding::PcmS16le => Some(2),
+            LiveEncoding::PcmF32le => Some(4),
+            LiveEncoding::Opus => None,
+        }
+    }
+}
+
+/// Turns live audio frames into 16 kHz mono `f32` samples
+pub struct LiveDecoder {
+    encoding: LiveEncoding,
+    /// Trailing bytes of a PCM sample split across frames
+    pending: Vec<u8>,
+    opus: Option<OpusDecoder>,
+}
+
+impl LiveDecoder {
+    /// Create a decoder for the given encoding
+    pub fn new(encoding: LiveEncoding) -> Result<Self> {
+        let opus = match encoding {
+            LiveEncoding::Opus => Some(OpusDecoder::new()?),
+            LiveEncoding::PcmS16le | LiveEncoding::PcmF32le => None,
+        };
+
+        Ok(Self {
+            encoding,
+            pending: Vec::new(),
+            opus,
+        })
+    }
+
+    /// Encoding of the frames
+    pub fn encoding(&self) -> LiveEncoding {
+        self.encoding
+    }
+
+    /// Decode one frame
+    pub fn decode(&mut self, frame: &[u8]) -> Result<Vec<f32>> {
+        if let Some(ref mut opus) = self.opus {
+            return opus.decode(frame);
+        }
+
+        let Some(sample_size) = self.encoding.sample_size() else {
+            return Err(WhisperError::AudioProcessing(format!(
+                "No decoder for {:?} audio",
+                self.encoding
+            )));
+        };
+
+        self.pending.extend_from_slice(frame);
+        let complete = self.pending.len() - self.pending.len() % sample_size;
+        let samples = self.pending[..complete]
+            .chunks_exact(sample_size)
+            .map(|bytes| match self.encoding {
+                LiveEncoding::PcmS16le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+                _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            })
+            .collect();
+        self.pending.drain(..complete);
+
+        Ok(samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_s16le_across_frames() {
+        let mut decoder = LiveDecoder::new(LiveEncoding::PcmS16le).unwrap();
+        let bytes: Vec<u8> = [0i16, 16384, -32768]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+
+        // Split in the middle of the second sample
+        let first = decoder.decode(&bytes[..3]).unwrap();
+        let second = decoder.decode(&bytes[3..]).unwrap();
+
+        assert_eq!(first, vec![0.0]);
+        assert_eq!(second, vec![0.5, -1.0]);
+    }
+
+    #[test]
+    fn test_decode_f32le() {
+        let mut decoder = LiveDecoder::new(LiveEncoding::PcmF32le).unwrap();
+        let bytes: Vec<u8> = [0.25f32, -0.75]
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+
+        assert_eq!(decoder.decode(&bytes).unwrap(), vec![0.25, -0.75]);
+        assert!(decoder.decode(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encoding_names() {
+        let encoding: LiveEncoding = serde_json::from_str("\"pcm_f32le\"").unwrap();
+        assert_eq!(encoding, LiveEncoding::PcmF32le);
+        assert_eq!(
+            serde_json::to_string(&LiveEncoding::Opus).unwrap(),
+            "\"opus\""
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Custom and fine-tuned models imported by the user
+//!
+//! Imported models live in the `custom` subdirectory of the models directory as
+//! `<name>.bin`, next to a `<name>.json` file holding their metadata.
+
+use super::ggml::GgmlHeader;
+use super::WhisperModel;
+use crate::error::{Result, WhisperError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Metadata of an imported model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomModel {
+    /// Name used to select the model (e.g. `--model mytuned`)
+    pub name: String,
+
+    /// File the model was imported from
+    pub source: PathBuf,
+
+    /// Import time (seconds since the Unix epoch)
+    pub imported_at: u64,
+
+    /// File size in bytes
+    pub size: u64,
+
+    /// Hyperparameters read from the model header
+    pub header: GgmlHeader,
+}
+
+impl CustomModel {
+    /// Filename of the model weights
+    pub fn filename(&self) -> String {
+        format!("{}.bin", self.name)
+    }
+
+    /// Filename of the metadata file
+    pub fn metadata_filename(&self) -> String {
+        format!("{}.json", self.name)
+    }
+
+    /// Read metadata from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            WhisperError::Configuration(format!(
+                "Invalid custom model metadata {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// Check that a name can be used for a custom or registry model
+///
+/// Names become file names and must not shadow built-in models.
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(WhisperError::Configuration(format!(
+            "Invalid model name '{}': use letters, digits, '-', '_' and '.' only",
+            name
+        )));
+    }
+
+    if WhisperModel::from_str(name).is_ok() {
+        return Err(WhisperError::Configuration(format!(
+            "Model name '{}' is already used by a built-in model",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("mytuned").is_ok());
+        assert!(validate_name("large-v3-de_1.2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name(".hidden").is_err());
+        assert!(validate_name("base").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+//! Resumable, verified downloads of model files
+
+use crate::error::{Result, WhisperError};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use std::fmt;
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+/// Expected digest of a model file (lowercase hex)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelChecksum {
+    Sha1(String),
+    Sha256(String),
+}
+
+impl ModelChecksum {
+    /// Name of the hash algorithm
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            ModelChecksum::Sha1(_) => "sha1",
+            ModelChecksum::Sha256(_) => "sha256",
+        }
+    }
+
+    /// Expected digest in lowercase hex
+    pub fn expected(&self) -> &str {
+        match self {
+            ModelChecksum::Sha1(digest) | ModelChecksum::Sha256(digest) => digest,
+        }
+    }
+
+    /// Hash a file with this checksum's algorithm, returning the lowercase hex digest
+    pub async fn compute<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let path = path.as_ref().to_path_buf();
+        let sha256 = matches!(self, ModelChecksum::Sha256(_));
+
+        tokio::task::spawn_blocking(move || {
+            if sha256 {
+                hash_file::<sha2::Sha256>(&path)
+            } else {
+                hash_file::<sha1::Sha1>(&path)
+            }
+        })
+        .await?
+    }
+
+    /// Check whether a file matches this checksum
+    pub async fn verify<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let actual = self.compute(path).await?;
+        Ok(actual.eq_ignore_ascii_case(self.expected()))
+    }
+}
+
+impl fmt::Display for ModelChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm(), self.expected())
+    }
+}
+
+pub(crate) fn hash_file<D: sha1::Digest>(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Result of rehashing an installed model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// The file matches the known checksum
+    Valid,
+    /// The file does not match the known checksum
+    Mismatch { expected: String, actual: String },
+    /// No checksum is known for this model
+    Unknown,
+}
+
+/// How failed downloads are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled after every failure
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before the given retry (1-based)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Why a single download attempt failed
+enum AttemptError {
+    /// Transient failure, worth retrying (resuming from the partial file)
+    Retry(WhisperError),
+    /// Permanent failure
+    Fatal(WhisperError),
+}
+
+/// Path of the partial download for a destination file
+pub fn partial_path(path: &Path) -> PathBuf {
+    path.with_extension("tmp")
+}
+
+/// Download `url` to `path`, resuming a previous partial download if there is one
+///
+/// Data is written to a `.tmp` file next to `path` which is only renamed into
+/// place once it is complete and matches `checksum` (when given). Transient
+/// failures are retried according to `retry`, each attempt continuing from
+/// where the previous one stopped using an HTTP Range request.
+pub(crate) async fn download_resumable<F>(
+    url: &str,
+    path: &Path,
+    checksum: Option<&ModelChecksum>,
+    retry: &RetryPolicy,
+    progress_callback: &mut F,
+) -> Result<()>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let client = reqwest::Client::new();
+    let temp_path = partial_path(path);
+    let max_attempts = retry.max_attempts.max(1);
+
+    let mut attempt = 1;
+    loop {
+        match download_attempt(&client, url, &temp_path, progress_callback).await {
+            Ok(()) => break,
+            Err(AttemptError::Fatal(e)) => return Err(e),
+            Err(AttemptError::Retry(e)) if attempt >= max_attempts => return Err(e),
+            Err(AttemptError::Retry(e)) => {
+                let delay = retry.backoff(attempt);
+                warn!(
+                    "Download attempt {}/{} failed: {}. Retrying in {:?}...",
+                    attempt, max_attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+    }
+
+    finish_download(url, &temp_path, path, checksum).await
+}
+
+/// Copy a model from a local directory or `file://` mirror, with the same
+/// verification as a download
+pub(crate) async fn copy_local<F>(
+    source: &Path,
+    path: &Path,
+    checksum: Option<&ModelChecksum>,
+    progress_callback: &mut F,
+) -> Result<()>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut input = fs::File::open(source)
+        .await
+        .map_err(|e| WhisperError::io(format!("Failed to open {}", source.display()), e))?;
+    let total_size = input.metadata().await.ok().map(|metadata| metadata.len());
+
+    let temp_path = partial_path(path);
+    let mut output = fs::File::create(&temp_path).await?;
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut copied = 0u64;
+
+    progress_callback(copied, total_size);
+    loop {
+        let read = input.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        output.write_all(&buffer[..read]).await?;
+        copied += read as u64;
+        progress_callback(copied, total_size);
+    }
+    output.flush().await?;
+    drop(output);
+
+    finish_download(&source.display().to_string(), &temp_path, path, checksum).await
+}
+
+/// Verify a completed partial file and move it into place
+async fn finish_download(
+    source: &str,
+    temp_path: &Path,
+    path: &Path,
+    checksum: Option<&ModelChecksum>,
+) -> Result<()> {
+    if let Some(checksum) = checksum {
+        debug!(
+            "Verifying {} checksum of {:?}",
+            checksum.algorithm(),
+            temp_path
+        );
+        let actual = checksum.compute(temp_path).await?;
+        if !actual.eq_ignore_ascii_case(checksum.expected()) {
+            // The partial file is useless now, start over next time
+            let _ = fs::remove_file(temp_path).await;
+            return Err(WhisperError::ChecksumMismatch {
+                url: source.to_string(),
+                expected: checksum.to_string(),
+                actual,
+            });
+        }
+    }
+
+    fs::rename(temp_path, path)
+        .await
+        .map_err(|e| WhisperError::io("Failed to rename downloaded file", e))?;
+
+    Ok(())
+}
+
+async fn download_attempt<F>(
+    client: &reqwest::Client,
+    url: &str,
+    temp_path: &Path,
+    progress_callback: &mut F,
+) -> std::result::Result<(), AttemptError>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let offset = fs::metadata(temp_path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    let mut request = client.get(url);
+    if offset > 0 {
+        debug!("Resuming download of {} from byte {}", url, offset);
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AttemptError::Retry(WhisperError::network(url, e)))?;
+
+    let status = response.status();
+    let resumed = match status {
+        StatusCode::PARTIAL_CONTENT if offset > 0 => true,
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            // The partial file does not fit the remote file anymore
+            let _ = fs::remove_file(temp_path).await;
+            return Err(AttemptError::Retry(WhisperError::DownloadFailed {
+                status: status.as_u16(),
+                url: url.to_string(),
+            }));
+        }
+        status if status.is_success() => false,
+        status
+            if status.is_server_error()
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT =>
+        {
+            return Err(AttemptError::Retry(WhisperError::DownloadFailed {
+                status: status.as_u16(),
+                url: url.to_string(),
+            }));
+        }
+        status => {
+            return Err(AttemptError::Fatal(WhisperError::DownloadFailed {
+                status: status.as_u16(),
+                url: url.to_string(),
+            }));
+        }
+    };
+
+    let (mut file, mut downloaded, total_size) = if resumed {
+        let total = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit('/').next())
+            .and_then(|total| total.parse().ok())
+            .or_else(|| response.content_length().map(|len| len + offset));
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(temp_path)
+            .await
+            .map_err(|e| AttemptError::Fatal(e.into()))?;
+        (file, offset, total)
+    } else {
+        // Either a fresh download or the server ignored the range request
+        let file = fs::File::create(temp_path)
+            .await
+            .map_err(|e| AttemptError::Fatal(e.into()))?;
+        (file, 0, response.content_length())
+    };
+
+    progress_callback(downloaded, total_size);
+
+    let mut stream = response.bytes_stream();
+
+    use futures_util::StreamExt;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // Keep what we have so the next attempt can resume
+                let _ = file.flush().await;
+                return Err(AttemptError::Retry(WhisperError::network(url, e)));
+            }
+        };
+
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| AttemptError::Fatal(e.into()))?;
+
+        downloaded += chunk.len() as u64;
+        progress_callback(downloaded, total_size);
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| AttemptError::Fatal(e.into()))?;
+
+    if let Some(total) = total_size {
+        if downloaded < total {
+            return Err(AttemptError::Retry(WhisperError::io(
+                format!("Download ended early ({} of {} bytes)", downloaded, total),
+                std::io::ErrorKind::UnexpectedEof.into(),
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_checksum_compute() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        std::fs::write(&path, b"abc").unwrap();
+
+        let sha1 = ModelChecksum::Sha1("a9993e364706816aba3e25717850c26c9cd0d89d".to_string());
+        let sha256 = ModelChecksum::Sha256(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string(),
+        );
+
+        assert!(sha1.verify(&path).await.unwrap());
+        assert!(sha256.verify(&path).await.unwrap());
+        assert!(!ModelChecksum::Sha1("00".to_string())
+            .verify(&path)
+            .await
+            .unwrap());
+    }
+}
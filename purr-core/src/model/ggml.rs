@@ -0,0 +1,326 @@
+//! Reading the header of whisper.cpp ggml model files
+//!
+//! A ggml model starts with a 4-byte magic followed by eleven little-endian
+//! `i32` hyperparameters. That is enough to tell the architecture, vocabulary
+//! and quantization of a model without loading it.
+//!
+//! GGUF files, the format of newer ggml-based projects, are recognised from
+//! their header but cannot be loaded by whisper.cpp.
+
+use crate::error::{Result, WhisperError};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+/// Magic number at the start of ggml model files ("ggml")
+pub const GGML_MAGIC: u32 = 0x6767_6d6c;
+
+/// Magic bytes at the start of GGUF files
+pub const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// Vocabulary size from which a model is multilingual (English-only models use 51864)
+const MULTILINGUAL_VOCAB: i32 = 51865;
+
+/// Factor encoding the quantization version in `ftype`
+const QNT_VERSION_FACTOR: i32 = 1000;
+
+/// Hyperparameters stored in a ggml model header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GgmlHeader {
+    pub n_vocab: i32,
+    pub n_audio_ctx: i32,
+    pub n_audio_state: i32,
+    pub n_audio_head: i32,
+    pub n_audio_layer: i32,
+    pub n_text_ctx: i32,
+    pub n_text_state: i32,
+    pub n_text_head: i32,
+    pub n_text_layer: i32,
+    pub n_mels: i32,
+    pub ftype: i32,
+}
+
+impl GgmlHeader {
+    /// Size of the header in bytes, including the magic
+    pub const SIZE: usize = 4 + 11 * 4;
+
+    /// Read the header of a model file
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut bytes = [0u8; Self::SIZE];
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut bytes))
+            .map_err(|e| {
+                WhisperError::io(format!("Failed to read model header {}", path.display()), e)
+            })?;
+
+        Self::parse(&bytes)
+    }
+
+    /// Parse a header from the first [`GgmlHeader::SIZE`] bytes of a model file
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.starts_with(GGUF_MAGIC) {
+            let description = match GgufHeader::parse(bytes) {
+                Ok(gguf) => gguf.to_string(),
+                Err(_) => "GGUF file".to_string(),
+            };
+            return Err(WhisperError::InvalidModel(format!(
+                "{} is not supported by whisper.cpp, convert the model to ggml first",
+                description
+            )));
+        }
+        if bytes.len() < Self::SIZE {
+            return Err(WhisperError::InvalidModel(
+                "Model file is too short to be a ggml model".to_string(),
+            ));
+        }
+
+        let field = |i: usize| {
+            let offset = 4 + i * 4;
+            i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+        };
+
+        let magic = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+        if magic != GGML_MAGIC {
+            return Err(WhisperError::InvalidModel(format!(
+                "Not a ggml model file (bad magic 0x{:08x})",
+                magic
+            )));
+        }
+
+        Ok(Self {
+            n_vocab: field(0),
+            n_audio_ctx: field(1),
+            n_audio_state: field(2),
+            n_audio_head: field(3),
+            n_audio_layer: field(4),
+            n_text_ctx: field(5),
+            n_text_state: field(6),
+            n_text_head: field(7),
+            n_text_layer: field(8),
+            n_mels: field(9),
+            ftype: field(10),
+        })
+    }
+
+    /// Whether the model supports languages other than English
+    pub fn is_multilingual(&self) -> bool {
+        self.n_vocab >= MULTILINGUAL_VOCAB
+    }
+
+    /// Model size class, derived from the number of encoder layers
+    pub fn model_type(&self) -> &'static str {
+        match self.n_audio_layer {
+            4 => "tiny",
+            6 => "base",
+            12 => "small",
+            24 => "medium",
+            32 => "large",
+            _ => "unknown",
+        }
+    }
+
+    /// Quantization version (0 for unquantized models)
+    pub fn quantization_version(&self) -> i32 {
+        self.ftype / QNT_VERSION_FACTOR
+    }
+
+    /// Tensor type of the weights (e.g. `f16`, `q5_0`)
+    pub fn quantization(&self) -> &'static str {
+        match self.ftype % QNT_VERSION_FACTOR {
+            0 => "f32",
+            1 => "f16",
+            2 => "q4_0",
+            3 => "q4_1",
+            4 => "q4_1 (some f16)",
+            7 => "q8_0",
+            8 => "q5_0",
+            9 => "q5_1",
+            10 => "q2_k",
+            11 => "q3_k",
+            12 => "q4_k",
+            13 => "q5_k",
+            14 => "q6_k",
+            _ => "unknown",
+        }
+    }
+
+    /// Whether the weights are quantized
+    pub fn is_quantized(&self) -> bool {
+        !matches!(self.ftype % QNT_VERSION_FACTOR, 0 | 1)
+    }
+
+    /// Rough memory needed to run the model, in bytes
+    ///
+    /// Adds the weights (`weights_size`, usually the file size), the f16
+    /// self-attention and cross-attention KV caches, and the largest f32 compute
+    /// buffers: encoder attention scores, encoder activations and the decoder
+    /// logits. Real usage depends on the backend and on beam search settings.
+    pub fn estimated_memory(&self, weights_size: u64) -> u64 {
+        const F16: u64 = 2;
+        const F32: u64 = 4;
+        let dim = |value: i32| value.max(0) as u64;
+
+        let kv_self = 2 * dim(self.n_text_layer) * dim(self.n_text_ctx) * dim(self.n_text_state);
+        let kv_cross = 2 * dim(self.n_text_layer) * dim(self.n_audio_ctx) * dim(self.n_text_state);
+        let attention = dim(self.n_audio_head) * dim(self.n_audio_ctx).pow(2);
+        let activations = 16 * dim(self.n_audio_ctx) * dim(self.n_audio_state);
+        let logits = dim(self.n_vocab) * dim(self.n_text_ctx);
+
+        weights_size + F16 * (kv_self + kv_cross) + F32 * (attention + activations + logits)
+    }
+}
+
+/// Header of a GGUF file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GgufHeader {
+    pub version: u32,
+    pub tensor_count: u64,
+    pub metadata_count: u64,
+}
+
+impl GgufHeader {
+    /// Size of the header in bytes, including the magic (GGUF version 2 and later)
+    pub const SIZE: usize = 4 + 4 + 8 + 8;
+
+    /// Read the header of a GGUF file
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        std::fs::File::open(path)
+            .and_then(|file| file.take(Self::SIZE as u64).read_to_end(&mut bytes))
+            .map_err(|e| {
+                WhisperError::io(format!("Failed to read model header {}", path.display()), e)
+            })?;
+
+        Self::parse(&bytes)
+    }
+
+    /// Parse a header from the first bytes of a GGUF file
+    ///
+    /// Version 1 stores the counts as `u32`, later versions as `u64`.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        if !bytes.starts_with(GGUF_MAGIC) {
+            return Err(WhisperError::InvalidModel("Not a GGUF file".to_string()));
+        }
+        let too_short = || WhisperError::InvalidModel("GGUF header is truncated".to_string());
+
+        let u32_at = |offset: usize| {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        };
+        let u64_at = |offset: usize| {
+            bytes
+                .get(offset..offset + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        };
+
+        let version = u32_at(4).ok_or_else(too_short)?;
+        let (tensor_count, metadata_count) = if version == 1 {
+            (u32_at(8).map(u64::from), u32_at(12).map(u64::from))
+        } else {
+            (u64_at(8), u64_at(16))
+        };
+
+        Ok(Self {
+            version,
+            tensor_count: tensor_count.ok_or_else(too_short)?,
+            metadata_count: metadata_count.ok_or_else(too_short)?,
+        })
+    }
+}
+
+impl std::fmt::Display for GgufHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GGUF version {} file ({} tensors, {} metadata keys)",
+            self.version, self.tensor_count, self.metadata_count
+        )
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a ggml header for the given hyperparameters
+    pub(crate) fn header_bytes(fields: [i32; 11]) -> Vec<u8> {
+        let mut bytes = GGML_MAGIC.to_le_bytes().to_vec();
+        for field in fields {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let bytes = header_bytes([51865, 1500, 512, 8, 6, 448, 512, 8, 6, 80, 1009]);
+        let header = GgmlHeader::parse(&bytes).unwrap();
+
+        assert_eq!(header.n_vocab, 51865);
+        assert_eq!(header.n_mels, 80);
+        assert_eq!(header.model_type(), "base");
+        assert!(header.is_multilingual());
+        assert_eq!(header.quantization(), "q5_1");
+        assert_eq!(header.quantization_version(), 1);
+        assert!(header.is_quantized());
+    }
+
+    #[test]
+    fn test_estimated_memory() {
+        let bytes = header_bytes([51865, 1500, 512, 8, 6, 448, 512, 8, 6, 80, 1]);
+        let header = GgmlHeader::parse(&bytes).unwrap();
+        let weights = 147_951_465;
+
+        // whisper.cpp allocates about 375 MB for base (weights, KV caches and compute buffers)
+        let estimate = header.estimated_memory(weights);
+        assert!(
+            estimate > 330_000_000 && estimate < 450_000_000,
+            "{}",
+            estimate
+        );
+        assert!(header.estimated_memory(0) < estimate);
+    }
+
+    #[test]
+    fn test_english_only_header() {
+        let bytes = header_bytes([51864, 1500, 384, 6, 4, 448, 384, 6, 4, 80, 1]);
+        let header = GgmlHeader::parse(&bytes).unwrap();
+
+        assert_eq!(header.model_type(), "tiny");
+        assert!(!header.is_multilingual());
+        assert_eq!(header.quantization(), "f16");
+        assert!(!header.is_quantized());
+    }
+
+    #[test]
+    fn test_gguf_header() {
+        let mut bytes = b"GGUF".to_vec();
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&245u64.to_le_bytes());
+        bytes.extend_from_slice(&21u64.to_le_bytes());
+
+        let header = GgufHeader::parse(&bytes).unwrap();
+        assert_eq!(header.version, 3);
+        assert_eq!(header.tensor_count, 245);
+        assert_eq!(header.metadata_count, 21);
+
+        let error = GgmlHeader::parse(&bytes).unwrap_err().to_string();
+        assert!(
+            error.contains("GGUF version 3 file (245 tensors"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert!(GgmlHeader::parse(b"GGUF\x03\x00\x00\x00").is_err());
+        assert!(GgufHeader::parse(b"GGUF\x03\x00\x00\x00").is_err());
+        assert!(GgufHeader::parse(&GGML_MAGIC.to_le_bytes()).is_err());
+        assert!(GgmlHeader::parse(&[0u8; GgmlHeader::SIZE]).is_err());
+        assert!(GgmlHeader::parse(&GGML_MAGIC.to_le_bytes()).is_err());
+    }
+}
@@ -0,0 +1,1252 @@
+//! Model downloading and management functionality
+
+pub mod custom;
+pub mod download;
+pub mod ggml;
+pub mod quantize;
+pub mod registry;
+pub mod select;
+pub mod usage;
+
+use crate::error::{Result, WhisperError};
+use crate::math::ByteSpeed;
+use core::str;
+pub use custom::CustomModel;
+use directories::ProjectDirs;
+pub use download::{ModelChecksum, RetryPolicy, VerifyStatus};
+pub use ggml::{GgmlHeader, GgufHeader};
+pub use quantize::{QuantizationType, QuantizeStats};
+pub use registry::{Location, ModelSettings, RegistryModel};
+pub use select::{HardwareProfile, ModelFamily, ModelSelection, Quality, SelectionCriteria};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::fs;
+use tracing::{debug, info, warn};
+pub use usage::{ModelFile, ModelFileKind, PruneOptions, UsageStore};
+
+/// Available Whisper model types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WhisperModel {
+    /// Tiny model (39 MB, fastest)
+    Tiny,
+    /// Tiny English-only model (39 MB)
+    TinyEn,
+    /// Tiny quantized Q5_1 (31 MB)
+    TinyQ5_1,
+    /// Tiny English-only quantized Q5_1 (31 MB)
+    TinyEnQ5_1,
+    /// Tiny quantized Q8_0 (42 MB)
+    TinyQ8_0,
+    /// Base model (142 MB, good balance)
+    Base,
+    /// Base English-only model (142 MB)
+    BaseEn,
+    /// Base quantized Q5_1 (103 MB)
+    BaseQ5_1,
+    /// Base English-only quantized Q5_1 (103 MB)
+    BaseEnQ5_1,
+    /// Base quantized Q8_0 (149 MB)
+    BaseQ8_0,
+    /// Small model (466 MB)
+    Small,
+    /// Small English-only model (466 MB)
+    SmallEn,
+    /// Small English-only TinyDiarize model
+    SmallEnTdrz,
+    /// Small quantized Q5_1 (340 MB)
+    SmallQ5_1,
+    /// Small English-only quantized Q5_1 (340 MB)
+    SmallEnQ5_1,
+    /// Small quantized Q8_0 (488 MB)
+    SmallQ8_0,
+    /// Medium model (1.5 GB)
+    Medium,
+    /// Medium English-only model (1.5 GB)
+    MediumEn,
+    /// Medium quantized Q5_0 (1.1 GB)
+    MediumQ5_0,
+    /// Medium English-only quantized Q5_0 (1.1 GB)
+    MediumEnQ5_0,
+    /// Medium quantized Q8_0 (1.6 GB)
+    MediumQ8_0,
+    /// Large v1 model (3.0 GB)
+    LargeV1,
+    /// Large v2 model (3.0 GB)
+    LargeV2,
+    /// Large v2 quantized Q5_0 (2.3 GB)
+    LargeV2Q5_0,
+    /// Large v2 quantized Q8_0 (3.2 GB)
+    LargeV2Q8_0,
+    /// Large v3 model (3.0 GB, most accurate)
+    LargeV3,
+    /// Large v3 quantized Q5_0 (2.3 GB)
+    LargeV3Q5_0,
+    /// Large v3 Turbo model (1.5 GB, faster)
+    LargeV3Turbo,
+    /// Large v3 Turbo quantized Q5_0 (1.2 GB)
+    LargeV3TurboQ5_0,
+    /// Large v3 Turbo quantized Q8_0 (1.6 GB)
+    LargeV3TurboQ8_0,
+}
+
+impl WhisperModel {
+    /// Get the model identifier string used in filenames and URLs
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "tiny",
+            WhisperModel::TinyEn => "tiny.en",
+            WhisperModel::TinyQ5_1 => "tiny-q5_1",
+            WhisperModel::TinyEnQ5_1 => "tiny.en-q5_1",
+            WhisperModel::TinyQ8_0 => "tiny-q8_0",
+            WhisperModel::Base => "base",
+            WhisperModel::BaseEn => "base.en",
+            WhisperModel::BaseQ5_1 => "base-q5_1",
+            WhisperModel::BaseEnQ5_1 => "base.en-q5_1",
+            WhisperModel::BaseQ8_0 => "base-q8_0",
+            WhisperModel::Small => "small",
+            WhisperModel::SmallEn => "small.en",
+            WhisperModel::SmallEnTdrz => "small.en-tdrz",
+            WhisperModel::SmallQ5_1 => "small-q5_1",
+            WhisperModel::SmallEnQ5_1 => "small.en-q5_1",
+            WhisperModel::SmallQ8_0 => "small-q8_0",
+            WhisperModel::Medium => "medium",
+            WhisperModel::MediumEn => "medium.en",
+            WhisperModel::MediumQ5_0 => "medium-q5_0",
+            WhisperModel::MediumEnQ5_0 => "medium.en-q5_0",
+            WhisperModel::MediumQ8_0 => "medium-q8_0",
+            WhisperModel::LargeV1 => "large-v1",
+            WhisperModel::LargeV2 => "large-v2",
+            WhisperModel::LargeV2Q5_0 => "large-v2-q5_0",
+            WhisperModel::LargeV2Q8_0 => "large-v2-q8_0",
+            WhisperModel::LargeV3 => "large-v3",
+            WhisperModel::LargeV3Q5_0 => "large-v3-q5_0",
+            WhisperModel::LargeV3Turbo => "large-v3-turbo",
+            WhisperModel::LargeV3TurboQ5_0 => "large-v3-turbo-q5_0",
+            WhisperModel::LargeV3TurboQ8_0 => "large-v3-turbo-q8_0",
+        }
+    }
+
+    /// Get the model description
+    pub const fn description(&self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "Tiny model (39 MB, fastest, lowest accuracy)",
+            WhisperModel::TinyEn => "Tiny English-only model (39 MB, fastest for English)",
+            WhisperModel::TinyQ5_1 => "Tiny quantized Q5_1 (31 MB, fastest)",
+            WhisperModel::TinyEnQ5_1 => "Tiny English-only quantized Q5_1 (31 MB)",
+            WhisperModel::TinyQ8_0 => "Tiny quantized Q8_0 (42 MB)",
+            WhisperModel::Base => "Base model (142 MB, good balance of speed and accuracy)",
+            WhisperModel::BaseEn => "Base English-only model (142 MB, good for English)",
+            WhisperModel::BaseQ5_1 => "Base quantized Q5_1 (103 MB)",
+            WhisperModel::BaseEnQ5_1 => "Base English-only quantized Q5_1 (103 MB)",
+            WhisperModel::BaseQ8_0 => "Base quantized Q8_0 (149 MB)",
+            WhisperModel::Small => "Small model (466 MB, good accuracy)",
+            WhisperModel::SmallEn => "Small English-only model (466 MB)",
+            WhisperModel::SmallEnTdrz => {
+                "Small English-only TinyDiarize model (speaker diarization)"
+            }
+            WhisperModel::SmallQ5_1 => "Small quantized Q5_1 (340 MB)",
+            WhisperModel::SmallEnQ5_1 => "Small English-only quantized Q5_1 (340 MB)",
+            WhisperModel::SmallQ8_0 => "Small quantized Q8_0 (488 MB)",
+            WhisperModel::Medium => "Medium model (1.5 GB, high accuracy)",
+            WhisperModel::MediumEn => "Medium English-only model (1.5 GB)",
+            WhisperModel::MediumQ5_0 => "Medium quantized Q5_0 (1.1 GB)",
+            WhisperModel::MediumEnQ5_0 => "Medium English-only quantized Q5_0 (1.1 GB)",
+            WhisperModel::MediumQ8_0 => "Medium quantized Q8_0 (1.6 GB)",
+            WhisperModel::LargeV1 => "Large v1 model (3.0 GB, highest accuracy)",
+            WhisperModel::LargeV2 => "Large v2 model (3.0 GB, improved accuracy)",
+            WhisperModel::LargeV2Q5_0 => "Large v2 quantized Q5_0 (2.3 GB)",
+            WhisperModel::LargeV2Q8_0 => "Large v2 quantized Q8_0 (3.2 GB)",
+            WhisperModel::LargeV3 => "Large v3 model (3.0 GB, most accurate)",
+            WhisperModel::LargeV3Q5_0 => "Large v3 quantized Q5_0 (2.3 GB)",
+            WhisperModel::LargeV3Turbo => "Large v3 Turbo model (1.5 GB, faster large model)",
+            WhisperModel::LargeV3TurboQ5_0 => "Large v3 Turbo quantized Q5_0 (1.2 GB)",
+            WhisperModel::LargeV3TurboQ8_0 => "Large v3 Turbo quantized Q8_0 (1.6 GB)",
+        }
+    }
+
+    /// Get all available models
+    pub const fn all_models() -> &'static [WhisperModel] {
+        &[
+            WhisperModel::Tiny,
+            WhisperModel::TinyEn,
+            WhisperModel::TinyQ5_1,
+            WhisperModel::TinyEnQ5_1,
+            WhisperModel::TinyQ8_0,
+            WhisperModel::Base,
+            WhisperModel::BaseEn,
+            WhisperModel::BaseQ5_1,
+            WhisperModel::BaseEnQ5_1,
+            WhisperModel::BaseQ8_0,
+            WhisperModel::Small,
+            WhisperModel::SmallEn,
+            WhisperModel::SmallEnTdrz,
+            WhisperModel::SmallQ5_1,
+            WhisperModel::SmallEnQ5_1,
+            WhisperModel::SmallQ8_0,
+            WhisperModel::Medium,
+            WhisperModel::MediumEn,
+            WhisperModel::MediumQ5_0,
+            WhisperModel::MediumEnQ5_0,
+            WhisperModel::MediumQ8_0,
+            WhisperModel::LargeV1,
+            WhisperModel::LargeV2,
+            WhisperModel::LargeV2Q5_0,
+            WhisperModel::LargeV2Q8_0,
+            WhisperModel::LargeV3,
+            WhisperModel::LargeV3Q5_0,
+            WhisperModel::LargeV3Turbo,
+            WhisperModel::LargeV3TurboQ5_0,
+            WhisperModel::LargeV3TurboQ8_0,
+        ]
+    }
+
+    pub const fn size(&self) -> u64 {
+        match self {
+            WhisperModel::Tiny => 39 * 1024 * 1024,        // 39 MB
+            WhisperModel::TinyEn => 39 * 1024 * 1024,      // 39 MB
+            WhisperModel::TinyQ5_1 => 31 * 1024 * 1024,    // 31 MB
+            WhisperModel::TinyEnQ5_1 => 31 * 1024 * 1024,  // 31 MB
+            WhisperModel::TinyQ8_0 => 42 * 1024 * 1024,    // 42 MB
+            WhisperModel::Base => 142 * 1024 * 1024,       // 142 MB
+            WhisperModel::BaseEn => 142 * 1024 * 1024,     // 142 MB
+            WhisperModel::BaseQ5_1 => 103 * 1024 * 1024,   // 103 MB
+            WhisperModel::BaseEnQ5_1 => 103 * 1024 * 1024, // 103 MB
+            WhisperModel::BaseQ8_0 => 149 * 1024 * 1024,   // 149 MB
+            WhisperModel::Small => 466 * 1024 * 1024,      // 466 MB
+            WhisperModel::SmallEn => 466 * 1024 * 1024,    // 466 MB
+            // TinyDiarize model size is not specified, assuming similar to SmallEn
+            // Adjust if actual size is known
+            WhisperModel::SmallEnTdrz => {
+                466 * 1024 * 1024 // Assuming ~50MB extra for diarization features
+            }
+            WhisperModel::SmallQ5_1 => 340 * 1024 * 1024, // 340 MB
+            WhisperModel::SmallEnQ5_1 => 340 * 1024 * 1024, // 340 MB
+            WhisperModel::SmallQ8_0 => 488 * 1024 * 1024, // 488 MB
+            WhisperModel::Medium => 1_500 * 1024 * 1024,  // 1.5 GB
+            WhisperModel::MediumEn => 1_500 * 1024 * 1024, // 1.5 GB
+            WhisperModel::MediumQ5_0 => 1_100 * 1024 * 1024, // 1.1 GB
+            WhisperModel::MediumEnQ5_0 => 1_100 * 1024 * 1024, // 1.1 GB
+            WhisperModel::MediumQ8_0 => 1_600 * 1024 * 1024, // 1.6 GB
+            WhisperModel::LargeV1 => 3_000 * 1024 * 1024, // 3.0 GB
+            WhisperModel::LargeV2 => 3_000 * 1024 * 1024, // 3.0 GB
+            WhisperModel::LargeV2Q5_0 => 2_300 * 1024 * 1024, // 2.3 GB
+            WhisperModel::LargeV2Q8_0 => 3_200 * 1024 * 1024, // 3.2 GB
+            WhisperModel::LargeV3 => 3_000 * 1024 * 1024, // 3.0 GB
+            WhisperModel::LargeV3Q5_0 => 2_300 * 1024 * 1024, // 2.3 GB
+            WhisperModel::LargeV3Turbo => 1_500 * 1024 * 1024, // 1.5 GB
+            WhisperModel::LargeV3TurboQ5_0 => 1_200 * 1024 * 1024, // 1.2 GB
+            WhisperModel::LargeV3TurboQ8_0 => 1_600 * 1024 * 1024, // 1.6 GB
+        }
+    }
+
+    /// Get the architecture size class of the model
+    pub fn family(&self) -> ModelFamily {
+        let name = self.as_str();
+        if name.starts_with("tiny") {
+            ModelFamily::Tiny
+        } else if name.starts_with("base") {
+            ModelFamily::Base
+        } else if name.starts_with("small") {
+            ModelFamily::Small
+        } else if name.starts_with("medium") {
+            ModelFamily::Medium
+        } else if name.contains("turbo") {
+            ModelFamily::LargeTurbo
+        } else {
+            ModelFamily::Large
+        }
+    }
+
+    /// Whether the model only transcribes English
+    pub fn is_english_only(&self) -> bool {
+        self.as_str().contains(".en")
+    }
+
+    /// Whether the model weights are quantized
+    pub fn is_quantized(&self) -> bool {
+        self.as_str().contains("-q")
+    }
+
+    /// Rough memory needed to run the model, see [`GgmlHeader::estimated_memory`]
+    pub fn estimated_memory(&self) -> u64 {
+        self.family().header().estimated_memory(self.size())
+    }
+
+    pub fn estimated_download_time(&self, speed: ByteSpeed) -> Duration {
+        // Calculate time in seconds
+        self.size() as usize / speed
+    }
+
+    /// Get the download URL for this model
+    fn get_url(&self) -> String {
+        let base_url = if self.as_str().contains("tdrz") {
+            "https://huggingface.co/akashmjn/tinydiarize-whisper.cpp/resolve/main"
+        } else {
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main"
+        };
+
+        format!("{}/ggml-{}.bin", base_url, self.as_str())
+    }
+
+    /// Get the filename for this model
+    pub fn filename(&self) -> String {
+        format!("ggml-{}.bin", self.as_str())
+    }
+
+    /// Known checksum of the model file, as published by whisper.cpp
+    pub fn checksum(&self) -> Option<ModelChecksum> {
+        let sha1 = match self {
+            WhisperModel::Tiny => "bd577a113a864445d4c299885e0cb97d4ba92b5f",
+            WhisperModel::TinyEn => "c78c86eb1a8faa21b369bcd33207cc90d64ae9df",
+            WhisperModel::TinyQ5_1 => "2827a03e495b1ed3048ef28a6a4620537db4ee51",
+            WhisperModel::TinyEnQ5_1 => "3fb92ec865cbbc769f08137f22470d6b66e071b6",
+            WhisperModel::TinyQ8_0 => "19e8118f6652a650569f5a949d962154e01571d9",
+            WhisperModel::Base => "465707469ff3a37a2b9b8d8f89f2f99de7299dac",
+            WhisperModel::BaseEn => "137c40403d78fd54d454da0f9bd998f78703390c",
+            WhisperModel::BaseQ5_1 => "a3733eda680ef76256db5fc5dd9de8629e62c5e7",
+            WhisperModel::BaseEnQ5_1 => "d26d7ce5a1b6e57bea5d0431b9c20ae49423c94a",
+            WhisperModel::BaseQ8_0 => "7bb89bb49ed6955013b166f1b6a6c04584a20fbe",
+            WhisperModel::Small => "55356645c2b361a969dfd0ef2c5a50d530afd8d5",
+            WhisperModel::SmallEn => "db8a495a91d927739e50b3fc1cc4c6b8f6c2d022",
+            WhisperModel::SmallEnTdrz => "b6c6e7e89af1a35c08e6de56b66ca6a02a2fdfa1",
+            WhisperModel::SmallQ5_1 => "6fe57ddcfdd1c6b07cdcc73aaf620810ce5fc771",
+            WhisperModel::SmallEnQ5_1 => "20f54878d608f94e4a8ee3ae56016571d47cba34",
+            WhisperModel::SmallQ8_0 => "bcad8a2083f4e53d648d586b7dbc0cd673d8afad",
+            WhisperModel::Medium => "fd9727b6e1217c2f614f9b698455c4ffd82463b4",
+            WhisperModel::MediumEn => "8c30f0e44ce9560643ebd10bbe50cd20eafd3723",
+            WhisperModel::MediumQ5_0 => "7718d4c1ec62ca96998f058114db418236937276",
+            WhisperModel::MediumEnQ5_0 => "bb3b5281bddd61605d6fc76bc5b92d8f20284c3b",
+            WhisperModel::MediumQ8_0 => "e66645948aff4bebbec71b3485c576f3d63af5d6",
+            WhisperModel::LargeV1 => "b1caaf735c4cc1429223d5a74f0f4d0b9b59a299",
+            WhisperModel::LargeV2 => "0f4c8e34f21cf1a914c59d8b3ce882345ad349d6",
+            WhisperModel::LargeV2Q5_0 => "00e39f2196344e901b3a2bd5814807a769bd1630",
+            WhisperModel::LargeV2Q8_0 => "da97d6ca8f8ffbeeb5fd147f79010eeea194ba38",
+            WhisperModel::LargeV3 => "ad82bf6a9043ceed055076d0fd39f5f186ff8062",
+            WhisperModel::LargeV3Q5_0 => "e6e2ed78495d403bef4b7cff42ef4aaadcfea8de",
+            WhisperModel::LargeV3Turbo => "4af2b29d7ec73d781377bfd1758ca957a807e941",
+            WhisperModel::LargeV3TurboQ5_0 => "e050f7970618a659205450ad97eb95a18d69c9ee",
+            WhisperModel::LargeV3TurboQ8_0 => "01bf15bedffe9f39d65c1b6ff9b687ea91f59e0e",
+        };
+
+        Some(ModelChecksum::Sha1(sha1.to_string()))
+    }
+}
+
+impl FromStr for WhisperModel {
+    type Err = WhisperError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tiny" => Ok(WhisperModel::Tiny),
+            "tiny.en" => Ok(WhisperModel::TinyEn),
+            "tiny-q5_1" => Ok(WhisperModel::TinyQ5_1),
+            "tiny.en-q5_1" => Ok(WhisperModel::TinyEnQ5_1),
+            "tiny-q8_0" => Ok(WhisperModel::TinyQ8_0),
+            "base" => Ok(WhisperModel::Base),
+            "base.en" => Ok(WhisperModel::BaseEn),
+            "base-q5_1" => Ok(WhisperModel::BaseQ5_1),
+            "base.en-q5_1" => Ok(WhisperModel::BaseEnQ5_1),
+            "base-q8_0" => Ok(WhisperModel::BaseQ8_0),
+            "small" => Ok(WhisperModel::Small),
+            "small.en" => Ok(WhisperModel::SmallEn),
+            "small.en-tdrz" => Ok(WhisperModel::SmallEnTdrz),
+            "small-q5_1" => Ok(WhisperModel::SmallQ5_1),
+            "small.en-q5_1" => Ok(WhisperModel::SmallEnQ5_1),
+            "small-q8_0" => Ok(WhisperModel::SmallQ8_0),
+            "medium" => Ok(WhisperModel::Medium),
+            "medium.en" => Ok(WhisperModel::MediumEn),
+            "medium-q5_0" => Ok(WhisperModel::MediumQ5_0),
+            "medium.en-q5_0" => Ok(WhisperModel::MediumEnQ5_0),
+            "medium-q8_0" => Ok(WhisperModel::MediumQ8_0),
+            "large-v1" => Ok(WhisperModel::LargeV1),
+            "large-v2" => Ok(WhisperModel::LargeV2),
+            "large-v2-q5_0" => Ok(WhisperModel::LargeV2Q5_0),
+            "large-v2-q8_0" => Ok(WhisperModel::LargeV2Q8_0),
+            "large-v3" => Ok(WhisperModel::LargeV3),
+            "large-v3-q5_0" => Ok(WhisperModel::LargeV3Q5_0),
+            "large-v3-turbo" => Ok(WhisperModel::LargeV3Turbo),
+            "large-v3-turbo-q5_0" => Ok(WhisperModel::LargeV3TurboQ5_0),
+            "large-v3-turbo-q8_0" => Ok(WhisperModel::LargeV3TurboQ8_0),
+            _ => Err(WhisperError::Configuration(format!(
+                "Unknown Whisper model: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Model manager for downloading and managing Whisper models
+pub struct ModelManager {
+    models_dir: PathBuf,
+    retry_policy: RetryPolicy,
+    settings: ModelSettings,
+}
+
+impl ModelManager {
+    /// Create a new model manager
+    pub fn new() -> Result<Self> {
+        let project_dirs = ProjectDirs::from("dev.gaetans", "", "purr").ok_or_else(|| {
+            WhisperError::Configuration("Failed to get XDG directories".to_string())
+        })?;
+
+        let models_dir = project_dirs.data_dir().join("models");
+
+        Ok(Self::with_models_dir(models_dir).with_settings(ModelSettings::load()?))
+    }
+
+    /// Create a model manager using a custom models directory
+    pub fn with_models_dir<P: Into<PathBuf>>(models_dir: P) -> Self {
+        Self {
+            models_dir: models_dir.into(),
+            retry_policy: RetryPolicy::default(),
+            settings: ModelSettings::default(),
+        }
+    }
+
+    /// Set the mirror and registry to download from
+    pub fn with_settings(mut self, settings: ModelSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Get the mirror and registry settings
+    pub fn settings(&self) -> &ModelSettings {
+        &self.settings
+    }
+
+    /// Set how failed downloads are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Get the models directory path
+    pub fn models_dir(&self) -> &Path {
+        &self.models_dir
+    }
+
+    /// Ensure the models directory exists
+    pub async fn ensure_models_dir(&self) -> Result<()> {
+        fs::create_dir_all(&self.models_dir)
+            .await
+            .map_err(|e| WhisperError::io("Failed to create models directory", e))?;
+        Ok(())
+    }
+
+    /// Check if a model is already downloaded
+    pub async fn is_model_downloaded(&self, model: WhisperModel) -> bool {
+        let model_path = self.get_model_path(model);
+        model_path.exists()
+    }
+
+    /// Get the full path to a model file
+    pub fn get_model_path(&self, model: WhisperModel) -> PathBuf {
+        self.models_dir.join(model.filename())
+    }
+
+    /// Assign a model path to a transcription configuration
+    pub fn assign_model_path(
+        &self,
+        config: &mut crate::config::TranscriptionConfig,
+        model: WhisperModel,
+    ) {
+        config.model_path = Some(self.get_model_path(model));
+    }
+
+    /// Find the first available model (for auto-selection)
+    pub async fn find_first_available_model(&self) -> Option<PathBuf> {
+        // Check recommended models in order of preference
+        let recommended = [
+            WhisperModel::Base,
+            WhisperModel::BaseEn,
+            WhisperModel::Small,
+            WhisperModel::SmallEn,
+            WhisperModel::Tiny,
+            WhisperModel::TinyEn,
+        ];
+
+        for model in recommended {
+            if self.is_model_downloaded(model).await {
+                return Some(self.get_model_path(model));
+            }
+        }
+
+        // Check any available model
+        for &model in WhisperModel::all_models() {
+            if self.is_model_downloaded(model).await {
+                return Some(self.get_model_path(model));
+            }
+        }
+
+        None
+    }
+
+    /// Download a model
+    pub async fn download_model(&self, model: WhisperModel) -> Result<PathBuf> {
+        self.download_model_with_progress(model, |_, _| {}).await
+    }
+
+    /// Download a model with progress callback
+    pub async fn download_model_with_progress<F>(
+        &self,
+        model: WhisperModel,
+        mut progress_callback: F,
+    ) -> Result<PathBuf>
+    where
+        F: FnMut(u64, Option<u64>), // (downloaded_bytes, total_bytes)
+    {
+        let model_path = self.get_model_path(model);
+
+        debug!("Downloading model {} to {:?}", model.as_str(), model_path);
+
+        self.fetch(
+            &self.model_location(model),
+            &model_path,
+            model.checksum().as_ref(),
+            &mut progress_callback,
+        )
+        .await?;
+
+        debug!(
+            "Successfully downloaded model {} to {:?}",
+            model.as_str(),
+            model_path
+        );
+        Ok(model_path)
+    }
+
+    /// Download a file into the models directory
+    ///
+    /// Interrupted downloads are resumed from the partial `.tmp` file, transient
+    /// failures are retried with backoff, and the result is checked against
+    /// `checksum` before being moved into place.
+    pub async fn download_file<F>(
+        &self,
+        url: &str,
+        path: &Path,
+        checksum: Option<&ModelChecksum>,
+        mut progress_callback: F,
+    ) -> Result<()>
+    where
+        F: FnMut(u64, Option<u64>), // (downloaded_bytes, total_bytes)
+    {
+        self.ensure_models_dir().await?;
+
+        download::download_resumable(
+            url,
+            path,
+            checksum,
+            &self.retry_policy,
+            &mut progress_callback,
+        )
+        .await
+    }
+
+    /// Where a built-in model is downloaded from, taking the mirror into account
+    pub fn model_location(&self, model: WhisperModel) -> Location {
+        match &self.settings.mirror {
+            Some(mirror) => Location::parse(mirror).join(&model.filename()),
+            None => Location::Remote(model.get_url()),
+        }
+    }
+
+    /// Download or copy a file from a location into the models directory
+    async fn fetch<F>(
+        &self,
+        location: &Location,
+        path: &Path,
+        checksum: Option<&ModelChecksum>,
+        progress_callback: &mut F,
+    ) -> Result<()>
+    where
+        F: FnMut(u64, Option<u64>),
+    {
+        match location {
+            Location::Remote(url) => {
+                self.download_file(url, path, checksum, progress_callback)
+                    .await
+            }
+            Location::Local(source) => {
+                self.ensure_models_dir().await?;
+                download::copy_local(source, path, checksum, progress_callback).await
+            }
+        }
+    }
+
+    /// List the models of the configured registry (empty if none is configured)
+    pub async fn registry_models(&self) -> Result<Vec<RegistryModel>> {
+        match &self.settings.registry {
+            Some(registry) => registry::load_registry(&Location::parse(registry)).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Find a model by name in the configured registry
+    pub async fn find_registry_model(&self, name: &str) -> Result<Option<RegistryModel>> {
+        Ok(self
+            .registry_models()
+            .await?
+            .into_iter()
+            .find(|model| model.name == name))
+    }
+
+    /// Get the full path to a registry model file
+    pub fn registry_model_path(&self, model: &RegistryModel) -> PathBuf {
+        self.models_dir.join(model.filename())
+    }
+
+    /// Check if a registry model is already downloaded
+    pub fn is_registry_model_downloaded(&self, model: &RegistryModel) -> bool {
+        self.registry_model_path(model).exists()
+    }
+
+    /// Download a registry model with progress callback
+    pub async fn download_registry_model<F>(
+        &self,
+        model: &RegistryModel,
+        mut progress_callback: F,
+    ) -> Result<PathBuf>
+    where
+        F: FnMut(u64, Option<u64>), // (downloaded_bytes, total_bytes)
+    {
+        let model_path = self.registry_model_path(model);
+
+        debug!(
+            "Downloading registry model {} to {:?}",
+            model.name, model_path
+        );
+
+        self.fetch(
+            &model.location(),
+            &model_path,
+            model.checksum().as_ref(),
+            &mut progress_callback,
+        )
+        .await?;
+
+        Ok(model_path)
+    }
+
+    /// Choose among the downloaded models for the given hardware and criteria
+    ///
+    /// Returns `None` when no downloaded model can handle the requested language.
+    pub async fn select_model(
+        &self,
+        hardware: &HardwareProfile,
+        criteria: &SelectionCriteria,
+    ) -> Result<Option<ModelSelection>> {
+        let downloaded = self.list_downloaded_models().await?;
+        Ok(select::select_model(&downloaded, hardware, criteria))
+    }
+
+    /// Find the file of a model given by name or path
+    ///
+    /// Looks for a downloaded built-in model, an imported custom model, a
+    /// downloaded registry model and finally a model file at that path.
+    pub async fn find_model_file(&self, name: &str) -> Option<PathBuf> {
+        if let Ok(model) = WhisperModel::from_str(name) {
+            if self.is_model_downloaded(model).await {
+                return Some(self.get_model_path(model));
+            }
+        }
+
+        [
+            self.custom_model_path(name),
+            self.models_dir.join(format!("ggml-{}.bin", name)),
+            PathBuf::from(name),
+        ]
+        .into_iter()
+        .find(|path| path.is_file())
+    }
+
+    /// Directory holding imported custom models
+    pub fn custom_models_dir(&self) -> PathBuf {
+        self.models_dir.join("custom")
+    }
+
+    /// Get the full path to a custom model file
+    pub fn custom_model_path(&self, name: &str) -> PathBuf {
+        self.custom_models_dir().join(format!("{}.bin", name))
+    }
+
+    /// Import a ggml model file under a custom name
+    ///
+    /// The header is checked before anything is copied, so files that are not
+    /// whisper.cpp models are rejected early.
+    pub async fn import_custom_model<P, F>(
+        &self,
+        source: P,
+        name: &str,
+        mut progress_callback: F,
+    ) -> Result<CustomModel>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, Option<u64>), // (copied_bytes, total_bytes)
+    {
+        let source = source.as_ref();
+        self.prepare_custom_model(name).await?;
+        GgmlHeader::read(source)?;
+
+        let path = self.custom_model_path(name);
+        debug!("Importing custom model {} from {:?}", name, source);
+        download::copy_local(source, &path, None, &mut progress_callback).await?;
+
+        let model = self.register_custom_model(name, source).await?;
+        info!("Imported custom model {} to {:?}", name, path);
+        Ok(model)
+    }
+
+    /// Quantize a local f16 or f32 model and register the result as a custom model
+    pub async fn quantize_model<P: AsRef<Path>>(
+        &self,
+        source: P,
+        name: &str,
+        target: QuantizationType,
+    ) -> Result<(CustomModel, QuantizeStats)> {
+        let source = source.as_ref();
+        self.prepare_custom_model(name).await?;
+
+        let path = self.custom_model_path(name);
+        let temp_path = download::partial_path(&path);
+        debug!("Quantizing {:?} to {} as {}", source, target, name);
+
+        let (input, output) = (source.to_path_buf(), temp_path.clone());
+        let stats = tokio::task::spawn_blocking(move || {
+            quantize::quantize_model_file(&input, &output, target)
+        })
+        .await?;
+
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(e);
+            }
+        };
+        fs::rename(&temp_path, &path).await?;
+
+        let model = self.register_custom_model(name, source).await?;
+        info!(
+            "Quantized {:?} to {} as custom model {}",
+            source, target, name
+        );
+        Ok((model, stats))
+    }
+
+    /// Check that a custom model name is free and create the custom models directory
+    async fn prepare_custom_model(&self, name: &str) -> Result<()> {
+        custom::validate_name(name)?;
+        if self.custom_model_path(name).exists() {
+            return Err(WhisperError::Configuration(format!(
+                "A custom model named '{}' already exists",
+                name
+            )));
+        }
+
+        fs::create_dir_all(self.custom_models_dir())
+            .await
+            .map_err(|e| WhisperError::io("Failed to create models directory", e))
+    }
+
+    /// Write the metadata of a custom model whose weights are in place
+    async fn register_custom_model(&self, name: &str, source: &Path) -> Result<CustomModel> {
+        let path = self.custom_model_path(name);
+        let model = CustomModel {
+            name: name.to_string(),
+            source: std::path::absolute(source).unwrap_or_else(|_| source.to_path_buf()),
+            imported_at: usage::unix_now(),
+            size: fs::metadata(&path).await?.len(),
+            header: GgmlHeader::read(&path)?,
+        };
+
+        let metadata = serde_json::to_string_pretty(&model).map_err(|e| {
+            WhisperError::Unknown(format!("Failed to serialize model metadata: {}", e))
+        })?;
+        fs::write(
+            self.custom_models_dir().join(model.metadata_filename()),
+            metadata,
+        )
+        .await?;
+
+        Ok(model)
+    }
+
+    /// Find an imported custom model by name
+    pub async fn find_custom_model(&self, name: &str) -> Result<Option<CustomModel>> {
+        custom::validate_name(name)?;
+        let metadata_path = self.custom_models_dir().join(format!("{}.json", name));
+        if !metadata_path.exists() || !self.custom_model_path(name).exists() {
+            return Ok(None);
+        }
+
+        CustomModel::from_file(metadata_path).map(Some)
+    }
+
+    /// List imported custom models, sorted by name
+    pub async fn list_custom_models(&self) -> Result<Vec<CustomModel>> {
+        let custom_dir = self.custom_models_dir();
+        if !custom_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut models = Vec::new();
+        let mut entries = fs::read_dir(&custom_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+
+            match CustomModel::from_file(&path) {
+                Ok(model) if self.custom_model_path(&model.name).exists() => models.push(model),
+                Ok(model) => warn!("Custom model {} is missing its weights file", model.name),
+                Err(e) => warn!("Skipping {:?}: {}", path, e.report()),
+            }
+        }
+
+        models.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(models)
+    }
+
+    /// Delete an imported custom model and its metadata
+    pub async fn delete_custom_model(&self, name: &str) -> Result<()> {
+        custom::validate_name(name)?;
+        let metadata_path = self.custom_models_dir().join(format!("{}.json", name));
+        for path in [self.custom_model_path(name), metadata_path] {
+            if path.exists() {
+                fs::remove_file(&path)
+                    .await
+                    .map_err(|e| WhisperError::io("Failed to delete model file", e))?;
+            }
+        }
+
+        info!("Deleted custom model {}", name);
+        Ok(())
+    }
+
+    /// Key of a file in the usage store, if it is inside the models directory
+    fn usage_key(&self, path: &Path) -> Option<String> {
+        path.strip_prefix(&self.models_dir)
+            .ok()
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Record that a model file was just loaded
+    ///
+    /// Files outside the models directory are not tracked.
+    pub fn record_model_use(&self, path: &Path) -> Result<()> {
+        let Some(key) = self.usage_key(path) else {
+            return Ok(());
+        };
+
+        UsageStore::update(&self.models_dir, |store| {
+            store.record(&key, usage::unix_now())
+        })
+    }
+
+    /// List every file in the models directory with its size and last use
+    pub async fn disk_usage(&self) -> Result<Vec<ModelFile>> {
+        if !self.models_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let store = UsageStore::load(&self.models_dir);
+        let builtin: HashSet<String> = WhisperModel::all_models()
+            .iter()
+            .map(|model| model.filename())
+            .collect();
+        // Without a reachable registry its models are reported as unknown files
+        let registry: HashSet<String> = match self.registry_models().await {
+            Ok(models) => models.iter().map(RegistryModel::filename).collect(),
+            Err(e) => {
+                warn!("Failed to load model registry: {}", e.report());
+                HashSet::new()
+            }
+        };
+
+        let mut files = Vec::new();
+        for path in usage::walk_files(&self.models_dir)? {
+            let metadata = std::fs::metadata(&path)?;
+            let key = self.usage_key(&path).unwrap_or_default();
+
+            let kind = if key == usage::USAGE_FILE || key == format!("{}.lock", usage::USAGE_FILE) {
+                ModelFileKind::Metadata
+            } else if key.ends_with(".tmp") {
+                ModelFileKind::Partial
+            } else if let Some(name) = key.strip_prefix("custom/") {
+                match name.rsplit_once('.') {
+                    Some((_, "bin")) => ModelFileKind::Custom,
+                    Some((stem, "json")) if self.custom_model_path(stem).exists() => {
+                        ModelFileKind::Metadata
+                    }
+                    Some((_, "json")) => ModelFileKind::Orphan,
+                    _ => ModelFileKind::Unknown,
+                }
+            } else if builtin.contains(&key) {
+                ModelFileKind::Model
+            } else if registry.contains(&key) {
+                ModelFileKind::Registry
+            } else {
+                ModelFileKind::Unknown
+            };
+
+            files.push(ModelFile {
+                last_used: store.last_used(&key),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|elapsed| elapsed.as_secs()),
+                size: metadata.len(),
+                path,
+                key,
+                kind,
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// Remove leftovers and, optionally, models that have not been used for a while
+    ///
+    /// Returns the removed files (or the files that would be removed on a dry run).
+    /// Models that were never loaded are aged from their download time.
+    pub async fn prune(&self, options: &PruneOptions) -> Result<Vec<ModelFile>> {
+        let now = usage::unix_now();
+        let files = self.disk_usage().await?;
+
+        let is_stale = |file: &ModelFile| {
+            options.unused_for.is_some_and(|max_age| {
+                file.kind.is_model()
+                    && file
+                        .last_activity()
+                        .is_some_and(|time| now.saturating_sub(time) > max_age.as_secs())
+            })
+        };
+
+        let mut keys: HashSet<String> = HashSet::new();
+        for file in files
+            .iter()
+            .filter(|file| file.is_garbage() || is_stale(file))
+        {
+            keys.insert(file.key.clone());
+            // Custom model metadata goes together with its weights
+            if let Some(name) = file
+                .key
+                .strip_prefix("custom/")
+                .and_then(|key| key.strip_suffix(".bin"))
+            {
+                keys.insert(format!("custom/{}.json", name));
+            }
+        }
+
+        let removed: Vec<ModelFile> = files
+            .into_iter()
+            .filter(|file| keys.contains(&file.key))
+            .collect();
+        if options.dry_run {
+            return Ok(removed);
+        }
+
+        for file in &removed {
+            fs::remove_file(&file.path).await.map_err(|e| {
+                WhisperError::io(format!("Failed to delete {}", file.path.display()), e)
+            })?;
+            info!("Pruned {:?}", file.path);
+        }
+
+        UsageStore::update(&self.models_dir, |store| {
+            store.retain(|key| self.models_dir.join(key).exists())
+        })?;
+
+        Ok(removed)
+    }
+
+    /// Rehash an installed model and compare it against its known checksum
+    pub async fn verify_model(&self, model: WhisperModel) -> Result<VerifyStatus> {
+        let Some(checksum) = model.checksum() else {
+            return Ok(VerifyStatus::Unknown);
+        };
+
+        let actual = checksum.compute(self.get_model_path(model)).await?;
+        if actual.eq_ignore_ascii_case(checksum.expected()) {
+            Ok(VerifyStatus::Valid)
+        } else {
+            Ok(VerifyStatus::Mismatch {
+                expected: checksum.expected().to_string(),
+                actual,
+            })
+        }
+    }
+
+    /// List all downloaded models
+    pub async fn list_downloaded_models(&self) -> Result<Vec<WhisperModel>> {
+        if !self.models_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut downloaded = Vec::new();
+
+        for &model in WhisperModel::all_models() {
+            if self.is_model_downloaded(model).await {
+                downloaded.push(model);
+            }
+        }
+
+        Ok(downloaded)
+    }
+
+    /// Delete a downloaded model
+    pub async fn delete_model(&self, model: WhisperModel) -> Result<()> {
+        let model_path = self.get_model_path(model);
+
+        if model_path.exists() {
+            fs::remove_file(&model_path)
+                .await
+                .map_err(|e| WhisperError::io("Failed to delete model file", e))?;
+            info!("Deleted model {} from {:?}", model.as_str(), model_path);
+        }
+
+        Ok(())
+    }
+
+    /// Find a default model file
+    pub async fn find_default_model(&self) -> Result<PathBuf> {
+        let mut searched = Vec::new();
+
+        // First try to find a model using the model manager (XDG compliant)
+        if let Ok(model_manager) = crate::model::ModelManager::new() {
+            if let Some(model_path) = model_manager.find_first_available_model().await {
+                return Ok(model_path);
+            }
+            searched.push(model_manager.models_dir);
+        }
+
+        // Fallback to legacy locations for backward compatibility
+        let possible_paths = [
+            "models/ggml-base.en.bin",
+            "models/ggml-base.bin",
+            "ggml-base.en.bin",
+            "ggml-base.bin",
+            "whisper-base.en.bin",
+            "whisper-base.bin",
+        ];
+
+        for path in &possible_paths {
+            let path_buf = PathBuf::from(path);
+            if path_buf.exists() {
+                return Ok(path_buf);
+            }
+        }
+
+        searched.extend(possible_paths.iter().map(PathBuf::from));
+        Err(WhisperError::ModelNotFound { searched })
+    }
+}
+
+impl Default for ModelManager {
+    fn default() -> Self {
+        Self::new().expect("Failed to create ModelManager")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_parsing() {
+        assert_eq!(WhisperModel::from_str("base"), Ok(WhisperModel::Base));
+        assert_eq!(WhisperModel::from_str("base.en"), Ok(WhisperModel::BaseEn));
+        let invalid = WhisperModel::from_str("invalid_model");
+        assert!(invalid.is_err(), "Expected an error but got: {:?}", invalid);
+    }
+
+    #[test]
+    fn test_model_filename() {
+        assert_eq!(WhisperModel::Base.filename(), "ggml-base.bin");
+        assert_eq!(
+            WhisperModel::LargeV3Turbo.filename(),
+            "ggml-large-v3-turbo.bin"
+        );
+    }
+
+    #[test]
+    fn test_model_checksum() {
+        assert_eq!(
+            WhisperModel::Base.checksum(),
+            Some(ModelChecksum::Sha1(
+                "465707469ff3a37a2b9b8d8f89f2f99de7299dac".to_string()
+            ))
+        );
+        assert_eq!(
+            WhisperModel::TinyQ5_1.checksum(),
+            Some(ModelChecksum::Sha1(
+                "2827a03e495b1ed3048ef28a6a4620537db4ee51".to_string()
+            ))
+        );
+        assert!(WhisperModel::all_models()
+            .iter()
+            .all(|model| model.checksum().is_some()));
+    }
+
+    #[test]
+    fn test_model_family() {
+        assert_eq!(WhisperModel::BaseEnQ5_1.family(), ModelFamily::Base);
+        assert_eq!(
+            WhisperModel::LargeV3TurboQ8_0.family(),
+            ModelFamily::LargeTurbo
+        );
+        assert_eq!(WhisperModel::LargeV2.family(), ModelFamily::Large);
+        assert!(WhisperModel::BaseEnQ5_1.is_english_only());
+        assert!(WhisperModel::BaseEnQ5_1.is_quantized());
+        assert!(!WhisperModel::LargeV3Turbo.is_english_only());
+        assert!(!WhisperModel::LargeV3Turbo.is_quantized());
+        assert!(WhisperModel::Tiny.estimated_memory() < WhisperModel::Small.estimated_memory());
+    }
+
+    #[test]
+    fn test_model_description() {
+        assert!(WhisperModel::Base.description().contains("142 MB"));
+        assert!(WhisperModel::Tiny.description().contains("fastest"));
+    }
+
+    #[tokio::test]
+    async fn test_import_custom_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("fine-tuned.bin");
+        let mut contents =
+            ggml::tests::header_bytes([51865, 1500, 512, 8, 6, 448, 512, 8, 6, 80, 1]);
+        contents.extend_from_slice(&[0u8; 64]);
+        std::fs::write(&source, &contents).unwrap();
+
+        let manager = ModelManager::with_models_dir(dir.path().join("models"));
+        let model = manager
+            .import_custom_model(&source, "mytuned", |_, _| {})
+            .await
+            .unwrap();
+
+        assert_eq!(model.header.model_type(), "base");
+        assert_eq!(model.size, contents.len() as u64);
+        assert_eq!(
+            std::fs::read(manager.custom_model_path("mytuned")).unwrap(),
+            contents
+        );
+        assert_eq!(
+            manager.list_custom_models().await.unwrap(),
+            vec![model.clone()]
+        );
+        assert_eq!(
+            manager.find_custom_model("mytuned").await.unwrap(),
+            Some(model)
+        );
+        assert_eq!(
+            manager.find_model_file("mytuned").await,
+            Some(manager.custom_model_path("mytuned"))
+        );
+        assert_eq!(
+            manager.find_model_file(source.to_str().unwrap()).await,
+            Some(source.clone())
+        );
+        assert_eq!(manager.find_model_file("base").await, None);
+
+        // Names are unique and files must be ggml models
+        assert!(manager
+            .import_custom_model(&source, "mytuned", |_, _| {})
+            .await
+            .is_err());
+        std::fs::write(dir.path().join("junk.bin"), [0u8; 128]).unwrap();
+        assert!(manager
+            .import_custom_model(dir.path().join("junk.bin"), "junk", |_, _| {})
+            .await
+            .is_err());
+
+        // Names cannot reach outside the custom models directory
+        assert!(manager.find_custom_model("../mytuned").await.is_err());
+        assert!(manager.delete_custom_model("../mytuned").await.is_err());
+
+        manager.delete_custom_model("mytuned").await.unwrap();
+        assert!(manager.list_custom_models().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prune_models_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ModelManager::with_models_dir(dir.path());
+        let write = |name: &str, size: usize| {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, vec![0u8; size]).unwrap();
+        };
+        write("ggml-base.bin", 100);
+        write("ggml-small.bin", 300);
+        write("ggml-medium.tmp", 50);
+        write("ggml-large-v3.tmp", 70);
+        write("custom/gone.json", 10);
+        write("notes.txt", 5);
+
+        let day = 24 * 60 * 60;
+        // An abandoned partial download, and one that may still be resumed
+        std::fs::File::options()
+            .write(true)
+            .open(dir.path().join("ggml-medium.tmp"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(2 * day))
+            .unwrap();
+        let mut store = UsageStore::load(dir.path());
+        store.record("ggml-base.bin", usage::unix_now());
+        store.record("ggml-small.bin", usage::unix_now() - 30 * day);
+        store.save().unwrap();
+
+        let files = manager.disk_usage().await.unwrap();
+        let kind = |key: &str| files.iter().find(|f| f.key == key).unwrap().kind.clone();
+        assert_eq!(kind("ggml-base.bin"), ModelFileKind::Model);
+        assert_eq!(kind("ggml-medium.tmp"), ModelFileKind::Partial);
+        assert_eq!(kind("ggml-large-v3.tmp"), ModelFileKind::Partial);
+        assert_eq!(kind("custom/gone.json"), ModelFileKind::Orphan);
+        assert_eq!(kind("notes.txt"), ModelFileKind::Unknown);
+        assert_eq!(kind("usage.json"), ModelFileKind::Metadata);
+
+        let removed_keys = |removed: Vec<ModelFile>| {
+            let mut keys: Vec<String> = removed.into_iter().map(|f| f.key).collect();
+            keys.sort();
+            keys
+        };
+
+        // Dry runs and plain prunes only touch leftovers
+        let options = PruneOptions {
+            unused_for: Some(Duration::from_secs(7 * day)),
+            dry_run: true,
+        };
+        assert_eq!(
+            removed_keys(manager.prune(&options).await.unwrap()),
+            vec!["custom/gone.json", "ggml-medium.tmp", "ggml-small.bin"]
+        );
+        assert!(dir.path().join("ggml-small.bin").exists());
+
+        let removed = manager.prune(&PruneOptions::default()).await.unwrap();
+        assert_eq!(
+            removed_keys(removed),
+            vec!["custom/gone.json", "ggml-medium.tmp"]
+        );
+
+        let options = PruneOptions {
+            dry_run: false,
+            ..options
+        };
+        assert_eq!(
+            removed_keys(manager.prune(&options).await.unwrap()),
+            vec!["ggml-small.bin"]
+        );
+        assert!(dir.path().join("ggml-base.bin").exists());
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(dir.path().join("ggml-large-v3.tmp").exists());
+        assert_eq!(
+            UsageStore::load(dir.path()).last_used("ggml-small.bin"),
+            None
+        );
+    }
+}
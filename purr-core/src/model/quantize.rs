@@ -0,0 +1,461 @@
+//! Quantizing f16/f32 ggml models
+//!
+//! This mirrors whisper.cpp's `quantize` tool: the header, mel filters and
+//! vocabulary are copied as is, 2D weight tensors are quantized with ggml's
+//! routines and every other tensor is kept at its original precision.
+
+use super::ggml::{GgmlHeader, GGML_MAGIC};
+use crate::error::{Result, WhisperError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use whisper_rs::whisper_rs_sys;
+
+/// Tensors that whisper.cpp never quantizes
+const SKIPPED_TENSORS: &[&str] = &[
+    "encoder.conv1.bias",
+    "encoder.conv2.bias",
+    "encoder.positional_embedding",
+    "decoder.positional_embedding",
+];
+
+/// Number of values per block for all supported formats
+const BLOCK_SIZE: usize = 32;
+
+/// Quantization formats supported by [`quantize_model_file`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuantizationType {
+    Q5_0,
+    Q5_1,
+    Q8_0,
+}
+
+impl QuantizationType {
+    /// Name used in model filenames (e.g. `q5_0`)
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            QuantizationType::Q5_0 => "q5_0",
+            QuantizationType::Q5_1 => "q5_1",
+            QuantizationType::Q8_0 => "q8_0",
+        }
+    }
+
+    /// ggml tensor type of quantized weights
+    fn ggml_type(&self) -> whisper_rs_sys::ggml_type {
+        match self {
+            QuantizationType::Q5_0 => whisper_rs_sys::ggml_type_GGML_TYPE_Q5_0,
+            QuantizationType::Q5_1 => whisper_rs_sys::ggml_type_GGML_TYPE_Q5_1,
+            QuantizationType::Q8_0 => whisper_rs_sys::ggml_type_GGML_TYPE_Q8_0,
+        }
+    }
+
+    /// `ftype` stored in the model header, including the quantization version
+    fn ftype(&self) -> i32 {
+        let ftype = match self {
+            QuantizationType::Q5_0 => 8,
+            QuantizationType::Q5_1 => 9,
+            QuantizationType::Q8_0 => 7,
+        };
+        ftype
+            + whisper_rs_sys::GGML_QNT_VERSION as i32
+                * whisper_rs_sys::GGML_QNT_VERSION_FACTOR as i32
+    }
+}
+
+impl FromStr for QuantizationType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "q5_0" => Ok(QuantizationType::Q5_0),
+            "q5_1" => Ok(QuantizationType::Q5_1),
+            "q8_0" => Ok(QuantizationType::Q8_0),
+            _ => Err(format!("Unknown quantization type: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for QuantizationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Summary of a quantization run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuantizeStats {
+    /// Number of tensors that were quantized
+    pub quantized_tensors: usize,
+
+    /// Number of tensors copied at their original precision
+    pub kept_tensors: usize,
+
+    /// Total tensor data size before quantization, in bytes
+    pub original_size: u64,
+
+    /// Total tensor data size after quantization, in bytes
+    pub quantized_size: u64,
+}
+
+/// Quantize an f16 or f32 ggml model into a new file
+pub fn quantize_model_file(
+    input: &Path,
+    output: &Path,
+    target: QuantizationType,
+) -> Result<QuantizeStats> {
+    let open_error =
+        |e: io::Error| WhisperError::io(format!("Failed to open {}", input.display()), e);
+    let mut reader = BufReader::new(File::open(input).map_err(open_error)?);
+    let mut writer = BufWriter::new(File::create(output)?);
+
+    let stats = quantize_model(&mut reader, &mut writer, target, |data, n_per_row| {
+        ggml_quantize(target, data, n_per_row)
+    })?;
+    writer.flush()?;
+
+    Ok(stats)
+}
+
+/// Quantize rows of f32 values with ggml
+fn ggml_quantize(target: QuantizationType, data: &[f32], n_per_row: usize) -> Vec<u8> {
+    let nrows = data.len() / n_per_row;
+    let row_size = unsafe { whisper_rs_sys::ggml_row_size(target.ggml_type(), n_per_row as i64) };
+    let mut quantized = vec![0u8; row_size * nrows];
+
+    let written = unsafe {
+        whisper_rs_sys::ggml_quantize_chunk(
+            target.ggml_type(),
+            data.as_ptr(),
+            quantized.as_mut_ptr().cast(),
+            0,
+            nrows as i64,
+            n_per_row as i64,
+            std::ptr::null(),
+        )
+    };
+    quantized.truncate(written);
+    quantized
+}
+
+/// Rewrite a ggml model stream, quantizing weight tensors with `quantize`
+///
+/// `quantize` receives the tensor values and the row length and returns the
+/// encoded data.
+pub(crate) fn quantize_model<R, W, F>(
+    reader: &mut R,
+    writer: &mut W,
+    target: QuantizationType,
+    mut quantize: F,
+) -> Result<QuantizeStats>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&[f32], usize) -> Vec<u8>,
+{
+    let mut header_bytes = [0u8; GgmlHeader::SIZE];
+    reader.read_exact(&mut header_bytes)?;
+    let mut header = GgmlHeader::parse(&header_bytes)?;
+    if header.is_quantized() {
+        return Err(WhisperError::Configuration(format!(
+            "Model is already quantized ({}), quantize an f16 or f32 model instead",
+            header.quantization()
+        )));
+    }
+
+    header.ftype = target.ftype();
+    writer.write_all(&GGML_MAGIC.to_le_bytes())?;
+    for field in [
+        header.n_vocab,
+        header.n_audio_ctx,
+        header.n_audio_state,
+        header.n_audio_head,
+        header.n_audio_layer,
+        header.n_text_ctx,
+        header.n_text_state,
+        header.n_text_head,
+        header.n_text_layer,
+        header.n_mels,
+        header.ftype,
+    ] {
+        writer.write_all(&field.to_le_bytes())?;
+    }
+
+    // Mel filters
+    let n_mel = copy_i32(reader, writer)?;
+    let n_fft = copy_i32(reader, writer)?;
+    copy_bytes(reader, writer, dimension(n_mel)? * dimension(n_fft)? * 4)?;
+
+    // Vocabulary
+    let n_vocab = copy_i32(reader, writer)?;
+    for _ in 0..n_vocab {
+        let mut length = [0u8; 4];
+        reader.read_exact(&mut length)?;
+        writer.write_all(&length)?;
+        copy_bytes(reader, writer, u32::from_le_bytes(length) as usize)?;
+    }
+
+    let mut stats = QuantizeStats::default();
+    while let Some(n_dims) = read_i32_or_eof(reader)? {
+        let name_length = read_i32(reader)?;
+        let ttype = read_i32(reader)?;
+
+        let mut shape = Vec::with_capacity(dimension(n_dims)?);
+        for _ in 0..n_dims {
+            shape.push(dimension(read_i32(reader)?)?);
+        }
+        let mut name_bytes = vec![0u8; dimension(name_length)?];
+        reader.read_exact(&mut name_bytes)?;
+        let name = String::from_utf8_lossy(&name_bytes);
+
+        let elements: usize = shape.iter().product();
+        let element_size = match ttype {
+            0 => 4,
+            1 => 2,
+            _ => {
+                return Err(WhisperError::InvalidModel(format!(
+                    "Unsupported tensor type {} for {}, only f16 and f32 models can be quantized",
+                    ttype, name
+                )))
+            }
+        };
+        let mut data = vec![0u8; elements * element_size];
+        reader.read_exact(&mut data)?;
+        stats.original_size += data.len() as u64;
+
+        let quantize_tensor =
+            n_dims == 2 && shape[0] % BLOCK_SIZE == 0 && !SKIPPED_TENSORS.contains(&name.as_ref());
+
+        let (ttype, data) = if quantize_tensor {
+            let values: Vec<f32> = if ttype == 0 {
+                data.chunks_exact(4)
+                    .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect()
+            } else {
+                data.chunks_exact(2)
+                    .map(|bytes| f16_to_f32(u16::from_le_bytes(bytes.try_into().unwrap())))
+                    .collect()
+            };
+            stats.quantized_tensors += 1;
+            (target.ggml_type() as i32, quantize(&values, shape[0]))
+        } else {
+            stats.kept_tensors += 1;
+            (ttype, data)
+        };
+
+        writer.write_all(&n_dims.to_le_bytes())?;
+        writer.write_all(&name_length.to_le_bytes())?;
+        writer.write_all(&ttype.to_le_bytes())?;
+        for &size in &shape {
+            writer.write_all(&(size as i32).to_le_bytes())?;
+        }
+        writer.write_all(&name_bytes)?;
+        writer.write_all(&data)?;
+        stats.quantized_size += data.len() as u64;
+    }
+
+    Ok(stats)
+}
+
+fn dimension(value: i32) -> Result<usize> {
+    usize::try_from(value)
+        .map_err(|_| WhisperError::InvalidModel(format!("Negative tensor size {}", value)))
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> Result<i32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+/// Read an `i32`, returning `None` at a clean end of file
+fn read_i32_or_eof<R: Read>(reader: &mut R) -> Result<Option<i32>> {
+    let mut bytes = [0u8; 4];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match reader.read(&mut bytes[filled..])? {
+            0 if filled == 0 => return Ok(None),
+            0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            read => filled += read,
+        }
+    }
+    Ok(Some(i32::from_le_bytes(bytes)))
+}
+
+fn copy_i32<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> Result<i32> {
+    let value = read_i32(reader)?;
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(value)
+}
+
+fn copy_bytes<R: Read, W: Write>(reader: &mut R, writer: &mut W, length: usize) -> Result<()> {
+    let copied = io::copy(&mut reader.take(length as u64), writer)?;
+    if copied != length as u64 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(())
+}
+
+/// Convert an IEEE 754 half-precision value to `f32`
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits & 0x8000) as u32) << 16;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+
+    let value = match (exponent, mantissa) {
+        (0, 0) => sign,
+        // Subnormal: renormalize the mantissa
+        (0, _) => {
+            let shift = mantissa.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | (((mantissa << shift) & 0x3ff) << 13)
+        }
+        (0x1f, _) => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ggml::tests::header_bytes;
+
+    fn tensor(name: &str, ttype: i32, shape: &[i32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(shape.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(&ttype.to_le_bytes());
+        for dim in shape {
+            bytes.extend_from_slice(&dim.to_le_bytes());
+        }
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn f16_bytes(count: usize) -> Vec<u8> {
+        // 1.0 in half precision
+        std::iter::repeat_n(0x3c00u16.to_le_bytes(), count)
+            .flatten()
+            .collect()
+    }
+
+    fn test_model() -> Vec<u8> {
+        let mut model = header_bytes([3, 1500, 32, 1, 1, 448, 32, 1, 1, 2, 1]);
+        // Mel filters: 2 x 2
+        model.extend_from_slice(&2i32.to_le_bytes());
+        model.extend_from_slice(&2i32.to_le_bytes());
+        model.extend_from_slice(&[0u8; 16]);
+        // Vocabulary
+        model.extend_from_slice(&3i32.to_le_bytes());
+        for token in ["a", "bc", "def"] {
+            model.extend_from_slice(&(token.len() as u32).to_le_bytes());
+            model.extend_from_slice(token.as_bytes());
+        }
+        model.extend(tensor(
+            "encoder.blocks.0.mlp.0.weight",
+            1,
+            &[32, 2],
+            &f16_bytes(64),
+        ));
+        model.extend(tensor("encoder.blocks.0.mlp.0.bias", 0, &[32], &[0u8; 128]));
+        model.extend(tensor(
+            "encoder.positional_embedding",
+            0,
+            &[32, 1],
+            &[0u8; 128],
+        ));
+        model
+    }
+
+    #[test]
+    fn test_quantize_model_rewrites_weights() {
+        let input = test_model();
+        let mut output = Vec::new();
+        let mut calls = Vec::new();
+
+        let stats = quantize_model(
+            &mut input.as_slice(),
+            &mut output,
+            QuantizationType::Q8_0,
+            |values, n_per_row| {
+                calls.push((values.to_vec(), n_per_row));
+                vec![0xab; 8]
+            },
+        )
+        .unwrap();
+
+        assert_eq!(stats.quantized_tensors, 1);
+        assert_eq!(stats.kept_tensors, 2);
+        assert_eq!(calls, vec![(vec![1.0; 64], 32)]);
+
+        let header = GgmlHeader::parse(&output).unwrap();
+        assert_eq!(header.quantization(), "q8_0");
+        assert_eq!(header.quantization_version(), 2);
+
+        // Header, mel filters and vocabulary are unchanged
+        let prefix = GgmlHeader::SIZE + 8 + 16 + 4 + 4 * 3 + 6;
+        assert_eq!(
+            output[GgmlHeader::SIZE..prefix],
+            input[GgmlHeader::SIZE..prefix]
+        );
+
+        let mut expected = input[..prefix].to_vec();
+        expected[GgmlHeader::SIZE - 4..GgmlHeader::SIZE].copy_from_slice(&2007i32.to_le_bytes());
+        expected.extend(tensor(
+            "encoder.blocks.0.mlp.0.weight",
+            whisper_rs_sys::ggml_type_GGML_TYPE_Q8_0 as i32,
+            &[32, 2],
+            &[0xab; 8],
+        ));
+        expected.extend(tensor("encoder.blocks.0.mlp.0.bias", 0, &[32], &[0u8; 128]));
+        expected.extend(tensor(
+            "encoder.positional_embedding",
+            0,
+            &[32, 1],
+            &[0u8; 128],
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_quantize_rejects_quantized_and_truncated_models() {
+        let quantized = header_bytes([3, 1500, 32, 1, 1, 448, 32, 1, 1, 2, 1009]);
+        assert!(quantize_model(
+            &mut quantized.as_slice(),
+            &mut Vec::new(),
+            QuantizationType::Q5_0,
+            |_, _| Vec::new()
+        )
+        .is_err());
+
+        let truncated = test_model();
+        let truncated = &truncated[..truncated.len() - 10];
+        assert!(quantize_model(
+            &mut &truncated[..],
+            &mut Vec::new(),
+            QuantizationType::Q5_0,
+            |_, _| Vec::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_f16_to_f32() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x3555), 0.333_251_95);
+        assert_eq!(f16_to_f32(0x0001), 5.960_464_5e-8);
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
+    }
+
+    #[test]
+    fn test_quantization_type_from_str() {
+        assert_eq!("Q5_1".parse(), Ok(QuantizationType::Q5_1));
+        assert!("q4_0".parse::<QuantizationType>().is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! Model sources: download mirrors and registry manifests
+//!
+//! By default models are fetched from huggingface.co. A mirror replaces that
+//! base URL for all built-in models, and a registry manifest lists extra models
+//! that can be downloaded by name. Both can point at an HTTP(S) server, a
+//! `file://` URL or a plain local directory.
+//!
+//! Settings are read from `models.toml` in the purr config directory and can be
+//! overridden with the `PURR_MODEL_MIRROR` and `PURR_MODEL_REGISTRY`
+//! environment variables:
+//!
+//! ```toml
+//! mirror = "https://artifacts.example.com/whisper"
+//! registry = "/srv/models/registry.toml"
+//! ```
+//!
+//! A registry manifest is a TOML file listing models. Relative URLs are
+//! resolved against the location of the manifest:
+//!
+//! ```toml
+//! [[models]]
+//! name = "large-v3-de"
+//! url = "ggml-large-v3-de.bin"
+//! sha256 = "…"
+//! size = 3095033483
+//! description = "Large v3 fine-tuned on German"
+//! ```
+
+use super::{custom::validate_name, ModelChecksum};
+use crate::error::{Result, WhisperError};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the download mirror
+pub const MIRROR_ENV: &str = "PURR_MODEL_MIRROR";
+
+/// Environment variable overriding the registry manifest location
+pub const REGISTRY_ENV: &str = "PURR_MODEL_REGISTRY";
+
+/// Where models are downloaded from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    /// Base URL or directory replacing huggingface.co for built-in models
+    pub mirror: Option<String>,
+
+    /// Location of a registry manifest listing extra models
+    pub registry: Option<String>,
+}
+
+impl ModelSettings {
+    /// Load settings from the config file, then apply environment overrides
+    pub fn load() -> Result<Self> {
+        let mut settings = match Self::config_path() {
+            Some(path) if path.exists() => Self::from_file(&path)?,
+            _ => Self::default(),
+        };
+
+        if let Some(mirror) = env_value(MIRROR_ENV) {
+            settings.mirror = Some(mirror);
+        }
+        if let Some(registry) = env_value(REGISTRY_ENV) {
+            settings.registry = Some(registry);
+        }
+
+        Ok(settings)
+    }
+
+    /// Read settings from a TOML file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| {
+            WhisperError::Configuration(format!("Invalid model settings {}: {}", path.display(), e))
+        })
+    }
+
+    /// Default location of the settings file (`models.toml` in the config directory)
+    pub fn config_path() -> Option<PathBuf> {
+        ProjectDirs::from("dev.gaetans", "", "purr")
+            .map(|dirs| dirs.config_dir().join("models.toml"))
+    }
+
+    /// Set the download mirror
+    pub fn with_mirror<S: Into<String>>(mut self, mirror: S) -> Self {
+        self.mirror = Some(mirror.into());
+        self
+    }
+
+    /// Set the registry manifest location
+    pub fn with_registry<S: Into<String>>(mut self, registry: S) -> Self {
+        self.registry = Some(registry.into());
+        self
+    }
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// A file location, either on a remote server or on the local filesystem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// HTTP(S) URL
+    Remote(String),
+    /// Local path (from a `file://` URL or a plain path)
+    Local(PathBuf),
+}
+
+impl Location {
+    /// Parse a URL or path
+    pub fn parse(value: &str) -> Self {
+        if value.starts_with("http://") || value.starts_with("https://") {
+            Location::Remote(value.to_string())
+        } else if let Some(path) = value.strip_prefix("file://") {
+            Location::Local(PathBuf::from(path))
+        } else {
+            Location::Local(PathBuf::from(value))
+        }
+    }
+
+    /// Resolve a file name or relative path against this location
+    pub fn join(&self, name: &str) -> Self {
+        match self {
+            Location::Remote(base) => {
+                Location::Remote(format!("{}/{}", base.trim_end_matches('/'), name))
+            }
+            Location::Local(base) => Location::Local(base.join(name)),
+        }
+    }
+
+    /// The location containing this one
+    fn parent(&self) -> Self {
+        match self {
+            Location::Remote(url) => match url.rsplit_once('/') {
+                Some((parent, _)) if !parent.ends_with('/') => Location::Remote(parent.to_string()),
+                _ => self.clone(),
+            },
+            Location::Local(path) => {
+                Location::Local(path.parent().map(Path::to_path_buf).unwrap_or_default())
+            }
+        }
+    }
+
+    /// Resolve a possibly relative URL or path found in a manifest at this location
+    fn resolve(&self, value: &str) -> Self {
+        let is_absolute = value.contains("://") || Path::new(value).is_absolute();
+        if is_absolute {
+            Location::parse(value)
+        } else {
+            self.parent().join(value)
+        }
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Remote(url) => write!(f, "{}", url),
+            Location::Local(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A model listed in a registry manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryModel {
+    /// Name used to select the model (e.g. `--model large-v3-de`)
+    pub name: String,
+
+    /// Download URL or path, relative ones are resolved against the manifest
+    pub url: String,
+
+    /// Expected SHA-256 of the file
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// Expected SHA-1 of the file (used when no SHA-256 is given)
+    #[serde(default)]
+    pub sha1: Option<String>,
+
+    /// File size in bytes
+    #[serde(default)]
+    pub size: Option<u64>,
+
+    /// Human readable description
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl RegistryModel {
+    /// Get the filename for this model
+    pub fn filename(&self) -> String {
+        format!("ggml-{}.bin", self.name)
+    }
+
+    /// Expected checksum, preferring SHA-256
+    pub fn checksum(&self) -> Option<ModelChecksum> {
+        self.sha256
+            .clone()
+            .map(ModelChecksum::Sha256)
+            .or_else(|| self.sha1.clone().map(ModelChecksum::Sha1))
+    }
+
+    /// Where to download this model from
+    pub fn location(&self) -> Location {
+        Location::parse(&self.url)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryManifest {
+    #[serde(default)]
+    models: Vec<RegistryModel>,
+}
+
+/// Parse a registry manifest, resolving relative URLs against `location`
+pub fn parse_registry(contents: &str, location: &Location) -> Result<Vec<RegistryModel>> {
+    let manifest: RegistryManifest = toml::from_str(contents).map_err(|e| {
+        WhisperError::Configuration(format!("Invalid model registry {}: {}", location, e))
+    })?;
+
+    // Names become file names next to the built-in models
+    for model in &manifest.models {
+        if let Err(WhisperError::Configuration(reason)) = validate_name(&model.name) {
+            return Err(WhisperError::Configuration(format!(
+                "Invalid model registry {}: {}",
+                location, reason
+            )));
+        }
+    }
+
+    Ok(manifest
+        .models
+        .into_iter()
+        .map(|mut model| {
+            model.url = location.resolve(&model.url).to_string();
+            model
+        })
+        .collect())
+}
+
+/// Fetch and parse a registry manifest
+pub async fn load_registry(location: &Location) -> Result<Vec<RegistryModel>> {
+    let contents = match location {
+        Location::Remote(url) => {
+            let response = reqwest::get(url)
+                .await
+                .map_err(|e| WhisperError::network(url.to_string(), e))?;
+            if !response.status().is_success() {
+                return Err(WhisperError::DownloadFailed {
+                    status: response.status().as_u16(),
+                    url: url.to_string(),
+                });
+            }
+            response
+                .text()
+                .await
+                .map_err(|e| WhisperError::network(url.to_string(), e))?
+        }
+        Location::Local(path) => tokio::fs::read_to_string(path).await.map_err(|e| {
+            WhisperError::io(
+                format!("Failed to read model registry {}", path.display()),
+                e,
+            )
+        })?,
+    };
+
+    parse_registry(&contents, location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_parse() {
+        assert_eq!(
+            Location::parse("https://example.com/models"),
+            Location::Remote("https://example.com/models".to_string())
+        );
+        assert_eq!(
+            Location::parse("file:///srv/models"),
+            Location::Local(PathBuf::from("/srv/models"))
+        );
+        assert_eq!(
+            Location::parse("/srv/models"),
+            Location::Local(PathBuf::from("/srv/models"))
+        );
+    }
+
+    #[test]
+    fn test_location_join() {
+        assert_eq!(
+            Location::parse("https://example.com/models/").join("ggml-base.bin"),
+            Location::Remote("https://example.com/models/ggml-base.bin".to_string())
+        );
+        assert_eq!(
+            Location::parse("file:///srv/models").join("ggml-base.bin"),
+            Location::Local(PathBuf::from("/srv/models/ggml-base.bin"))
+        );
+    }
+
+    #[test]
+    fn test_parse_registry_resolves_relative_urls() {
+        let manifest = r#"
+            [[models]]
+            name = "tuned"
+            url = "ggml-tuned.bin"
+            sha256 = "abc"
+
+            [[models]]
+            name = "remote"
+            url = "https://example.com/ggml-remote.bin"
+            sha1 = "def"
+            description = "Remote model"
+        "#;
+
+        let local = parse_registry(manifest, &Location::parse("/srv/registry.toml")).unwrap();
+        assert_eq!(local[0].url, "/srv/ggml-tuned.bin");
+        assert_eq!(
+            local[0].checksum(),
+            Some(ModelChecksum::Sha256("abc".to_string()))
+        );
+        assert_eq!(local[1].url, "https://example.com/ggml-remote.bin");
+        assert_eq!(
+            local[1].checksum(),
+            Some(ModelChecksum::Sha1("def".to_string()))
+        );
+
+        let remote = parse_registry(
+            manifest,
+            &Location::parse("https://example.com/r/models.toml"),
+        )
+        .unwrap();
+        assert_eq!(remote[0].url, "https://example.com/r/ggml-tuned.bin");
+    }
+
+    #[test]
+    fn test_parse_registry_rejects_invalid_names() {
+        let location = Location::parse("/srv/registry.toml");
+        for name in ["../../escape", "base", ""] {
+            let manifest = format!("[[models]]\nname = \"{}\"\nurl = \"model.bin\"\n", name);
+            assert!(parse_registry(&manifest, &location).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_settings_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models.toml");
+        std::fs::write(&path, "mirror = \"file:///srv/models\"\n").unwrap();
+
+        let settings = ModelSettings::from_file(&path).unwrap();
+        assert_eq!(settings.mirror.as_deref(), Some("file:///srv/models"));
+        assert_eq!(settings.registry, None);
+    }
+}
@@ -0,0 +1,400 @@
+//! Automatic model selection
+//!
+//! Picks the most accurate model that the machine can run within a compute
+//! budget derived from the hardware and the requested quality, skipping models
+//! that cannot handle the requested language.
+
+use super::{GgmlHeader, WhisperModel};
+use crate::SystemInfo;
+use serde::{Deserialize, Serialize};
+
+/// Speed/accuracy trade-off for automatic model selection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+    /// Favor speed over accuracy
+    Fast,
+    /// Largest model the hardware handles comfortably
+    #[default]
+    Balanced,
+    /// Most accurate model the hardware can run
+    Best,
+}
+
+/// Architecture size class of a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    Large,
+    LargeTurbo,
+}
+
+impl ModelFamily {
+    /// Relative accuracy, higher is better
+    pub const fn accuracy(self) -> u8 {
+        match self {
+            ModelFamily::Tiny => 0,
+            ModelFamily::Base => 10,
+            ModelFamily::Small => 20,
+            ModelFamily::Medium => 30,
+            ModelFamily::LargeTurbo => 35,
+            ModelFamily::Large => 40,
+        }
+    }
+
+    /// Relative compute cost, on the same scale as [`HardwareProfile::compute_class`]
+    pub const fn cost(self) -> u8 {
+        match self {
+            ModelFamily::Tiny => 0,
+            ModelFamily::Base => 1,
+            ModelFamily::Small => 2,
+            ModelFamily::Medium | ModelFamily::LargeTurbo => 3,
+            ModelFamily::Large => 4,
+        }
+    }
+
+    /// Typical hyperparameters of the family (f16 weights, multilingual)
+    pub const fn header(self) -> GgmlHeader {
+        // (n_state, n_head, n_audio_layer, n_text_layer)
+        let (n_state, n_head, n_audio_layer, n_text_layer) = match self {
+            ModelFamily::Tiny => (384, 6, 4, 4),
+            ModelFamily::Base => (512, 8, 6, 6),
+            ModelFamily::Small => (768, 12, 12, 12),
+            ModelFamily::Medium => (1024, 16, 24, 24),
+            ModelFamily::Large => (1280, 20, 32, 32),
+            ModelFamily::LargeTurbo => (1280, 20, 32, 4),
+        };
+
+        GgmlHeader {
+            n_vocab: 51865,
+            n_audio_ctx: 1500,
+            n_audio_state: n_state,
+            n_audio_head: n_head,
+            n_audio_layer,
+            n_text_ctx: 448,
+            n_text_state: n_state,
+            n_text_head: n_head,
+            n_text_layer,
+            n_mels: 80,
+            ftype: 1,
+        }
+    }
+}
+
+/// What the machine can run, as used by [`select_model`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HardwareProfile {
+    /// Free memory of the largest GPU, if GPU acceleration is used
+    pub gpu_memory: Option<u64>,
+
+    /// Number of CPU threads
+    pub cpu_cores: usize,
+
+    /// Whether the CPU supports AVX2
+    pub avx2: bool,
+}
+
+impl HardwareProfile {
+    /// GPUs with less free memory than this are ignored
+    const MIN_GPU_MEMORY: u64 = 1024 * 1024 * 1024;
+
+    /// Build a profile from detected system information
+    pub fn from_system_info(info: &SystemInfo, use_gpu: bool, cpu_cores: usize) -> Self {
+        let gpu_memory = if use_gpu {
+            info.gpus()
+                .map(|device| device.vram_free as u64)
+                .max()
+                .filter(|&free| free >= Self::MIN_GPU_MEMORY)
+        } else {
+            None
+        };
+
+        Self {
+            gpu_memory,
+            cpu_cores,
+            avx2: info.system_info.avx2,
+        }
+    }
+
+    /// Largest model cost this hardware runs comfortably (0 = tiny .. 4 = large)
+    pub fn compute_class(&self) -> u8 {
+        if self.gpu_memory.is_some() {
+            4
+        } else if self.cpu_cores >= 8 && self.avx2 {
+            3
+        } else if self.cpu_cores >= 4 {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.gpu_memory {
+            Some(memory) => format!(
+                "GPU with {:.1} GB free",
+                memory as f64 / (1024.0 * 1024.0 * 1024.0)
+            ),
+            None => format!(
+                "CPU only, {} threads{}",
+                self.cpu_cores,
+                if self.avx2 { " with AVX2" } else { "" }
+            ),
+        }
+    }
+}
+
+/// What the selected model should be good at
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionCriteria {
+    /// Language of the audio (`None` or `auto` for detection)
+    pub language: Option<String>,
+
+    /// Speed/accuracy preference
+    pub quality: Quality,
+}
+
+impl SelectionCriteria {
+    /// Whether the audio is known to be English
+    fn is_english(&self) -> bool {
+        self.language
+            .as_deref()
+            .is_some_and(|language| matches!(language, "en" | "english"))
+    }
+
+    /// Whether English-only models can handle the audio
+    fn allows_english_only(&self) -> bool {
+        match self.language.as_deref() {
+            None | Some("auto") => true,
+            Some(_) => self.is_english(),
+        }
+    }
+}
+
+/// The outcome of [`select_model`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelSelection {
+    /// Chosen model
+    pub model: WhisperModel,
+
+    /// Human readable explanation of the choice
+    pub reasons: Vec<String>,
+}
+
+/// Choose the best model among `candidates` for the given hardware and criteria
+///
+/// Returns `None` when no candidate can handle the requested language.
+pub fn select_model(
+    candidates: &[WhisperModel],
+    hardware: &HardwareProfile,
+    criteria: &SelectionCriteria,
+) -> Option<ModelSelection> {
+    let mut reasons = Vec::new();
+
+    // Diarization models are special purpose and never picked automatically
+    let compatible: Vec<WhisperModel> = candidates
+        .iter()
+        .copied()
+        .filter(|model| *model != WhisperModel::SmallEnTdrz)
+        .filter(|model| criteria.allows_english_only() || !model.is_english_only())
+        .collect();
+
+    if !criteria.allows_english_only() {
+        let skipped: Vec<&str> = candidates
+            .iter()
+            .filter(|model| model.is_english_only())
+            .map(|model| model.as_str())
+            .collect();
+        if !skipped.is_empty() {
+            reasons.push(format!(
+                "skipped English-only models for language '{}': {}",
+                criteria.language.as_deref().unwrap_or_default(),
+                skipped.join(", ")
+            ));
+        }
+    }
+
+    let compute_budget = match criteria.quality {
+        Quality::Fast => hardware.compute_class().saturating_sub(2),
+        Quality::Balanced => hardware.compute_class().saturating_sub(1),
+        Quality::Best => hardware.compute_class(),
+    };
+    reasons.push(format!(
+        "{}: {:?} quality allows up to {} models",
+        hardware.describe(),
+        criteria.quality,
+        family_name(compute_budget)
+    ));
+
+    let fits = |model: &WhisperModel| {
+        model.family().cost() <= compute_budget
+            && hardware
+                .gpu_memory
+                .is_none_or(|memory| model.estimated_memory() <= memory)
+    };
+
+    let preference = |model: &WhisperModel| {
+        let family = model.family();
+        // Unknown audio may not be English, so it prefers multilingual models
+        let language_match = criteria.is_english() == model.is_english_only();
+        let quantization_match = match criteria.quality {
+            Quality::Fast => model.is_quantized(),
+            Quality::Balanced | Quality::Best => !model.is_quantized(),
+        };
+        let newest = WhisperModel::all_models()
+            .iter()
+            .position(|m| m == model)
+            .unwrap_or_default();
+        (
+            family.accuracy(),
+            language_match,
+            quantization_match,
+            newest,
+        )
+    };
+
+    let chosen = match compatible
+        .iter()
+        .filter(|model| fits(model))
+        .max_by_key(|model| preference(model))
+    {
+        Some(model) => *model,
+        None => {
+            // Nothing fits the budget, fall back to the cheapest usable model
+            let model = *compatible.iter().min_by_key(|model| {
+                let (accuracy, language_match, quantization_match, newest) = preference(model);
+                (
+                    model.family().cost(),
+                    std::cmp::Reverse((accuracy, language_match, quantization_match, newest)),
+                )
+            })?;
+            reasons.push(
+                "no installed model fits within that budget, using the lightest one".to_string(),
+            );
+            model
+        }
+    };
+
+    reasons.push(format!(
+        "chose {} (~{} MB of memory)",
+        chosen.as_str(),
+        chosen.estimated_memory() / (1024 * 1024)
+    ));
+
+    Some(ModelSelection {
+        model: chosen,
+        reasons,
+    })
+}
+
+fn family_name(cost: u8) -> &'static str {
+    match cost {
+        0 => "tiny",
+        1 => "base",
+        2 => "small",
+        3 => "medium/large-v3-turbo",
+        _ => "large",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpu(cores: usize) -> HardwareProfile {
+        HardwareProfile {
+            gpu_memory: None,
+            cpu_cores: cores,
+            avx2: true,
+        }
+    }
+
+    fn criteria(language: Option<&str>, quality: Quality) -> SelectionCriteria {
+        SelectionCriteria {
+            language: language.map(str::to_string),
+            quality,
+        }
+    }
+
+    #[test]
+    fn test_skips_english_only_models_for_other_languages() {
+        let installed = [WhisperModel::BaseEn];
+        let spanish = criteria(Some("es"), Quality::Balanced);
+        assert_eq!(select_model(&installed, &cpu(8), &spanish), None);
+
+        let installed = [WhisperModel::BaseEn, WhisperModel::Tiny];
+        let selection = select_model(&installed, &cpu(8), &spanish).unwrap();
+        assert_eq!(selection.model, WhisperModel::Tiny);
+        assert!(selection.reasons[0].contains("base.en"));
+    }
+
+    #[test]
+    fn test_prefers_english_only_models_for_english() {
+        let installed = [WhisperModel::Base, WhisperModel::BaseEn];
+        let english = criteria(Some("en"), Quality::Balanced);
+        let selection = select_model(&installed, &cpu(8), &english).unwrap();
+        assert_eq!(selection.model, WhisperModel::BaseEn);
+    }
+
+    #[test]
+    fn test_prefers_multilingual_models_for_detection() {
+        let installed = [WhisperModel::Base, WhisperModel::BaseEn];
+        for language in [None, Some("auto")] {
+            let detect = criteria(language, Quality::Balanced);
+            let selection = select_model(&installed, &cpu(8), &detect).unwrap();
+            assert_eq!(selection.model, WhisperModel::Base);
+        }
+
+        // English-only models are still used when nothing else is installed
+        let installed = [WhisperModel::BaseEn];
+        let detect = criteria(None, Quality::Balanced);
+        let selection = select_model(&installed, &cpu(8), &detect).unwrap();
+        assert_eq!(selection.model, WhisperModel::BaseEn);
+    }
+
+    #[test]
+    fn test_quality_follows_hardware() {
+        let installed = [
+            WhisperModel::Tiny,
+            WhisperModel::Base,
+            WhisperModel::Small,
+            WhisperModel::LargeV3Turbo,
+            WhisperModel::LargeV3,
+        ];
+        let select = |hardware: &HardwareProfile, quality| {
+            select_model(&installed, hardware, &criteria(None, quality))
+                .unwrap()
+                .model
+        };
+
+        assert_eq!(select(&cpu(8), Quality::Fast), WhisperModel::Base);
+        assert_eq!(select(&cpu(8), Quality::Balanced), WhisperModel::Small);
+        assert_eq!(select(&cpu(8), Quality::Best), WhisperModel::LargeV3Turbo);
+
+        let gpu = HardwareProfile {
+            gpu_memory: Some(8 * 1024 * 1024 * 1024),
+            ..cpu(8)
+        };
+        assert_eq!(select(&gpu, Quality::Best), WhisperModel::LargeV3);
+
+        // Large models do not fit on a small GPU
+        let small_gpu = HardwareProfile {
+            gpu_memory: Some(2 * 1024 * 1024 * 1024),
+            ..cpu(8)
+        };
+        assert_eq!(
+            select(&small_gpu, Quality::Best),
+            WhisperModel::LargeV3Turbo
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_lightest_model() {
+        let installed = [WhisperModel::Medium, WhisperModel::LargeV3];
+        let selection = select_model(&installed, &cpu(2), &criteria(None, Quality::Fast)).unwrap();
+        assert_eq!(selection.model, WhisperModel::Medium);
+    }
+}
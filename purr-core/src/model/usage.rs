@@ -0,0 +1,252 @@
+//! Disk usage and last-use tracking for the models directory
+//!
+//! The last time each model was loaded is kept in `usage.json` at the root of
+//! the models directory, keyed by the path of the model relative to it.
+//! Changes go through [`UsageStore::update`], which holds a lock on the store
+//! so that processes loading models at the same time do not lose each
+//! other's updates.
+
+use crate::error::{Result, WhisperError};
+use crate::state_file;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Name of the usage store file in the models directory
+pub const USAGE_FILE: &str = "usage.json";
+
+/// Age after which a partial download or quantization is considered abandoned
+///
+/// Younger partial files may still be written or resumed and are kept.
+pub const PARTIAL_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Current time in seconds since the Unix epoch
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Last-use times of the models in a models directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageStore {
+    path: PathBuf,
+    last_used: BTreeMap<String, u64>,
+}
+
+impl UsageStore {
+    /// Load the store of a models directory (empty if missing or unreadable)
+    pub fn load(models_dir: &Path) -> Self {
+        let path = models_dir.join(USAGE_FILE);
+        let last_used = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring corrupt model usage store {:?}: {}", path, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+
+        Self { path, last_used }
+    }
+
+    /// Change the store of a models directory while holding its lock
+    pub fn update<F: FnOnce(&mut Self)>(models_dir: &Path, change: F) -> Result<()> {
+        let _lock = state_file::lock(&models_dir.join(USAGE_FILE))?;
+        let mut store = Self::load(models_dir);
+        change(&mut store);
+        store.save()
+    }
+
+    /// Last use of a model (seconds since the Unix epoch)
+    pub fn last_used(&self, key: &str) -> Option<u64> {
+        self.last_used.get(key).copied()
+    }
+
+    /// Record that a model was used at `time`
+    pub fn record(&mut self, key: &str, time: u64) {
+        self.last_used.insert(key.to_string(), time);
+    }
+
+    /// Forget a model
+    pub fn remove(&mut self, key: &str) {
+        self.last_used.remove(key);
+    }
+
+    /// Drop entries for which `keep` returns false
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, mut keep: F) {
+        self.last_used.retain(|key, _| keep(key));
+    }
+
+    /// Write the store back to disk
+    ///
+    /// Use [`UsageStore::update`] to change the store of a models directory
+    /// that other processes may be using.
+    pub fn save(&self) -> Result<()> {
+        let contents = serde_json::to_string_pretty(&self.last_used).map_err(|e| {
+            WhisperError::Unknown(format!("Failed to serialize model usage: {}", e))
+        })?;
+        state_file::write_atomic(&self.path, contents.as_bytes())
+    }
+}
+
+/// What a file in the models directory is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelFileKind {
+    /// A built-in model
+    Model,
+    /// A model from the configured registry
+    Registry,
+    /// An imported or quantized custom model
+    Custom,
+    /// Metadata of a custom model, or the usage store and its lock
+    Metadata,
+    /// Leftover of an interrupted download or quantization
+    Partial,
+    /// Custom model metadata whose weights are gone
+    Orphan,
+    /// Any other file
+    Unknown,
+}
+
+impl ModelFileKind {
+    /// Whether the file holds model weights
+    pub fn is_model(&self) -> bool {
+        matches!(
+            self,
+            ModelFileKind::Model | ModelFileKind::Registry | ModelFileKind::Custom
+        )
+    }
+}
+
+/// A file in the models directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelFile {
+    /// Full path of the file
+    pub path: PathBuf,
+
+    /// Path relative to the models directory
+    pub key: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// What the file is
+    pub kind: ModelFileKind,
+
+    /// Last time the model was loaded (seconds since the Unix epoch)
+    pub last_used: Option<u64>,
+
+    /// Last modification of the file (seconds since the Unix epoch)
+    pub modified: Option<u64>,
+}
+
+impl ModelFile {
+    /// Last use, falling back to the download time for models never loaded
+    pub fn last_activity(&self) -> Option<u64> {
+        self.last_used.or(self.modified)
+    }
+
+    /// Whether the file is safe to delete
+    ///
+    /// Partial files only become garbage once nothing has written to them
+    /// for [`PARTIAL_MAX_AGE`], so that downloads in progress or waiting to
+    /// be resumed survive a prune.
+    pub fn is_garbage(&self) -> bool {
+        match self.kind {
+            ModelFileKind::Orphan => true,
+            ModelFileKind::Partial => self.modified.is_some_and(|modified| {
+                unix_now().saturating_sub(modified) > PARTIAL_MAX_AGE.as_secs()
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// What `ModelManager::prune` removes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneOptions {
+    /// Also remove models not used for this long
+    pub unused_for: Option<std::time::Duration>,
+
+    /// Only report what would be removed
+    pub dry_run: bool,
+}
+
+/// List all files below a directory, recursively
+pub(crate) fn walk_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut store = UsageStore::load(dir.path());
+        assert_eq!(store.last_used("ggml-base.bin"), None);
+
+        store.record("ggml-base.bin", 100);
+        store.record("custom/mine.bin", 200);
+        store.save().unwrap();
+
+        let mut store = UsageStore::load(dir.path());
+        assert_eq!(store.last_used("ggml-base.bin"), Some(100));
+        store.remove("ggml-base.bin");
+        assert_eq!(store.last_used("ggml-base.bin"), None);
+        assert_eq!(store.last_used("custom/mine.bin"), Some(200));
+    }
+
+    #[test]
+    fn test_concurrent_updates() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = dir.path().to_path_buf();
+                std::thread::spawn(move || {
+                    UsageStore::update(&dir, |store| store.record(&format!("model-{}", i), i))
+                        .unwrap()
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let store = UsageStore::load(dir.path());
+        for i in 0..8 {
+            assert_eq!(store.last_used(&format!("model-{}", i)), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_usage_store_ignores_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(USAGE_FILE), "not json").unwrap();
+
+        assert_eq!(UsageStore::load(dir.path()).last_used("anything"), None);
+    }
+}
@@ -0,0 +1,404 @@
+//! Subtitle, text and data renderings of transcription segments
+
+use crate::whisper::{SyncTranscriptionResult, TranscriptionSegment};
+use crate::TranscriptionConfig;
+use serde::Serialize;
+
+/// Header of TSV transcripts
+pub const TSV_HEADER: &str = "start\tend\ttext\n";
+
+/// Header of CSV transcripts
+pub const CSV_HEADER: &str = "start,end,text\n";
+
+/// Split seconds into hours, minutes, seconds and milliseconds
+fn time_parts(seconds: f64) -> (u32, u32, u32, u32) {
+    let seconds = seconds.max(0.0);
+    let hours = (seconds / 3600.0) as u32;
+    let minutes = ((seconds % 3600.0) / 60.0) as u32;
+    let secs = (seconds % 60.0) as u32;
+    let millis = ((seconds % 1.0) * 1000.0) as u32;
+    (hours, minutes, secs, millis)
+}
+
+/// Format a time as an SRT timestamp (`HH:MM:SS,mmm`)
+pub fn srt_timestamp(seconds: f64) -> String {
+    let (hours, minutes, secs, millis) = time_parts(seconds);
+    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
+}
+
+/// Format a time as a WebVTT timestamp (`HH:MM:SS.mmm`)
+pub fn vtt_timestamp(seconds: f64) -> String {
+    let (hours, minutes, secs, millis) = time_parts(seconds);
+    format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, secs, millis)
+}
+
+/// Format one SRT cue
+pub fn srt_cue(index: usize, start: f64, end: f64, text: &str) -> String {
+    format!(
+        "{}\n{} --> {}\n{}\n",
+        index,
+        srt_timestamp(start),
+        srt_timestamp(end),
+        text.trim()
+    )
+}
+
+/// Format one WebVTT cue
+pub fn vtt_cue(start: f64, end: f64, text: &str) -> String {
+    format!(
+        "{} --> {}\n{}\n",
+        vtt_timestamp(start),
+        vtt_timestamp(end),
+        text.trim()
+    )
+}
+
+/// Render segments as an SRT subtitle file
+pub fn to_srt(segments: &[TranscriptionSegment]) -> String {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| srt_cue(i + 1, segment.start, segment.end, &segment.text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render segments as a WebVTT subtitle file
+pub fn to_vtt(segments: &[TranscriptionSegment]) -> String {
+    let mut output = String::from("WEBVTT\n");
+    for segment in segments {
+        output.push('\n');
+        output.push_str(&vtt_cue(segment.start, segment.end, &segment.text));
+    }
+    output
+}
+
+/// Render segments as text, one `[start -> end] text` line per segment
+pub fn to_timestamped_text(segments: &[TranscriptionSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| {
+            format!(
+                "[{:.2}s -> {:.2}s] {}",
+                segment.start, segment.end, segment.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Time in whole milliseconds, as in TSV, CSV and whisper.cpp JSON offsets
+fn millis(seconds: f64) -> i64 {
+    (seconds.max(0.0) * 1000.0).round() as i64
+}
+
+/// Format one TSV row, with times in milliseconds like openai-whisper
+pub fn tsv_row(start: f64, end: f64, text: &str) -> String {
+    format!(
+        "{}\t{}\t{}\n",
+        millis(start),
+        millis(end),
+        text.trim().replace(['\t', '\n'], " ")
+    )
+}
+
+/// Format one CSV row, with times in milliseconds like whisper.cpp
+pub fn csv_row(start: f64, end: f64, text: &str) -> String {
+    format!(
+        "{},{},\"{}\"\n",
+        millis(start),
+        millis(end),
+        text.trim().replace('"', "\"\"")
+    )
+}
+
+/// Render segments as TSV with a header row
+pub fn to_tsv(segments: &[TranscriptionSegment]) -> String {
+    let mut output = String::from(TSV_HEADER);
+    for segment in segments {
+        output.push_str(&tsv_row(segment.start, segment.end, &segment.text));
+    }
+    output
+}
+
+/// Render segments as CSV with a header row
+pub fn to_csv(segments: &[TranscriptionSegment]) -> String {
+    let mut output = String::from(CSV_HEADER);
+    for segment in segments {
+        output.push_str(&csv_row(segment.start, segment.end, &segment.text));
+    }
+    output
+}
+
+/// Render items as JSON Lines, one object per line
+pub fn to_jsonl<T: Serialize>(items: &[T]) -> serde_json::Result<String> {
+    let mut output = String::new();
+    for item in items {
+        output.push_str(&serde_json::to_string(item)?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// whisper.cpp JSON output, in the order whisper.cpp writes its fields
+#[derive(Serialize)]
+struct CppJson<'a> {
+    systeminfo: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<CppModel<'a>>,
+    params: CppParams,
+    result: CppResult<'a>,
+    transcription: Vec<CppSegment<'a>>,
+}
+
+#[derive(Serialize)]
+struct CppModel<'a> {
+    #[serde(rename = "type")]
+    model_type: &'a str,
+    multilingual: bool,
+    vocab: i32,
+    audio: CppLayers,
+    text: CppLayers,
+    mels: i32,
+    ftype: i32,
+}
+
+#[derive(Serialize)]
+struct CppLayers {
+    ctx: i32,
+    state: i32,
+    head: i32,
+    layer: i32,
+}
+
+#[derive(Serialize)]
+struct CppParams {
+    model: String,
+    language: String,
+    translate: bool,
+}
+
+#[derive(Serialize)]
+struct CppResult<'a> {
+    language: &'a str,
+}
+
+#[derive(Serialize)]
+struct CppTimes<T> {
+    from: T,
+    to: T,
+}
+
+impl CppTimes<String> {
+    fn timestamps(start: f64, end: f64) -> Self {
+        Self {
+            from: srt_timestamp(start),
+            to: srt_timestamp(end),
+        }
+    }
+}
+
+impl CppTimes<i64> {
+    fn offsets(start: f64, end: f64) -> Self {
+        Self {
+            from: millis(start),
+            to: millis(end),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CppSegment<'a> {
+    timestamps: CppTimes<String>,
+    offsets: CppTimes<i64>,
+    text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tokens: Option<Vec<CppToken<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker_turn_next: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct CppToken<'a> {
+    text: &'a str,
+    timestamps: CppTimes<String>,
+    offsets: CppTimes<i64>,
+    id: i32,
+    p: f32,
+    t_dtw: i64,
+}
+
+/// Render a result in whisper.cpp's JSON schema
+///
+/// Results with full details (tokens and model) match `-ojf`, others match
+/// `-oj` without the model block.
+pub fn to_whisper_cpp_json(
+    result: &SyncTranscriptionResult,
+    config: &TranscriptionConfig,
+) -> serde_json::Result<String> {
+    whisper_cpp_json(result, config, whisper_rs::print_system_info())
+}
+
+fn whisper_cpp_json(
+    result: &SyncTranscriptionResult,
+    config: &TranscriptionConfig,
+    systeminfo: &str,
+) -> serde_json::Result<String> {
+    let model_path = result
+        .model
+        .as_ref()
+        .and_then(|model| model.path.as_ref())
+        .or(config.model_path.as_ref());
+    let language = config.language.as_deref().unwrap_or("auto");
+
+    let json = CppJson {
+        systeminfo,
+        model: result.model.as_ref().map(|model| CppModel {
+            model_type: &model.model_type,
+            multilingual: model.multilingual,
+            vocab: model.vocab,
+            audio: CppLayers {
+                ctx: model.audio_ctx,
+                state: model.audio_state,
+                head: model.audio_head,
+                layer: model.audio_layer,
+            },
+            text: CppLayers {
+                ctx: model.text_ctx,
+                state: model.text_state,
+                head: model.text_head,
+                layer: model.text_layer,
+            },
+            mels: model.mels,
+            ftype: model.ftype,
+        }),
+        params: CppParams {
+            model: model_path
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            language: language.to_string(),
+            translate: config.translate,
+        },
+        result: CppResult {
+            language: result.language.as_deref().unwrap_or(language),
+        },
+        transcription: result
+            .segments
+            .iter()
+            .map(|segment| CppSegment {
+                timestamps: CppTimes::timestamps(segment.start, segment.end),
+                offsets: CppTimes::offsets(segment.start, segment.end),
+                text: &segment.text,
+                tokens: segment.tokens.as_ref().map(|tokens| {
+                    tokens
+                        .iter()
+                        .map(|token| CppToken {
+                            text: &token.text,
+                            timestamps: CppTimes::timestamps(token.start, token.end),
+                            offsets: CppTimes::offsets(token.start, token.end),
+                            id: token.id,
+                            p: token.probability,
+                            // No DTW token timestamps
+                            t_dtw: -1,
+                        })
+                        .collect()
+                }),
+                speaker_turn_next: config.speaker_turns.then_some(segment.speaker_turn_next),
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, start: f64, end: f64) -> TranscriptionSegment {
+        TranscriptionSegment {
+            text: text.to_string(),
+            start,
+            end,
+            confidence: None,
+            words: None,
+            no_speech_prob: None,
+            hallucination: None,
+            speaker_turn_next: false,
+            tokens: None,
+        }
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(srt_timestamp(0.0), "00:00:00,000");
+        assert_eq!(srt_timestamp(3661.5), "01:01:01,500");
+        assert_eq!(vtt_timestamp(61.25), "00:01:01.250");
+        assert_eq!(vtt_timestamp(-1.0), "00:00:00.000");
+    }
+
+    #[test]
+    fn test_subtitles() {
+        let segments = [segment(" Hello.", 0.0, 1.5), segment(" World.", 1.5, 3.0)];
+
+        assert_eq!(
+            to_srt(&segments),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n2\n00:00:01,500 --> 00:00:03,000\nWorld.\n"
+        );
+        assert_eq!(
+            to_vtt(&segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello.\n\n00:00:01.500 --> 00:00:03.000\nWorld.\n"
+        );
+    }
+
+    #[test]
+    fn test_data_outputs() {
+        let segments = [
+            segment(" Hello,\tworld.", 0.0, 1.5),
+            segment(" She said \"hi\".", 1.5, 3.0),
+        ];
+
+        assert_eq!(
+            to_tsv(&segments),
+            "start\tend\ttext\n0\t1500\tHello, world.\n1500\t3000\tShe said \"hi\".\n"
+        );
+        assert_eq!(
+            to_csv(&segments),
+            "start,end,text\n0,1500,\"Hello,\tworld.\"\n1500,3000,\"She said \"\"hi\"\".\"\n"
+        );
+
+        let jsonl = to_jsonl(&segments).unwrap();
+        let lines: Vec<_> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["start"], 0.0);
+    }
+
+    #[test]
+    fn test_whisper_cpp_json() {
+        let result = SyncTranscriptionResult {
+            text: " Hello.".to_string(),
+            language: None,
+            segments: vec![segment(" Hello.", 0.0, 1.5)],
+            processing_time: 0.1,
+            audio_duration: 1.5,
+            stats: crate::whisper::TranscriptionStats::new(0.1, 1.5, 1, 1),
+            model: None,
+        };
+        let config = TranscriptionConfig::new()
+            .with_model_path("ggml-base.bin")
+            .with_language("en");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&whisper_cpp_json(&result, &config, "AVX = 1").unwrap()).unwrap();
+        assert_eq!(json["systeminfo"], "AVX = 1");
+        assert_eq!(json["params"]["model"], "ggml-base.bin");
+        assert_eq!(json["result"]["language"], "en");
+        let segment = &json["transcription"][0];
+        assert_eq!(segment["timestamps"]["to"], "00:00:01,500");
+        assert_eq!(segment["offsets"]["to"], 1500);
+        assert_eq!(segment["text"], " Hello.");
+        assert!(segment.get("tokens").is_none());
+        assert!(json.get("model").is_none());
+    }
+}
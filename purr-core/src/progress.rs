@@ -0,0 +1,56 @@
+//! Progress reporting for long running transcriptions
+
+use std::sync::Arc;
+
+/// Step of a transcription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ProgressStage {
+    /// Decoding and resampling the audio file
+    Decoding,
+    /// Running the model
+    Transcribing,
+}
+
+/// A progress update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Current step
+    pub stage: ProgressStage,
+
+    /// Completed fraction of the step, from 0 to 1
+    pub fraction: f32,
+}
+
+impl Progress {
+    pub(crate) fn new(stage: ProgressStage, fraction: f32) -> Self {
+        Self {
+            stage,
+            fraction: fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Completed percentage of the step
+    pub fn percent(&self) -> u8 {
+        (self.fraction * 100.0).round() as u8
+    }
+}
+
+/// Receives progress updates, from whichever thread does the work
+pub type ProgressCallback = Arc<dyn Fn(Progress) + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_clamped() {
+        assert_eq!(Progress::new(ProgressStage::Decoding, 1.5).fraction, 1.0);
+        assert_eq!(Progress::new(ProgressStage::Decoding, -0.5).percent(), 0);
+        assert_eq!(
+            Progress::new(ProgressStage::Transcribing, 0.424).percent(),
+            42
+        );
+        assert_eq!(ProgressStage::Transcribing.to_string(), "transcribing");
+    }
+}
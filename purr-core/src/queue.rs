@@ -0,0 +1,475 @@
+//! Persistent job queue for batch transcription
+//!
+//! Jobs are stored one per line in a JSON-lines file (`queue.jsonl` in the
+//! purr data directory by default). The whole file is rewritten after every
+//! change, through a temporary file and a rename, so it is never left half
+//! written. Changes go through [`JobQueue::update`], which rereads the file
+//! under a lock so that jobs added while a run is going on are not lost.
+//!
+//! Only one process at a time runs the queue, see [`JobQueue::lock_runner`].
+//! A job still marked as running when the next run starts was interrupted,
+//! [`JobQueue::recover`] puts it back in the queue.
+
+use crate::error::{Result, WhisperError};
+use crate::model::usage::unix_now;
+use crate::state_file;
+use crate::whisper::TranscriptionStats;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Name of the queue file in the data directory
+pub const QUEUE_FILE: &str = "queue.jsonl";
+
+/// State of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a worker
+    Pending,
+    /// Being transcribed
+    Running,
+    /// Transcript written
+    Done,
+    /// Failed on every attempt
+    Failed,
+}
+
+/// A transcription job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    /// Identifier, unique within a queue
+    pub id: u64,
+
+    /// Audio file to transcribe
+    pub input: PathBuf,
+
+    /// Current state
+    pub status: JobStatus,
+
+    /// Number of times the job was started
+    pub attempts: u32,
+
+    /// Error of the last failed attempt
+    pub error: Option<String>,
+
+    /// Path of the written transcript
+    pub output: Option<PathBuf>,
+
+    /// Statistics of the transcription
+    pub stats: Option<TranscriptionStats>,
+
+    /// When the job was added (seconds since the Unix epoch)
+    pub added_at: u64,
+
+    /// Last state change (seconds since the Unix epoch)
+    pub updated_at: u64,
+}
+
+impl Job {
+    /// Whether the job will not run again
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+/// Number of jobs in each state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueCounts {
+    pub pending: usize,
+    pub running: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+/// Held by the process running a queue, see [`JobQueue::lock_runner`]
+#[derive(Debug)]
+pub struct RunnerLock {
+    _file: std::fs::File,
+}
+
+/// Jobs stored in a queue file
+#[derive(Debug, Clone)]
+pub struct JobQueue {
+    path: PathBuf,
+    jobs: Vec<Job>,
+    /// Lines that could not be read, written back unchanged on save
+    invalid: Vec<String>,
+}
+
+impl JobQueue {
+    /// Default location of the queue file
+    pub fn default_path() -> Option<PathBuf> {
+        ProjectDirs::from("dev.gaetans", "", "purr").map(|dirs| dirs.data_dir().join(QUEUE_FILE))
+    }
+
+    /// Open a queue file (empty if missing)
+    ///
+    /// Unreadable lines are skipped with a warning rather than losing the
+    /// whole queue, and kept as they are when the queue is saved.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(WhisperError::io(
+                    format!("Failed to read job queue {}", path.display()),
+                    e,
+                ))
+            }
+        };
+
+        let mut jobs = Vec::new();
+        let mut invalid = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(job) => jobs.push(job),
+                Err(e) => {
+                    warn!(
+                        "Ignoring invalid job on line {} of {}: {}",
+                        index + 1,
+                        path.display(),
+                        e
+                    );
+                    invalid.push(line.to_string());
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            jobs,
+            invalid,
+        })
+    }
+
+    /// Change a queue file while holding its lock
+    ///
+    /// The queue is read again under the lock, so changes made by other
+    /// processes since it was last opened are kept.
+    pub fn update<P, F, T>(path: P, change: F) -> Result<T>
+    where
+        P: Into<PathBuf>,
+        F: FnOnce(&mut Self) -> T,
+    {
+        let path = path.into();
+        let _lock = state_file::lock(&path)?;
+        let mut queue = Self::open(path)?;
+        let value = change(&mut queue);
+        queue.save()?;
+        Ok(value)
+    }
+
+    /// Become the only process running a queue
+    ///
+    /// Returns `None` if another process is already running it. Jobs left
+    /// running are only interrupted ones while the lock is held.
+    pub fn lock_runner<P: AsRef<Path>>(path: P) -> Result<Option<RunnerLock>> {
+        let mut name = path.as_ref().file_name().unwrap_or_default().to_os_string();
+        name.push(".run");
+        let file = state_file::try_lock(&path.as_ref().with_file_name(name))?;
+        Ok(file.map(|file| RunnerLock { _file: file }))
+    }
+
+    /// Path of the queue file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All jobs, in the order they were added
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Find a job by id
+    pub fn get(&self, id: u64) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    /// Number of jobs in each state
+    pub fn counts(&self) -> QueueCounts {
+        let mut counts = QueueCounts::default();
+        for job in &self.jobs {
+            match job.status {
+                JobStatus::Pending => counts.pending += 1,
+                JobStatus::Running => counts.running += 1,
+                JobStatus::Done => counts.done += 1,
+                JobStatus::Failed => counts.failed += 1,
+            }
+        }
+        counts
+    }
+
+    /// Add a file to the queue
+    ///
+    /// Returns `None` if the file is already waiting or running.
+    pub fn add<P: Into<PathBuf>>(&mut self, input: P) -> Option<u64> {
+        let input = input.into();
+        if self
+            .jobs
+            .iter()
+            .any(|job| job.input == input && !job.is_finished())
+        {
+            return None;
+        }
+
+        let id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        let now = unix_now();
+        self.jobs.push(Job {
+            id,
+            input,
+            status: JobStatus::Pending,
+            attempts: 0,
+            error: None,
+            output: None,
+            stats: None,
+            added_at: now,
+            updated_at: now,
+        });
+        Some(id)
+    }
+
+    /// Put jobs left running by an interrupted run back in the queue
+    ///
+    /// The interrupted attempt still counts, so a file that crashes the
+    /// process is not retried forever. Returns the number of recovered jobs.
+    pub fn recover(&mut self) -> usize {
+        let now = unix_now();
+        let mut recovered = 0;
+        for job in &mut self.jobs {
+            if job.status == JobStatus::Running {
+                job.status = JobStatus::Pending;
+                job.updated_at = now;
+                recovered += 1;
+            }
+        }
+        recovered
+    }
+
+    /// Mark the next pending job as running and return it
+    pub fn claim(&mut self) -> Option<Job> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.status == JobStatus::Pending)?;
+        job.status = JobStatus::Running;
+        job.attempts += 1;
+        job.error = None;
+        job.updated_at = unix_now();
+        Some(job.clone())
+    }
+
+    /// Record a successful transcription
+    pub fn complete(&mut self, id: u64, output: PathBuf, stats: TranscriptionStats) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.status = JobStatus::Done;
+            job.error = None;
+            job.output = Some(output);
+            job.stats = Some(stats);
+            job.updated_at = unix_now();
+        }
+    }
+
+    /// Record a failed attempt
+    ///
+    /// The job goes back in the queue until it has been attempted
+    /// `max_attempts` times. Returns the new status.
+    pub fn fail(&mut self, id: u64, error: String, max_attempts: u32) -> Option<JobStatus> {
+        let job = self.jobs.iter_mut().find(|job| job.id == id)?;
+        job.status = if job.attempts < max_attempts {
+            JobStatus::Pending
+        } else {
+            JobStatus::Failed
+        };
+        job.error = Some(error);
+        job.updated_at = unix_now();
+        Some(job.status)
+    }
+
+    /// Put failed jobs back in the queue with a fresh attempt count
+    pub fn retry_failed(&mut self) -> usize {
+        let now = unix_now();
+        let mut retried = 0;
+        for job in &mut self.jobs {
+            if job.status == JobStatus::Failed {
+                job.status = JobStatus::Pending;
+                job.attempts = 0;
+                job.updated_at = now;
+                retried += 1;
+            }
+        }
+        retried
+    }
+
+    /// Write the queue back to disk
+    ///
+    /// Use [`JobQueue::update`] to change a queue file that other processes
+    /// may be using.
+    pub fn save(&self) -> Result<()> {
+        let mut contents = String::new();
+        for job in &self.jobs {
+            let line = serde_json::to_string(job).map_err(|e| {
+                WhisperError::Unknown(format!("Failed to serialize job {}: {}", job.id, e))
+            })?;
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+        for line in &self.invalid {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+
+        state_file::write_atomic(&self.path, contents.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> TranscriptionStats {
+        TranscriptionStats::new(2.0, 10.0, 3, 25)
+    }
+
+    #[test]
+    fn test_queue_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+
+        let mut queue = JobQueue::open(&path).unwrap();
+        assert!(queue.jobs().is_empty());
+        assert_eq!(queue.add("a.wav"), Some(1));
+        assert_eq!(queue.add("b.wav"), Some(2));
+        // Already queued
+        assert_eq!(queue.add("a.wav"), None);
+
+        let job = queue.claim().unwrap();
+        assert_eq!(job.id, 1);
+        queue.complete(job.id, PathBuf::from("a.json"), stats());
+        queue.save().unwrap();
+
+        let queue = JobQueue::open(&path).unwrap();
+        let job = queue.get(1).unwrap();
+        assert_eq!(job.status, JobStatus::Done);
+        assert_eq!(job.output, Some(PathBuf::from("a.json")));
+        assert_eq!(job.stats.as_ref().unwrap().word_count, 25);
+        assert_eq!(
+            queue.counts(),
+            QueueCounts {
+                pending: 1,
+                done: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut queue = JobQueue::open(dir.path().join(QUEUE_FILE)).unwrap();
+        queue.add("a.wav");
+
+        let job = queue.claim().unwrap();
+        assert_eq!(
+            queue.fail(job.id, "boom".to_string(), 2),
+            Some(JobStatus::Pending)
+        );
+        let job = queue.claim().unwrap();
+        assert_eq!(job.attempts, 2);
+        assert_eq!(
+            queue.fail(job.id, "boom".to_string(), 2),
+            Some(JobStatus::Failed)
+        );
+        assert!(queue.claim().is_none());
+        assert_eq!(queue.get(1).unwrap().error.as_deref(), Some("boom"));
+
+        // Finished jobs can be added again
+        assert_eq!(queue.add("a.wav"), Some(2));
+
+        assert_eq!(queue.retry_failed(), 1);
+        assert_eq!(queue.get(1).unwrap().attempts, 0);
+    }
+
+    #[test]
+    fn test_recover_interrupted_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+
+        let mut queue = JobQueue::open(&path).unwrap();
+        queue.add("a.wav");
+        queue.add("b.wav");
+        queue.claim().unwrap();
+        queue.save().unwrap();
+
+        // The process was killed while job 1 was running
+        let mut queue = JobQueue::open(&path).unwrap();
+        assert_eq!(queue.get(1).unwrap().status, JobStatus::Running);
+        assert_eq!(queue.recover(), 1);
+
+        let job = queue.claim().unwrap();
+        assert_eq!(job.id, 1);
+        assert_eq!(job.attempts, 2);
+    }
+
+    #[test]
+    fn test_concurrent_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+
+        let runner = JobQueue::lock_runner(&path).unwrap();
+        assert!(runner.is_some());
+        assert!(JobQueue::lock_runner(&path).unwrap().is_none());
+
+        JobQueue::update(&path, |queue| queue.add("a.wav")).unwrap();
+        let job = JobQueue::update(&path, |queue| queue.claim())
+            .unwrap()
+            .unwrap();
+
+        // Another process adds a job while the first one is transcribed
+        assert_eq!(
+            JobQueue::update(&path, |queue| queue.add("b.wav")).unwrap(),
+            Some(2)
+        );
+        JobQueue::update(&path, |queue| {
+            queue.complete(job.id, PathBuf::from("a.json"), stats())
+        })
+        .unwrap();
+
+        let queue = JobQueue::open(&path).unwrap();
+        assert_eq!(queue.get(1).unwrap().status, JobStatus::Done);
+        assert_eq!(queue.get(2).unwrap().status, JobStatus::Pending);
+
+        drop(runner);
+        assert!(JobQueue::lock_runner(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_skips_invalid_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+
+        let mut queue = JobQueue::open(&path).unwrap();
+        queue.add("a.wav");
+        queue.save().unwrap();
+
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        contents.push_str("{\"id\": 2, \"inp\n");
+        std::fs::write(&path, contents).unwrap();
+
+        let mut queue = JobQueue::open(&path).unwrap();
+        assert_eq!(queue.jobs().len(), 1);
+
+        // Saving keeps the line for the user to fix
+        queue.add("b.wav");
+        queue.save().unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.lines().any(|line| line == "{\"id\": 2, \"inp"));
+        assert_eq!(JobQueue::open(&path).unwrap().jobs().len(), 2);
+    }
+}
@@ -0,0 +1,301 @@
+//! Splitting audio into per-segment clips for dataset building
+
+use crate::{
+    audio::{AudioData, AudioProcessor, ClipFormat},
+    error::{Result, WhisperError},
+    vad::{detect_speech, VadConfig},
+    whisper::{sync::SyncWhisperTranscriber, TranscriptionSegment, WhisperTranscriber},
+    TranscriptionConfig,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// How audio is cut into clips
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitMode {
+    /// One clip per transcription segment
+    #[default]
+    Segments,
+    /// One clip per region of detected speech
+    Vad,
+}
+
+/// Options for [`split_file`]
+#[derive(Debug, Clone)]
+pub struct SplitOptions {
+    /// How to cut the audio
+    pub mode: SplitMode,
+
+    /// Container format of the written clips
+    pub format: ClipFormat,
+
+    /// Directory the clips are written to
+    pub output_dir: PathBuf,
+
+    /// Voice activity detection settings (used in [`SplitMode::Vad`])
+    pub vad: VadConfig,
+}
+
+impl SplitOptions {
+    /// Create options writing clips to the given directory
+    pub fn new<P: Into<PathBuf>>(output_dir: P) -> Self {
+        Self {
+            mode: SplitMode::default(),
+            format: ClipFormat::default(),
+            output_dir: output_dir.into(),
+            vad: VadConfig::default(),
+        }
+    }
+
+    /// Set the split mode
+    pub fn with_mode(mut self, mode: SplitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the clip format
+    pub fn with_format(mut self, format: ClipFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// A written clip, as recorded in the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path of the clip file
+    pub path: PathBuf,
+
+    /// Start time in the source audio (seconds)
+    pub start: f64,
+
+    /// End time in the source audio (seconds)
+    pub end: f64,
+
+    /// Transcribed text of the clip
+    pub text: String,
+}
+
+/// A clip to cut from the source audio
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipSpec {
+    /// Start time in seconds
+    pub start: f64,
+
+    /// End time in seconds
+    pub end: f64,
+
+    /// Transcribed text of the clip
+    pub text: String,
+}
+
+/// Transcribe a file and write each segment or speech region as a separate clip
+pub async fn split_file<P: AsRef<Path>>(
+    audio_path: P,
+    config: Option<TranscriptionConfig>,
+    options: &SplitOptions,
+) -> Result<Vec<ManifestEntry>> {
+    let audio_path = audio_path.as_ref();
+    let config = config.unwrap_or_default();
+
+    let transcriber = SyncWhisperTranscriber::from_config(config).await?;
+    let mut audio_processor = AudioProcessor::new()?;
+    let audio = audio_processor.load_audio(audio_path).await?;
+
+    info!("Transcribing audio before splitting...");
+    let result = transcriber.transcribe(audio.clone()).await?;
+
+    let clips = plan_clips(&result.segments, &audio, options);
+
+    tokio::fs::create_dir_all(&options.output_dir).await?;
+    let stem = audio_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "clip".to_string());
+
+    let mut entries = Vec::with_capacity(clips.len());
+    for (i, clip) in clips.into_iter().enumerate() {
+        let path = options.output_dir.join(format!(
+            "{}_{:04}.{}",
+            stem,
+            i + 1,
+            options.format.extension()
+        ));
+
+        audio_processor
+            .write_clip(&path, audio.slice(clip.start, clip.end), options.format)
+            .await?;
+
+        entries.push(ManifestEntry {
+            path,
+            start: clip.start,
+            end: clip.end,
+            text: clip.text,
+        });
+    }
+
+    info!(
+        "Wrote {} clips to {}",
+        entries.len(),
+        options.output_dir.display()
+    );
+
+    Ok(entries)
+}
+
+/// Decide which clips to cut, attaching the transcribed text to each
+pub fn plan_clips(
+    segments: &[TranscriptionSegment],
+    audio: &AudioData,
+    options: &SplitOptions,
+) -> Vec<ClipSpec> {
+    match options.mode {
+        SplitMode::Segments => segments
+            .iter()
+            .filter(|segment| segment.end > segment.start)
+            .map(|segment| ClipSpec {
+                start: segment.start,
+                end: segment.end.min(audio.duration as f64),
+                text: segment.text.trim().to_string(),
+            })
+            .collect(),
+        SplitMode::Vad => {
+            let regions = detect_speech(&audio.samples, audio.sample_rate, &options.vad);
+            let mut texts = vec![Vec::new(); regions.len()];
+
+            // Each segment goes to the region it overlaps most, so text is never duplicated
+            for segment in segments {
+                let best = regions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, region)| {
+                        let overlap = segment.end.min(region.end) - segment.start.max(region.start);
+                        (i, overlap)
+                    })
+                    .filter(|&(_, overlap)| overlap > 0.0)
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+
+                if let Some((i, _)) = best {
+                    texts[i].push(segment.text.trim());
+                }
+            }
+
+            regions
+                .iter()
+                .zip(texts)
+                .map(|(region, text)| ClipSpec {
+                    start: region.start,
+                    end: region.end,
+                    text: text.join(" "),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Write manifest entries as JSON lines
+pub fn write_manifest<P: AsRef<Path>>(path: P, entries: &[ManifestEntry]) -> Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for entry in entries {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| WhisperError::Unknown(format!("Failed to serialize manifest: {}", e)))?;
+        writeln!(file, "{}", line)?;
+    }
+    file.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, start: f64, end: f64) -> TranscriptionSegment {
+        TranscriptionSegment {
+            text: text.to_string(),
+            start,
+            end,
+            confidence: None,
+            words: None,
+            no_speech_prob: None,
+            hallucination: None,
+            speaker_turn_next: false,
+            tokens: None,
+        }
+    }
+
+    fn audio(samples: Vec<f32>) -> AudioData {
+        let duration = samples.len() as f32 / 16000.0;
+        AudioData {
+            samples,
+            sample_rate: 16000,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_plan_segment_clips() {
+        let segments = vec![segment(" Hello", 0.0, 1.5), segment(" world", 1.5, 3.0)];
+        let clips = plan_clips(
+            &segments,
+            &audio(vec![0.0; 32000]),
+            &SplitOptions::new("out"),
+        );
+
+        assert_eq!(
+            clips,
+            vec![
+                ClipSpec {
+                    start: 0.0,
+                    end: 1.5,
+                    text: "Hello".to_string()
+                },
+                ClipSpec {
+                    start: 1.5,
+                    end: 2.0,
+                    text: "world".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_vad_clips_assigns_text() {
+        let tone = |seconds: usize| vec![0.5f32; seconds * 16000];
+        let silence = |seconds: usize| vec![0.0f32; seconds * 16000];
+        let samples = [silence(1), tone(1), silence(1), tone(1), silence(1)].concat();
+
+        let segments = vec![
+            segment(" one", 0.8, 2.2),
+            segment(" two", 2.8, 3.6),
+            segment(" three", 3.6, 4.2),
+        ];
+        let options = SplitOptions::new("out").with_mode(SplitMode::Vad);
+        let clips = plan_clips(&segments, &audio(samples), &options);
+
+        assert_eq!(clips.len(), 2);
+        assert_eq!(clips[0].text, "one");
+        assert_eq!(clips[1].text, "two three");
+    }
+
+    #[test]
+    fn test_write_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("manifest.jsonl");
+        let entries = vec![ManifestEntry {
+            path: PathBuf::from("clips/a_0001.wav"),
+            start: 0.0,
+            end: 1.0,
+            text: "Hello".to_string(),
+        }];
+
+        write_manifest(&path, &entries).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let parsed: ManifestEntry = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(parsed, entries[0]);
+    }
+}
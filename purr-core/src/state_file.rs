@@ -0,0 +1,98 @@
+//! Crash-safe writes and cross-process locks for the state files purr keeps
+//! on disk (usage store, job queue, transcript cache)
+
+use crate::error::{Result, WhisperError};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Write a file through a uniquely named temporary file and a rename
+///
+/// A crash never leaves a truncated file behind, and concurrent writers
+/// never share a temporary file.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(contents)?;
+    temp.persist(path)
+        .map_err(|e| WhisperError::io(format!("Failed to write {}", path.display()), e.error))?;
+    Ok(())
+}
+
+/// Path of the lock file guarding `path`
+pub(crate) fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Take an exclusive lock on `path`, waiting for other processes to release it
+///
+/// The lock is held until the returned file is dropped.
+pub(crate) fn lock(path: &Path) -> Result<File> {
+    let lock_path = lock_path(path);
+    let file = open_lock(&lock_path)?;
+    file.lock()
+        .map_err(|e| WhisperError::io(format!("Failed to lock {}", lock_path.display()), e))?;
+    Ok(file)
+}
+
+/// Take an exclusive lock on `path` without waiting
+///
+/// Returns `None` if another process holds it.
+pub(crate) fn try_lock(path: &Path) -> Result<Option<File>> {
+    let lock_path = lock_path(path);
+    let file = open_lock(&lock_path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => Err(WhisperError::io(
+            format!("Failed to lock {}", lock_path.display()),
+            e,
+        )),
+    }
+}
+
+/// Open (or create) a lock file
+fn open_lock(lock_path: &Path) -> Result<File> {
+    if let Some(dir) = lock_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)
+        .map_err(|e| WhisperError::io(format!("Failed to open {}", lock_path.display()), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_and_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("store.json");
+
+        write_atomic(&path, b"one").unwrap();
+        write_atomic(&path, b"two").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+        // Only the file itself is left
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        let held = lock(&path).unwrap();
+        assert!(try_lock(&path).unwrap().is_none());
+        drop(held);
+        assert!(try_lock(&path).unwrap().is_some());
+        assert_eq!(lock_path(&path), dir.path().join("state/store.json.lock"));
+    }
+}
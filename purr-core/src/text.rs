@@ -0,0 +1,458 @@
+//! Post-processing of transcript text
+//!
+//! Runs on finished segments and streaming chunks alike: trims the leading
+//! space whisper puts before each segment, collapses whitespace, writes
+//! spoken numbers as digits ("twenty five dollars" becomes "$25") and masks
+//! words from a user-supplied list.
+
+use crate::eval::{ONES, SCALES, TENS};
+use crate::whisper::{StreamingChunk, SyncTranscriptionResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Text post-processing options
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextProcessing {
+    /// Remove leading and trailing whitespace from each segment
+    pub trim: bool,
+
+    /// Replace runs of whitespace with a single space
+    pub collapse_whitespace: bool,
+
+    /// Write spoken English numbers, amounts and percentages with digits
+    pub inverse_normalization: bool,
+
+    /// Words to mask, compared ignoring case
+    pub masked_words: Vec<String>,
+}
+
+impl TextProcessing {
+    /// Whether any processing is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.trim
+            || self.collapse_whitespace
+            || self.inverse_normalization
+            || !self.masked_words.is_empty()
+    }
+
+    /// Process a piece of text
+    pub fn process(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.inverse_normalization {
+            text = inverse_normalize(&text);
+        }
+        if !self.masked_words.is_empty() {
+            text = mask_words(&text, &self.masked_words);
+        }
+        if self.collapse_whitespace {
+            text = collapse_whitespace(&text);
+        }
+        if self.trim {
+            text = text.trim().to_string();
+        }
+        text
+    }
+
+    /// Process the segments of a result and rebuild its text from them
+    ///
+    /// Trimmed segments are joined with single spaces.
+    pub fn apply_result(&self, result: &mut SyncTranscriptionResult) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        for segment in &mut result.segments {
+            segment.text = self.process(&segment.text);
+            if let Some(ref mut words) = segment.words {
+                if !self.masked_words.is_empty() {
+                    for word in words {
+                        word.word = mask_words(&word.word, &self.masked_words);
+                    }
+                }
+            }
+        }
+
+        result.text = if result.segments.is_empty() {
+            self.process(&result.text)
+        } else if self.trim {
+            result
+                .segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            result
+                .segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect()
+        };
+    }
+
+    /// Process the text of a streaming chunk
+    ///
+    /// Consumers concatenate chunks as they come, so a trimmed chunk keeps a
+    /// single leading space if it had one, and words of consecutive chunks
+    /// stay apart.
+    pub fn apply_chunk(&self, chunk: &mut StreamingChunk) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let separated = chunk.text.starts_with(char::is_whitespace);
+        chunk.text = self.process(&chunk.text);
+        if self.trim && separated && !chunk.text.is_empty() {
+            chunk.text.insert(0, ' ');
+        }
+    }
+}
+
+/// Replace runs of whitespace with a single space, keeping one at either end
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// Mask listed words, keeping their first letter ("darn" becomes "d***")
+fn mask_words(text: &str, masked: &[String]) -> String {
+    let masked: HashSet<String> = masked.iter().map(|w| w.trim().to_lowercase()).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+
+    for range in word_ranges(text, |c| c.is_alphanumeric() || c == '\'') {
+        let word = text[range.clone()].trim_matches('\'');
+        if !masked.contains(&word.to_lowercase()) {
+            continue;
+        }
+        let start = range.start + text[range.clone()].find(word).unwrap_or(0);
+        let mut chars = word.chars();
+        out.push_str(&text[last..start]);
+        out.extend(chars.next());
+        out.extend(chars.map(|_| '*'));
+        last = start + word.len();
+    }
+
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Byte ranges of the runs of characters matching `is_word`
+fn word_ranges(text: &str, is_word: impl Fn(char) -> bool) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (is_word(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                ranges.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push(s..text.len());
+    }
+    ranges
+}
+
+/// Write spoken English numbers with digits
+///
+/// Handles cardinals ("two hundred and five"), years ("nineteen ninety
+/// four"), decimals ("three point five"), dollar amounts with cents and
+/// percentages. Single-word numbers below ten stay spelled out unless they
+/// are an amount or a percentage, as in most style guides.
+pub fn inverse_normalize(text: &str) -> String {
+    let words = word_ranges(text, |c| c.is_alphabetic() || c == '\'');
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut i = 0;
+
+    while i < words.len() {
+        match parse_number(text, &words, i) {
+            Some((replacement, end)) => {
+                out.push_str(&text[last..words[i].start]);
+                out.push_str(&replacement);
+                last = words[end - 1].end;
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+
+    out.push_str(&text[last..]);
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberWord {
+    /// zero to nine
+    Unit(u64),
+    /// ten to nineteen
+    Teen(u64),
+    /// twenty, thirty...
+    Tens(u64),
+    Hundred,
+    /// thousand, million...
+    Scale(u64),
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    let word = word.to_lowercase();
+    if let Some(value) = ONES.iter().position(|w| *w == word) {
+        let value = value as u64;
+        return Some(if value < 10 {
+            NumberWord::Unit(value)
+        } else {
+            NumberWord::Teen(value)
+        });
+    }
+    if let Some(value) = TENS.iter().position(|w| !w.is_empty() && *w == word) {
+        return Some(NumberWord::Tens(value as u64 * 10));
+    }
+    if word == "hundred" {
+        return Some(NumberWord::Hundred);
+    }
+    SCALES
+        .iter()
+        .find(|(_, name)| *name == word)
+        .map(|(scale, _)| NumberWord::Scale(*scale))
+}
+
+/// Lowercase word at index `k`
+fn word_at(text: &str, words: &[Range<usize>], k: usize) -> String {
+    text[words[k].clone()].to_lowercase()
+}
+
+/// Whether word `k` follows the previous word with only a space or hyphen between
+fn joined(text: &str, words: &[Range<usize>], k: usize) -> bool {
+    if k == 0 || k >= words.len() {
+        return false;
+    }
+    let gap = &text[words[k - 1].end..words[k].start];
+    gap == "-" || (!gap.is_empty() && gap.chars().all(char::is_whitespace))
+}
+
+/// Parse a spelled-out integer starting at word `i`
+///
+/// Returns the value, the index after its last word and how many number
+/// words it used.
+fn parse_integer(text: &str, words: &[Range<usize>], i: usize) -> Option<(u64, usize, usize)> {
+    let mut total = 0u64;
+    let mut current = 0u64;
+    let mut last: Option<NumberWord> = None;
+    let mut last_scale = u64::MAX;
+    let mut count = 0;
+    let mut end = i;
+    let mut k = i;
+
+    while k < words.len() && (k == i || joined(text, words, k)) {
+        let word = word_at(text, words, k);
+
+        // "two hundred and five"
+        if word == "and"
+            && matches!(last, Some(NumberWord::Hundred | NumberWord::Scale(_)))
+            && joined(text, words, k + 1)
+            && matches!(
+                number_word(&word_at(text, words, k + 1)),
+                Some(NumberWord::Unit(1..) | NumberWord::Teen(_) | NumberWord::Tens(_))
+            )
+        {
+            k += 1;
+            continue;
+        }
+
+        let Some(number) = number_word(&word) else {
+            break;
+        };
+        current = match (number, last) {
+            (
+                NumberWord::Unit(v),
+                None | Some(NumberWord::Tens(_) | NumberWord::Hundred | NumberWord::Scale(_)),
+            ) => current + v,
+            (
+                NumberWord::Teen(v) | NumberWord::Tens(v),
+                None | Some(NumberWord::Hundred | NumberWord::Scale(_)),
+            ) => current + v,
+            // Years: "nineteen ninety", "twenty twenty"
+            (NumberWord::Teen(v) | NumberWord::Tens(v), Some(NumberWord::Teen(_)))
+                if total == 0 =>
+            {
+                current * 100 + v
+            }
+            (NumberWord::Teen(v) | NumberWord::Tens(v), Some(NumberWord::Tens(_)))
+                if total == 0 && (20..100).contains(&current) =>
+            {
+                current * 100 + v
+            }
+            (NumberWord::Hundred, Some(NumberWord::Unit(1..) | NumberWord::Teen(_)))
+                if current < 100 =>
+            {
+                current * 100
+            }
+            (NumberWord::Scale(scale), Some(last))
+                if scale < last_scale && !matches!(last, NumberWord::Scale(_)) && current > 0 =>
+            {
+                total = total.checked_add(current.checked_mul(scale)?)?;
+                last_scale = scale;
+                0
+            }
+            _ => break,
+        };
+
+        last = Some(number);
+        count += 1;
+        k += 1;
+        end = k;
+    }
+
+    (count > 0).then_some((total + current, end, count))
+}
+
+/// Parse a spoken number with its decimals and unit starting at word `i`
+///
+/// Returns the written number and the index after its last word.
+fn parse_number(text: &str, words: &[Range<usize>], i: usize) -> Option<(String, usize)> {
+    let (value, mut end, count) = parse_integer(text, words, i)?;
+    let mut number = value.to_string();
+
+    // "three point one four"
+    if joined(text, words, end) && word_at(text, words, end) == "point" {
+        let mut decimals = String::new();
+        let mut k = end + 1;
+        while joined(text, words, k) {
+            match number_word(&word_at(text, words, k)) {
+                Some(NumberWord::Unit(digit)) => decimals.push_str(&digit.to_string()),
+                _ => break,
+            }
+            k += 1;
+        }
+        if !decimals.is_empty() {
+            number = format!("{}.{}", number, decimals);
+            end = k;
+        }
+    }
+
+    let next = |k: usize| joined(text, words, k).then(|| word_at(text, words, k));
+    match next(end).as_deref() {
+        Some("dollar" | "dollars") => {
+            end += 1;
+            // "five dollars and fifty cents"
+            if next(end).as_deref() == Some("and")
+                && joined(text, words, end + 1)
+                && !number.contains('.')
+            {
+                if let Some((cents, cents_end, _)) = parse_integer(text, words, end + 1) {
+                    if cents < 100 && matches!(next(cents_end).as_deref(), Some("cent" | "cents")) {
+                        number = format!("{}.{:02}", number, cents);
+                        end = cents_end + 1;
+                    }
+                }
+            }
+            Some((format!("${}", number), end))
+        }
+        Some("percent") => Some((format!("{}%", number), end + 1)),
+        Some("per") if next(end + 1).as_deref() == Some("cent") => {
+            Some((format!("{}%", number), end + 2))
+        }
+        _ if count == 1 && value < 10 && !number.contains('.') => None,
+        _ => Some((number, end)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inverse_normalize() {
+        let cases = [
+            ("It costs twenty five dollars.", "It costs $25."),
+            ("Twenty-five percent of them", "25% of them"),
+            ("two hundred and five people", "205 people"),
+            ("one thousand two hundred thirty four", "1234"),
+            ("three million", "3000000"),
+            ("three point one four", "3.14"),
+            ("five dollars and fifty cents", "$5.50"),
+            ("back in nineteen ninety four", "back in 1994"),
+            ("in twenty twenty four, it rained", "in 2024, it rained"),
+            ("one of the two cats", "one of the two cats"),
+            ("one, two, three", "one, two, three"),
+            ("five percent", "5%"),
+            ("There and back", "There and back"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(inverse_normalize(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_mask_words() {
+        let masked = vec!["darn".to_string(), "Heck".to_string()];
+        assert_eq!(
+            mask_words("Darn it, what the heck? 'darn'", &masked),
+            "D*** it, what the h***? 'd***'"
+        );
+        assert_eq!(mask_words("darned", &masked), "darned");
+    }
+
+    #[test]
+    fn test_chunks_stay_separated() {
+        let processing = TextProcessing {
+            trim: true,
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        let chunk = |text: &str| StreamingChunk {
+            text: text.to_string(),
+            start: 0.0,
+            end: 1.0,
+            is_final: true,
+            chunk_index: 0,
+            final_stats: None,
+            status: Default::default(),
+            error: None,
+        };
+
+        let mut text = String::new();
+        for piece in [" Hello   world.  ", " Hello again. ", "", "还有"] {
+            let mut chunk = chunk(piece);
+            processing.apply_chunk(&mut chunk);
+            text.push_str(&chunk.text);
+        }
+        assert_eq!(text, " Hello world. Hello again.还有");
+    }
+
+    #[test]
+    fn test_process() {
+        let processing = TextProcessing {
+            trim: true,
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(processing.process("  Hello   there \n"), "Hello there");
+
+        let processing = TextProcessing {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(processing.process("  Hello   there"), " Hello there");
+        assert!(!TextProcessing::default().is_enabled());
+    }
+}
@@ -0,0 +1,281 @@
+//! Energy-based voice activity detection
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Voice activity detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VadConfig {
+    /// Analysis frame length in seconds
+    pub frame_duration: f32,
+
+    /// Absolute energy threshold in dBFS below which a frame is always silence
+    pub threshold_db: f32,
+
+    /// How far above the estimated noise floor (and below the loudest frame) the
+    /// adaptive threshold sits, in dB
+    pub noise_margin_db: f32,
+
+    /// Speech regions shorter than this (seconds) are dropped
+    pub min_speech_duration: f32,
+
+    /// Silences shorter than this (seconds) do not split a region
+    pub min_silence_duration: f32,
+
+    /// Padding added on both sides of each region (seconds)
+    pub padding: f32,
+
+    /// Regions longer than this (seconds) are split at their quietest point
+    pub max_region_duration: Option<f32>,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            frame_duration: 0.03,
+            threshold_db: -50.0,
+            noise_margin_db: 12.0,
+            min_speech_duration: 0.25,
+            min_silence_duration: 0.4,
+            padding: 0.1,
+            // Whisper processes 30 second windows
+            max_region_duration: Some(30.0),
+        }
+    }
+}
+
+/// A detected region of speech
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeechRegion {
+    /// Start time in seconds
+    pub start: f64,
+
+    /// End time in seconds
+    pub end: f64,
+}
+
+impl SpeechRegion {
+    /// Region length in seconds
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Detect regions of speech in mono audio samples
+pub fn detect_speech(samples: &[f32], sample_rate: u32, config: &VadConfig) -> Vec<SpeechRegion> {
+    let frame_len = ((config.frame_duration * sample_rate as f32) as usize).max(1);
+    let energies: Vec<f32> = samples.chunks(frame_len).map(frame_energy_db).collect();
+    if energies.is_empty() {
+        return Vec::new();
+    }
+
+    // Sit above the noise floor, but never so high that uniformly loud audio is all silence
+    let peak = energies.iter().copied().fold(f32::MIN, f32::max);
+    let adaptive =
+        (noise_floor_db(&energies) + config.noise_margin_db).min(peak - config.noise_margin_db);
+    let threshold = config.threshold_db.max(adaptive);
+    let frame_secs = frame_len as f64 / sample_rate as f64;
+    let total = samples.len() as f64 / sample_rate as f64;
+
+    // Collect runs of consecutive speech frames as (first, last exclusive) frame indices
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut current: Option<usize> = None;
+    for (i, &energy) in energies.iter().enumerate() {
+        match (energy >= threshold, current) {
+            (true, None) => current = Some(i),
+            (false, Some(start)) => {
+                runs.push((start, i));
+                current = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = current {
+        runs.push((start, energies.len()));
+    }
+
+    // Bridge short silences
+    let min_gap = (config.min_silence_duration as f64 / frame_secs).ceil() as usize;
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for run in runs {
+        match merged.last_mut() {
+            Some(last) if run.0 - last.1 < min_gap => last.1 = run.1,
+            _ => merged.push(run),
+        }
+    }
+
+    // Split overlong regions at their quietest frame
+    let mut split = Vec::new();
+    let max_frames = config
+        .max_region_duration
+        .map(|max| ((max as f64 - 2.0 * config.padding as f64) / frame_secs).max(2.0) as usize);
+    for run in merged {
+        split_run(run, &energies, max_frames, &mut split);
+    }
+
+    split
+        .into_iter()
+        .filter(|&(start, end)| {
+            (end - start) as f64 * frame_secs >= config.min_speech_duration as f64
+        })
+        .map(|(start, end)| SpeechRegion {
+            start: (start as f64 * frame_secs - config.padding as f64).max(0.0),
+            end: (end as f64 * frame_secs + config.padding as f64).min(total),
+        })
+        .collect()
+}
+
+fn split_run(
+    (start, end): (usize, usize),
+    energies: &[f32],
+    max_frames: Option<usize>,
+    out: &mut Vec<(usize, usize)>,
+) {
+    match max_frames {
+        Some(max) if end - start > max => {
+            // Cut in the second half of the allowed window so pieces stay reasonably long
+            let window = (start + max / 2)..(start + max);
+            let cut = window
+                .min_by(|&a, &b| energies[a].total_cmp(&energies[b]))
+                .unwrap_or(start + max);
+            out.push((start, cut));
+            split_run((cut, end), energies, max_frames, out);
+        }
+        _ => out.push((start, end)),
+    }
+}
+
+/// Cut audio into `parts` pieces of similar length, at silences
+///
+/// Each cut is placed on the quietest frame within `search` seconds of the
+/// even split point. Returns sample ranges covering the whole input, fewer
+/// than `parts` if the audio is too short.
+pub fn split_at_silence(
+    samples: &[f32],
+    sample_rate: u32,
+    parts: usize,
+    search: f32,
+) -> Vec<Range<usize>> {
+    let frame_len = ((VadConfig::default().frame_duration * sample_rate as f32) as usize).max(1);
+    let energies: Vec<f32> = samples.chunks(frame_len).map(frame_energy_db).collect();
+    let search_frames = (search * sample_rate as f32) as usize / frame_len;
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for part in 1..parts.max(1) {
+        let target = samples.len() * part / parts / frame_len;
+        let window = target
+            .saturating_sub(search_frames)
+            .max(start / frame_len + 1)
+            ..(target + search_frames + 1).min(energies.len());
+        let Some(cut) = window.min_by(|&a, &b| energies[a].total_cmp(&energies[b])) else {
+            break;
+        };
+
+        // Cut in the middle of the quietest frame
+        let end = (cut * frame_len + frame_len / 2).min(samples.len());
+        if end > start {
+            ranges.push(start..end);
+            start = end;
+        }
+    }
+    ranges.push(start..samples.len());
+    ranges
+}
+
+/// RMS energy of a frame in dBFS
+fn frame_energy_db(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    10.0 * mean_square.max(1e-10).log10()
+}
+
+/// Estimate the noise floor as the 10th percentile of frame energies
+fn noise_floor_db(energies: &[f32]) -> f32 {
+    let mut sorted = energies.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    sorted[sorted.len() / 10]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(seconds: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|i| 0.5 * (i as f32 * 440.0 * std::f32::consts::TAU / RATE as f32).sin())
+            .collect()
+    }
+
+    fn silence(seconds: f32) -> Vec<f32> {
+        vec![0.0; (seconds * RATE as f32) as usize]
+    }
+
+    #[test]
+    fn test_silence_has_no_speech() {
+        let regions = detect_speech(&silence(2.0), RATE, &VadConfig::default());
+        assert!(regions.is_empty());
+    }
+
+    #[test]
+    fn test_detects_separate_regions() {
+        let samples = [
+            silence(1.0),
+            tone(1.0),
+            silence(1.0),
+            tone(2.0),
+            silence(1.0),
+        ]
+        .concat();
+        let regions = detect_speech(&samples, RATE, &VadConfig::default());
+
+        assert_eq!(regions.len(), 2);
+        assert!((regions[0].start - 0.9).abs() < 0.05);
+        assert!((regions[0].end - 2.1).abs() < 0.05);
+        assert!((regions[1].start - 2.9).abs() < 0.05);
+        assert!((regions[1].end - 5.1).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_bridges_short_silences() {
+        let samples = [tone(1.0), silence(0.2), tone(1.0)].concat();
+        let regions = detect_speech(&samples, RATE, &VadConfig::default());
+        assert_eq!(regions.len(), 1);
+    }
+
+    #[test]
+    fn test_splits_long_regions() {
+        let config = VadConfig {
+            max_region_duration: Some(5.0),
+            ..Default::default()
+        };
+        let samples = [silence(1.0), tone(12.0), silence(1.0)].concat();
+        let regions = detect_speech(&samples, RATE, &config);
+
+        assert!(regions.len() >= 3);
+        assert!(regions.iter().all(|r| r.duration() <= 5.0 + 1e-6));
+    }
+
+    #[test]
+    fn test_split_at_silence() {
+        let samples = [tone(4.0), silence(0.5), tone(5.0), silence(0.5), tone(3.0)].concat();
+        let ranges = split_at_silence(&samples, RATE, 3, 2.0);
+
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges[2].end, samples.len());
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+        // Cuts land in the silences rather than at even thirds
+        let cut = |range: &Range<usize>| range.end as f32 / RATE as f32;
+        assert!((4.0..4.5).contains(&cut(&ranges[0])));
+        assert!((9.5..10.0).contains(&cut(&ranges[1])));
+
+        assert_eq!(
+            split_at_silence(&samples, RATE, 1, 2.0),
+            vec![0..samples.len()]
+        );
+        assert_eq!(split_at_silence(&[], RATE, 4, 2.0), vec![0..0]);
+    }
+}
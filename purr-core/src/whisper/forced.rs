@@ -0,0 +1,158 @@
+//! Decoding constrained to a known transcript
+//!
+//! A logits filter leaves the next token of the transcript as the only text
+//! token whisper.cpp can pick. Timestamp tokens and the end of a window stay
+//! open, so the model still decides where each token falls in the audio.
+
+use crate::error::{Result, WhisperError};
+use std::ffi::{c_int, c_void};
+use std::sync::Mutex;
+use whisper_rs::whisper_rs_sys::{
+    whisper_context, whisper_full_get_token_id_from_state, whisper_full_n_segments_from_state,
+    whisper_full_n_tokens_from_state, whisper_state, whisper_token_data,
+};
+use whisper_rs::{FullParams, WhisperContext, WhisperToken};
+
+/// The tokens of a transcript and how many of them are decoded
+pub(crate) struct ForcedText {
+    tokens: Vec<WhisperToken>,
+    /// Text tokens have ids below the end of text token
+    eot: WhisperToken,
+    /// Segments already counted and the text tokens they hold
+    committed: Mutex<(c_int, usize)>,
+}
+
+impl ForcedText {
+    /// Tokenize the transcript for the loaded model
+    pub(crate) fn new(context: &WhisperContext, text: &str) -> Result<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let text = format!(" {}", words.join(" "));
+        // A token holds at least one byte
+        let tokens = context
+            .tokenize(&text, text.len() + 1)
+            .map_err(|e| WhisperError::whisper("Failed to tokenize the transcript", e))?;
+
+        Ok(Self {
+            tokens,
+            eot: context.token_eot(),
+            committed: Mutex::new((0, 0)),
+        })
+    }
+
+    /// Constrain decoding with `params` to the transcript
+    ///
+    /// # Safety
+    ///
+    /// `self` must outlive every decode run with `params`.
+    pub(crate) unsafe fn apply(&self, params: &mut FullParams) {
+        params.set_filter_logits_callback(Some(filter_logits));
+        params.set_filter_logits_callback_user_data(self as *const Self as *mut c_void);
+    }
+
+    /// Text tokens kept in the segments decoded so far
+    ///
+    /// Tokens after the last timestamp of a window are dropped and decoded
+    /// again in the next window, so only finished segments count.
+    fn committed(&self, state: *mut whisper_state) -> usize {
+        let mut committed = self.committed.lock().unwrap_or_else(|e| e.into_inner());
+        let (counted, tokens) = &mut *committed;
+
+        // SAFETY: whisper.cpp passes its live state to the logits filter
+        let segments = unsafe { whisper_full_n_segments_from_state(state) };
+        for segment in *counted..segments {
+            let ids = unsafe {
+                (0..whisper_full_n_tokens_from_state(state, segment))
+                    .map(|token| whisper_full_get_token_id_from_state(state, segment, token))
+                    .collect::<Vec<_>>()
+            };
+            *tokens += count_text_tokens(ids, self.eot);
+        }
+        *counted = segments;
+        *tokens
+    }
+}
+
+/// Number of text tokens, leaving out timestamps and other special tokens
+fn count_text_tokens(ids: impl IntoIterator<Item = WhisperToken>, eot: WhisperToken) -> usize {
+    ids.into_iter().filter(|&id| id < eot).count()
+}
+
+/// Make `expected` the only text token that can be sampled
+///
+/// Without an expected token the transcript is used up, and only special
+/// tokens such as timestamps and the end of text remain.
+fn constrain(logits: &mut [f32], expected: Option<WhisperToken>, eot: WhisperToken) {
+    let text_tokens = (eot.max(0) as usize).min(logits.len());
+    for (id, logit) in logits[..text_tokens].iter_mut().enumerate() {
+        if Some(id as WhisperToken) != expected {
+            *logit = f32::NEG_INFINITY;
+        }
+    }
+}
+
+unsafe extern "C" fn filter_logits(
+    _ctx: *mut whisper_context,
+    state: *mut whisper_state,
+    tokens: *const whisper_token_data,
+    n_tokens: c_int,
+    logits: *mut f32,
+    user_data: *mut c_void,
+) {
+    if user_data.is_null() || logits.is_null() {
+        return;
+    }
+    // SAFETY: `ForcedText::apply` passes a reference that outlives the decode
+    let forced = unsafe { &*(user_data as *const ForcedText) };
+
+    let decoded = match (tokens.is_null(), usize::try_from(n_tokens)) {
+        (false, Ok(len)) => {
+            let tokens = unsafe { std::slice::from_raw_parts(tokens, len) };
+            count_text_tokens(tokens.iter().map(|token| token.id), forced.eot)
+        }
+        _ => 0,
+    };
+    let expected = forced
+        .tokens
+        .get(forced.committed(state) + decoded)
+        .copied();
+
+    let text_tokens = forced.eot.max(0) as usize;
+    let logits = unsafe { std::slice::from_raw_parts_mut(logits, text_tokens) };
+    constrain(logits, expected, forced.eot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_text_tokens() {
+        // 50257 is the end of text token of multilingual models
+        assert_eq!(
+            count_text_tokens([50364, 440, 50257, 1002, 50400], 50257),
+            2
+        );
+    }
+
+    #[test]
+    fn test_constrain() {
+        let mut logits = vec![1.0; 6];
+        constrain(&mut logits, Some(2), 4);
+        assert_eq!(
+            logits,
+            vec![
+                f32::NEG_INFINITY,
+                f32::NEG_INFINITY,
+                1.0,
+                f32::NEG_INFINITY,
+                1.0,
+                1.0
+            ]
+        );
+
+        let mut logits = vec![1.0; 6];
+        constrain(&mut logits, None, 4);
+        assert!(logits[..4].iter().all(|l| *l == f32::NEG_INFINITY));
+        assert_eq!(&logits[4..], &[1.0, 1.0]);
+    }
+}
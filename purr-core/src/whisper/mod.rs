@@ -1,14 +1,20 @@
+mod forced;
 pub mod logging;
 pub mod streaming;
 pub mod sync;
 
 use serde::{Deserialize, Serialize};
 use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::task;
-use tracing::info;
+use tracing::{debug, info};
 use whisper_rs::{WhisperContext, WhisperContextParameters};
 
-use crate::{error::Result, ModelManager, TranscriptionConfig, WhisperError};
+use crate::{
+    error::Result, hallucination::HallucinationKind, ModelManager, TranscriptionConfig,
+    WhisperError,
+};
 
 pub trait TranscriptionResult {}
 
@@ -94,10 +100,60 @@ pub struct SyncTranscriptionResult {
 
     /// Transcription statistics
     pub stats: TranscriptionStats,
+
+    /// Model that produced the result (with full details)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<ModelInfo>,
 }
 
 impl TranscriptionResult for SyncTranscriptionResult {}
 
+/// Model file and hyperparameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// Model file
+    pub path: Option<PathBuf>,
+
+    /// Model size ("base", "large"...)
+    pub model_type: String,
+
+    pub multilingual: bool,
+    pub vocab: i32,
+    pub audio_ctx: i32,
+    pub audio_state: i32,
+    pub audio_head: i32,
+    pub audio_layer: i32,
+    pub text_ctx: i32,
+    pub text_state: i32,
+    pub text_head: i32,
+    pub text_layer: i32,
+    pub mels: i32,
+
+    /// Weight type, as in the ggml file header
+    pub ftype: i32,
+}
+
+impl ModelInfo {
+    pub(crate) fn new(context: &WhisperContext, path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            model_type: context.model_type_readable().unwrap_or_default(),
+            multilingual: context.is_multilingual(),
+            vocab: context.model_n_vocab(),
+            audio_ctx: context.model_n_audio_ctx(),
+            audio_state: context.model_n_audio_state(),
+            audio_head: context.model_n_audio_head(),
+            audio_layer: context.model_n_audio_layer(),
+            text_ctx: context.model_n_text_ctx(),
+            text_state: context.model_n_text_state(),
+            text_head: context.model_n_text_head(),
+            text_layer: context.model_n_text_layer(),
+            mels: context.model_n_mels(),
+            ftype: context.model_ftype(),
+        }
+    }
+}
+
 /// A transcription segment with timestamps
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionSegment {
@@ -115,6 +171,44 @@ pub struct TranscriptionSegment {
 
     /// Word-level timestamps (if requested)
     pub words: Option<Vec<WordTimestamp>>,
+
+    /// Estimated probability that the segment has no speech (if the
+    /// hallucination filter ran)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_speech_prob: Option<f32>,
+
+    /// Why the segment looks hallucinated (if flagged)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hallucination: Option<HallucinationKind>,
+
+    /// Whether another speaker talks after this segment (with speaker turn
+    /// detection)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub speaker_turn_next: bool,
+
+    /// Every decoded token, including special and timestamp tokens (with
+    /// full details)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<TokenInfo>>,
+}
+
+/// A decoded token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenInfo {
+    /// Token id in the model vocabulary
+    pub id: i32,
+
+    /// Token text, with its leading space
+    pub text: String,
+
+    /// Start time in seconds
+    pub start: f64,
+
+    /// End time in seconds
+    pub end: f64,
+
+    /// Probability of the token
+    pub probability: f32,
 }
 
 /// Word-level timestamp information
@@ -153,6 +247,35 @@ pub struct StreamingChunk {
 
     /// Final statistics (only present on the very last chunk)
     pub final_stats: Option<TranscriptionStats>,
+
+    /// Whether the chunk could be transcribed
+    #[serde(default)]
+    pub status: ChunkStatus,
+
+    /// Why the chunk could not be transcribed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl StreamingChunk {
+    /// Whether transcription failed for this chunk, leaving a gap in the transcript
+    pub fn is_failed(&self) -> bool {
+        self.status == ChunkStatus::Failed
+    }
+}
+
+/// Outcome of transcribing a streaming chunk
+///
+/// A failed chunk has no text but keeps its time range, so callers can
+/// retry it or mark the gap in their output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStatus {
+    /// Transcribed
+    #[default]
+    Ok,
+    /// Transcription failed, the rest of the stream continues
+    Failed,
 }
 
 /// Load the Whisper model
@@ -162,6 +285,11 @@ pub(crate) async fn load_model(
 ) -> Result<WhisperContext> {
     // Determine model path
     let model_path = if let Some(path) = &config.model_path {
+        if !path.exists() {
+            return Err(WhisperError::ModelNotFound {
+                searched: vec![path.clone()],
+            });
+        }
         path.clone()
     } else {
         // Try to find a default model
@@ -180,10 +308,43 @@ pub(crate) async fn load_model(
 
     // Load the model in a blocking task since it's a synchronous operation
     let model_path_str = model_path.to_string_lossy().to_string();
-    task::spawn_blocking(move || WhisperContext::new_with_params(&model_path_str, params))
-        .await
-        .map_err(|e| WhisperError::Unknown(format!("Task join error: {}", e)))?
-        .map_err(|e| WhisperError::Whisper(e.to_string()))
+    let context =
+        task::spawn_blocking(move || WhisperContext::new_with_params(&model_path_str, params))
+            .await?
+            .map_err(|e| WhisperError::whisper("Failed to load model", e))?;
+
+    // Usage tracking is best effort and must never fail a transcription
+    if let Err(e) = model_manager.record_model_use(&model_path) {
+        debug!("Failed to record model usage: {}", e);
+    }
+
+    Ok(context)
+}
+
+/// A model loaded in memory, shared by any number of transcribers
+///
+/// Each transcription creates its own whisper state, so a single model can
+/// serve several transcriptions without being reloaded.
+#[derive(Clone)]
+pub struct SharedModel {
+    pub(crate) context: Arc<WhisperContext>,
+}
+
+impl SharedModel {
+    /// Load the model selected by `config`
+    pub async fn load(config: &TranscriptionConfig) -> Result<Self> {
+        let model_manager = ModelManager::new()?;
+        let context = load_model(config, &model_manager).await?;
+        Ok(Self {
+            context: Arc::new(context),
+        })
+    }
+}
+
+impl std::fmt::Debug for SharedModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedModel").finish_non_exhaustive()
+    }
 }
 
 pub trait WhisperTranscriber {
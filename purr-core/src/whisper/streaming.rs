@@ -1,21 +1,47 @@
 use crate::{
+    audio::AudioChunk,
     whisper::{
-        load_model, StreamingChunk, TranscriptionResult, TranscriptionStats, WhisperTranscriber,
+        load_model, ChunkStatus, SharedModel, StreamingChunk, TranscriptionResult,
+        TranscriptionStats, WhisperTranscriber,
     },
-    AudioStream, ModelManager, TranscriptionConfig,
+    AudioStream, ModelManager, TranscriptionConfig, WhisperError,
 };
 use futures::{Stream, StreamExt};
 use std::{
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 use tokio::sync::mpsc;
+use tokio::task;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::warn;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperState};
+
+/// Sample rate of the audio fed to whisper
+const SAMPLE_RATE: usize = 16000;
+
+/// When results are produced for live audio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiveOptions {
+    /// Seconds of new audio between two partial results
+    pub partial_interval: f32,
+
+    /// Seconds of audio after which a window is finalized
+    pub window: f32,
+}
+
+impl Default for LiveOptions {
+    fn default() -> Self {
+        Self {
+            partial_interval: 1.0,
+            window: AudioChunk::TARGET_DURATION,
+        }
+    }
+}
 
 pub struct StreamWhisperTranscriber {
-    context: WhisperContext,
+    context: Arc<WhisperContext>,
     config: TranscriptionConfig,
 }
 
@@ -34,7 +60,10 @@ impl WhisperTranscriber for StreamWhisperTranscriber {
         // Load the model (which may involve async model discovery)
         let context = load_model(&config_clone, &model_manager).await?;
 
-        Ok(Self { context, config })
+        Ok(Self {
+            context: Arc::new(context),
+            config,
+        })
     }
 
     async fn transcribe(self, input: AudioStream) -> crate::Result<StreamingTranscriptionResult> {
@@ -55,15 +84,26 @@ impl WhisperTranscriber for StreamWhisperTranscriber {
 }
 
 impl StreamWhisperTranscriber {
+    /// Create a transcriber using an already loaded model
+    pub fn with_model(model: &SharedModel, config: TranscriptionConfig) -> Self {
+        Self {
+            context: model.context.clone(),
+            config,
+        }
+    }
+
     async fn process_audio_stream(
         &mut self,
         mut input: AudioStream,
         tx: mpsc::UnboundedSender<crate::Result<StreamingChunk>>,
     ) -> crate::Result<()> {
+        let config = Arc::new(self.config.clone());
+
         // Create a state for processing all chunks
-        let mut state = self.context.create_state().map_err(|e| {
-            crate::WhisperError::Transcription(format!("Failed to create state: {}", e))
-        })?;
+        let mut state = self
+            .context
+            .create_state()
+            .map_err(|e| crate::WhisperError::whisper("Failed to create state", e))?;
 
         // Statistics tracking
         let start_time = std::time::Instant::now();
@@ -78,90 +118,62 @@ impl StreamWhisperTranscriber {
                     // Update statistics tracking
                     total_audio_duration += audio_chunk.duration;
 
-                    // Create fresh params for each chunk
-                    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-
-                    // Configure parameters
-                    params.set_language(self.config.language.as_deref());
-                    params.set_translate(self.config.translate);
+                    // A chunk that fails is reported and skipped, the rest of
+                    // the audio is still transcribed
+                    let (returned, decoded) =
+                        decode_window(state, config.clone(), audio_chunk.samples).await?;
+                    state = returned;
 
-                    if let Some(threads) = self.config.num_threads {
-                        params.set_n_threads(threads as i32);
-                    }
+                    let (chunk_text, error) = match decoded {
+                        Ok((chunk_text, num_segments)) => {
+                            // Update statistics
+                            total_segments += num_segments;
+                            total_word_count += chunk_text.split_whitespace().count();
 
-                    params.set_temperature(self.config.temperature);
-                    params.set_print_timestamps(false);
-                    params.set_print_progress(false);
-                    params.set_print_special(false);
-                    params.set_print_realtime(false);
-
-                    // Process this chunk
-                    match state.full(params, &audio_chunk.samples) {
-                        Ok(_) => {
-                            // Extract results from state
-                            match state.full_n_segments() {
-                                Ok(num_segments) => {
-                                    let mut chunk_text = String::new();
-
-                                    for i in 0..num_segments {
-                                        match state.full_get_segment_text(i) {
-                                            Ok(text) => {
-                                                chunk_text.push_str(&text);
-                                            }
-                                            Err(e) => {
-                                                warn!(
-                                                    "Failed to get segment text for segment {}: {}",
-                                                    i, e
-                                                );
-                                            }
-                                        }
-                                    }
-
-                                    // Update statistics
-                                    total_segments += num_segments as usize;
-                                    total_word_count += chunk_text.split_whitespace().count();
-
-                                    // Calculate final statistics if this is the last chunk
-                                    let final_stats = if audio_chunk.is_final {
-                                        let processing_time = start_time.elapsed().as_secs_f64();
-                                        Some(TranscriptionStats::new(
-                                            processing_time,
-                                            total_audio_duration,
-                                            total_segments,
-                                            total_word_count,
-                                        ))
-                                    } else {
-                                        None
-                                    };
-
-                                    // Send the chunk result
-                                    let streaming_chunk = StreamingChunk {
-                                        text: chunk_text,
-                                        start: audio_chunk.start_time as f64,
-                                        end: (audio_chunk.start_time + audio_chunk.duration) as f64,
-                                        is_final: audio_chunk.is_final,
-                                        chunk_index: audio_chunk.index,
-                                        final_stats,
-                                    };
-
-                                    if tx.send(Ok(streaming_chunk)).is_err() {
-                                        // Receiver dropped, stop processing
-                                        break;
-                                    }
-                                }
-                                Err(e) => {
-                                    warn!("Failed to get segment count: {}", e);
-                                    // Continue with next chunk
-                                }
-                            }
+                            (chunk_text, None)
                         }
                         Err(e) => {
-                            warn!(
-                                "Transcription failed for chunk {}: {}",
-                                audio_chunk.index, e
-                            );
-                            // Continue with next chunk instead of failing completely
+                            let e = format!("Chunk {}: {}", audio_chunk.index, e.report());
+                            warn!("{}", e);
+                            (String::new(), Some(e))
                         }
+                    };
+
+                    // Calculate final statistics if this is the last chunk
+                    let final_stats = if audio_chunk.is_final {
+                        let processing_time = start_time.elapsed().as_secs_f64();
+                        Some(TranscriptionStats::new(
+                            processing_time,
+                            total_audio_duration,
+                            total_segments,
+                            total_word_count,
+                        ))
+                    } else {
+                        None
+                    };
+
+                    // Send the chunk result
+                    let mut streaming_chunk = StreamingChunk {
+                        text: chunk_text,
+                        start: audio_chunk.start_time as f64,
+                        end: (audio_chunk.start_time + audio_chunk.duration) as f64,
+                        is_final: audio_chunk.is_final,
+                        chunk_index: audio_chunk.index,
+                        final_stats,
+                        status: if error.is_some() {
+                            ChunkStatus::Failed
+                        } else {
+                            ChunkStatus::Ok
+                        },
+                        error,
+                    };
+                    self.config
+                        .text_processing
+                        .apply_chunk(&mut streaming_chunk);
+
+                    if tx.send(Ok(streaming_chunk)).is_err() {
+                        // Receiver dropped, stop processing
+                        break;
                     }
                 }
                 Err(e) => {
@@ -173,12 +185,275 @@ impl StreamWhisperTranscriber {
 
         Ok(())
     }
+
+    /// Transcribe audio pushed through `input` as it arrives
+    ///
+    /// Samples are collected into windows of [`LiveOptions::window`] seconds.
+    /// While a window fills up, partial results (`is_final: false`) are sent
+    /// for it every [`LiveOptions::partial_interval`] seconds of audio; a final
+    /// result is sent once it is full or `input` is closed. The last chunk
+    /// carries the statistics of the session. `input` is bounded so that a
+    /// producer faster than transcription waits instead of queueing audio
+    /// without limit.
+    pub fn transcribe_live(
+        self,
+        input: mpsc::Receiver<Vec<f32>>,
+        options: LiveOptions,
+    ) -> StreamingTranscriptionResult {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            if let Err(e) = self.process_live_audio(input, options, &tx).await {
+                let _ = tx.send(Err(e));
+            }
+        });
+
+        StreamingTranscriptionResult {
+            stream: Box::pin(UnboundedReceiverStream::new(rx)),
+        }
+    }
+
+    async fn process_live_audio(
+        self,
+        mut input: mpsc::Receiver<Vec<f32>>,
+        options: LiveOptions,
+        tx: &mpsc::UnboundedSender<crate::Result<StreamingChunk>>,
+    ) -> crate::Result<()> {
+        let state = self
+            .context
+            .create_state()
+            .map_err(|e| WhisperError::whisper("Failed to create state", e))?;
+
+        let window_samples = ((options.window * SAMPLE_RATE as f32) as usize).max(SAMPLE_RATE);
+        let partial_samples = (options.partial_interval * SAMPLE_RATE as f32) as usize;
+
+        let mut session = LiveSession {
+            state: Some(state),
+            config: Arc::new(self.config),
+            tx: tx.clone(),
+            start_time: std::time::Instant::now(),
+            window_start: 0,
+            chunk_index: 0,
+            total_segments: 0,
+            total_word_count: 0,
+        };
+        let mut window: Vec<f32> = Vec::new();
+        let mut since_partial = 0usize;
+
+        loop {
+            let mut open = match input.recv().await {
+                Some(samples) => {
+                    since_partial += samples.len();
+                    window.extend(samples);
+                    true
+                }
+                None => false,
+            };
+
+            // Catch up with audio that arrived while the previous result was decoding
+            while open {
+                match input.try_recv() {
+                    Ok(samples) => {
+                        since_partial += samples.len();
+                        window.extend(samples);
+                    }
+                    Err(mpsc::error::TryRecvError::Empty) => break,
+                    Err(mpsc::error::TryRecvError::Disconnected) => open = false,
+                }
+            }
+
+            while window.len() >= window_samples {
+                let rest = window.split_off(window_samples);
+                let samples = std::mem::replace(&mut window, rest);
+                since_partial = window.len();
+
+                let is_last = !open && window.is_empty();
+                if !session.emit(samples, true, is_last).await? || is_last {
+                    return Ok(());
+                }
+            }
+
+            if !open {
+                session
+                    .emit(std::mem::take(&mut window), true, true)
+                    .await?;
+                return Ok(());
+            }
+
+            if since_partial >= partial_samples {
+                since_partial = 0;
+                if !session.emit(window.clone(), false, false).await? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Progress of a live transcription
+struct LiveSession {
+    /// Whisper state, moved to the blocking pool while decoding
+    state: Option<WhisperState>,
+    config: Arc<TranscriptionConfig>,
+    tx: mpsc::UnboundedSender<crate::Result<StreamingChunk>>,
+    start_time: std::time::Instant,
+    /// Start of the current window, in samples
+    window_start: usize,
+    chunk_index: usize,
+    total_segments: usize,
+    total_word_count: usize,
+}
+
+impl LiveSession {
+    /// Transcribe the current window and send the result
+    ///
+    /// Final results close the window. Returns false once the receiver is gone.
+    async fn emit(
+        &mut self,
+        samples: Vec<f32>,
+        is_final: bool,
+        is_last: bool,
+    ) -> crate::Result<bool> {
+        let sample_count = samples.len();
+        let (text, segments, error) = if samples.is_empty() {
+            (String::new(), 0, None)
+        } else {
+            let state = self.state.take().ok_or_else(|| {
+                WhisperError::Transcription("Live transcription state was lost".to_string())
+            })?;
+            let (state, decoded) = decode_window(state, self.config.clone(), samples).await?;
+            self.state = Some(state);
+            match decoded {
+                Ok((text, segments)) => (text, segments, None),
+                Err(e) => {
+                    warn!(
+                        "Live transcription failed for chunk {}: {}",
+                        self.chunk_index, e
+                    );
+                    (String::new(), 0, Some(e.report()))
+                }
+            }
+        };
+
+        let start = self.window_start as f64 / SAMPLE_RATE as f64;
+        let end = (self.window_start + sample_count) as f64 / SAMPLE_RATE as f64;
+        let chunk_index = self.chunk_index;
+
+        if is_final {
+            self.total_segments += segments;
+            self.total_word_count += text.split_whitespace().count();
+            self.window_start += sample_count;
+            self.chunk_index += 1;
+        }
+
+        let final_stats = is_last.then(|| {
+            TranscriptionStats::new(
+                self.start_time.elapsed().as_secs_f64(),
+                end as f32,
+                self.total_segments,
+                self.total_word_count,
+            )
+        });
+
+        let mut chunk = StreamingChunk {
+            text,
+            start,
+            end,
+            is_final,
+            chunk_index,
+            final_stats,
+            status: if error.is_some() {
+                ChunkStatus::Failed
+            } else {
+                ChunkStatus::Ok
+            },
+            error,
+        };
+        self.config.text_processing.apply_chunk(&mut chunk);
+        Ok(self.tx.send(Ok(chunk)).is_ok())
+    }
+}
+
+/// Parameters for transcribing a chunk of audio
+fn full_params(config: &TranscriptionConfig) -> FullParams<'_, '_> {
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+
+    params.set_language(config.language.as_deref());
+    params.set_translate(config.translate);
+
+    if let Some(threads) = config.num_threads {
+        params.set_n_threads(threads as i32);
+    }
+
+    params.set_temperature(config.temperature);
+    if let Some(ref prompt) = config.prompt {
+        params.set_initial_prompt(&prompt.replace('\0', ""));
+    }
+    params.set_print_timestamps(false);
+    params.set_print_progress(false);
+    params.set_print_special(false);
+    params.set_print_realtime(false);
+
+    params
+}
+
+/// Transcribe a window of audio on the blocking pool
+///
+/// The state is handed back even when decoding fails, so the stream or live
+/// session can go on with the next window.
+async fn decode_window(
+    mut state: WhisperState,
+    config: Arc<TranscriptionConfig>,
+    mut samples: Vec<f32>,
+) -> crate::Result<(WhisperState, crate::Result<(String, usize)>)> {
+    let decoded = task::spawn_blocking(move || {
+        // whisper.cpp skips inputs shorter than a second, pad them with silence
+        if samples.len() < SAMPLE_RATE {
+            samples.resize(SAMPLE_RATE, 0.0);
+        }
+
+        let decoded = state
+            .full(full_params(&config), &samples)
+            .map_err(|e| WhisperError::whisper("Transcription failed", e))
+            .and_then(|_| {
+                state
+                    .full_n_segments()
+                    .map_err(|e| WhisperError::whisper("Failed to get segment count", e))
+            })
+            .map(|num_segments| {
+                let mut text = String::new();
+                for i in 0..num_segments {
+                    match state.full_get_segment_text(i) {
+                        Ok(segment) => text.push_str(&segment),
+                        Err(e) => warn!("Failed to get segment text for segment {}: {}", i, e),
+                    }
+                }
+                (text, num_segments as usize)
+            });
+
+        (state, decoded)
+    })
+    .await?;
+
+    Ok(decoded)
 }
 
 pub struct StreamingTranscriptionResult {
     stream: Pin<Box<dyn Stream<Item = Result<StreamingChunk, crate::WhisperError>> + Send>>,
 }
 
+impl StreamingTranscriptionResult {
+    /// Wrap a stream of chunks
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<StreamingChunk, crate::WhisperError>> + Send + 'static,
+    {
+        Self {
+            stream: Box::pin(stream),
+        }
+    }
+}
+
 impl Stream for StreamingTranscriptionResult {
     type Item = Result<StreamingChunk, crate::WhisperError>;
 
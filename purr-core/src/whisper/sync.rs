@@ -4,19 +4,39 @@ use crate::{
     audio::AudioData,
     config::TranscriptionConfig,
     error::{Result, WhisperError},
+    progress::{Progress, ProgressCallback, ProgressStage},
+    vad::split_at_silence,
     whisper::{
-        load_model, SyncTranscriptionResult, TranscriptionSegment, TranscriptionStats,
-        WhisperTranscriber,
+        forced::ForcedText, load_model, ModelInfo, SharedModel, SyncTranscriptionResult, TokenInfo,
+        TranscriptionSegment, TranscriptionStats, WhisperTranscriber, WordTimestamp,
     },
     ModelManager,
 };
-use tracing::warn;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use tokio::task;
+use tracing::{info, warn};
+use whisper_rs::{FullParams, SamplingStrategy, SegmentCallbackData, WhisperContext, WhisperState};
+
+/// Parallel regions are at least this long (seconds)
+const MIN_REGION_SECONDS: f32 = 30.0;
+
+/// How far from an even split a region boundary may move to find silence (seconds)
+const SPLIT_SEARCH_SECONDS: f32 = 10.0;
+
+/// Receives segments as soon as whisper.cpp decodes them
+///
+/// Segments are passed without word timestamps, which are only computed once
+/// the whole file is transcribed.
+pub type SegmentCallback = Arc<dyn Fn(TranscriptionSegment) + Send + Sync>;
 
 /// Whisper transcriber
 pub struct SyncWhisperTranscriber {
-    context: WhisperContext,
+    context: Arc<WhisperContext>,
     config: TranscriptionConfig,
+    progress: Option<ProgressCallback>,
+    on_segment: Option<SegmentCallback>,
+    forced_text: Option<String>,
 }
 
 impl WhisperTranscriber for SyncWhisperTranscriber {
@@ -31,19 +51,62 @@ impl WhisperTranscriber for SyncWhisperTranscriber {
         // Load the model (which may involve async model discovery)
         let context = load_model(&config_clone, &model_manager).await?;
 
-        Ok(Self { context, config })
+        Ok(Self {
+            context: Arc::new(context),
+            config,
+            progress: None,
+            on_segment: None,
+            forced_text: None,
+        })
     }
 
     /// Transcribe audio data
     async fn transcribe(mut self, audio_data: AudioData) -> Result<SyncTranscriptionResult> {
         let config = self.config.clone();
 
-        // Run transcription synchronously since we can't clone the context
-        self.transcribe_sync_internal(audio_data, config)
+        // Inference is CPU bound, keep it off the async workers
+        task::spawn_blocking(move || self.transcribe_sync_internal(audio_data, config)).await?
     }
 }
 
 impl SyncWhisperTranscriber {
+    /// Create a transcriber using an already loaded model
+    pub fn with_model(model: &SharedModel, config: TranscriptionConfig) -> Self {
+        Self {
+            context: model.context.clone(),
+            config,
+            progress: None,
+            on_segment: None,
+            forced_text: None,
+        }
+    }
+
+    /// Report the progress of the model while transcribing
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Receive each segment as soon as it is decoded
+    ///
+    /// Unlike streaming transcription, the model still sees the whole file,
+    /// so segments keep the full 30 second context. The hallucination filter
+    /// only checks the text of segments passed to the callback, see
+    /// [`HallucinationFilter::filter_callback`](crate::HallucinationFilter::filter_callback).
+    pub fn with_segment_callback(mut self, on_segment: SegmentCallback) -> Self {
+        self.on_segment = Some(on_segment);
+        self
+    }
+
+    /// Decode exactly this text, leaving the model to place its tokens in time
+    ///
+    /// Used for forced alignment. The text runs through the whole audio, so
+    /// it is decoded as a single region.
+    pub(crate) fn with_forced_text(mut self, text: &str) -> Self {
+        self.forced_text = Some(text.to_string());
+        self
+    }
+
     /// Synchronous transcription implementation
     fn transcribe_sync_internal(
         &mut self,
@@ -52,6 +115,168 @@ impl SyncWhisperTranscriber {
     ) -> Result<SyncTranscriptionResult> {
         let start_time = std::time::Instant::now();
 
+        let regions = split_at_silence(
+            &audio_data.samples,
+            audio_data.sample_rate,
+            self.region_count(&audio_data, &config),
+            SPLIT_SEARCH_SECONDS,
+        );
+
+        let on_segment = self
+            .on_segment
+            .clone()
+            .map(|on_segment| config.hallucinations.filter_callback(on_segment));
+        let segments = if regions.len() > 1 {
+            self.decode_parallel(&audio_data, &regions, &config, on_segment)?
+        } else {
+            self.decode(
+                &audio_data.samples,
+                0.0,
+                &config,
+                self.progress.clone(),
+                on_segment,
+            )?
+        };
+
+        let segments = config.hallucinations.apply(segments, Some(&audio_data));
+
+        let processing_time = start_time.elapsed().as_secs_f64();
+        let full_text: String = segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+
+        // FIXME: Implement language detection
+        let detected_language = config.language;
+
+        // Calculate statistics
+        let word_count = full_text.split_whitespace().count();
+        let stats = TranscriptionStats::new(
+            processing_time,
+            audio_data.duration,
+            segments.len(),
+            word_count,
+        );
+
+        let mut result = SyncTranscriptionResult {
+            text: full_text,
+            language: detected_language,
+            segments,
+            processing_time,
+            audio_duration: audio_data.duration,
+            stats,
+            model: config
+                .output_format
+                .full_details
+                .then(|| ModelInfo::new(&self.context, config.model_path.clone())),
+        };
+        config.text_processing.apply_result(&mut result);
+
+        Ok(result)
+    }
+
+    /// Number of regions to decode in parallel
+    ///
+    /// Regions shorter than whisper's 30 second window would lose context
+    /// without saving any time, so short files use fewer processors. Forced
+    /// text cannot be split between regions.
+    fn region_count(&self, audio_data: &AudioData, config: &TranscriptionConfig) -> usize {
+        if self.forced_text.is_some() {
+            return 1;
+        }
+        let max_regions = (audio_data.duration / MIN_REGION_SECONDS) as usize;
+        config.processors.min(max_regions).max(1)
+    }
+
+    /// Decode regions of the audio concurrently, each on its own state
+    ///
+    /// Segments are passed to the segment callback in order once every
+    /// region is done, as later regions usually finish first.
+    fn decode_parallel(
+        &self,
+        audio_data: &AudioData,
+        regions: &[Range<usize>],
+        config: &TranscriptionConfig,
+        on_segment: Option<SegmentCallback>,
+    ) -> Result<Vec<TranscriptionSegment>> {
+        info!("Transcribing {} regions in parallel", regions.len());
+
+        // Overall progress is the progress of each region weighted by its length
+        let total = audio_data.samples.len().max(1) as f32;
+        let weights: Arc<[f32]> = regions.iter().map(|r| r.len() as f32 / total).collect();
+        let fractions = Arc::new(Mutex::new(vec![0.0f32; regions.len()]));
+        let region_progress = |index: usize| -> Option<ProgressCallback> {
+            let progress = self.progress.clone()?;
+            let fractions = fractions.clone();
+            let weights = weights.clone();
+            Some(Arc::new(move |update: Progress| {
+                let overall = {
+                    let mut fractions = fractions.lock().unwrap_or_else(|e| e.into_inner());
+                    fractions[index] = update.fraction;
+                    fractions
+                        .iter()
+                        .zip(weights.iter())
+                        .map(|(f, w)| f * w)
+                        .sum()
+                };
+                progress(Progress::new(ProgressStage::Transcribing, overall));
+            }))
+        };
+
+        let sample_rate = audio_data.sample_rate as f64;
+        let results: Vec<Result<Vec<TranscriptionSegment>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = regions
+                .iter()
+                .enumerate()
+                .map(|(index, range)| {
+                    let progress = region_progress(index);
+                    let samples = &audio_data.samples[range.clone()];
+                    let offset = range.start as f64 / sample_rate;
+                    scope.spawn(move || self.decode(samples, offset, config, progress, None))
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(WhisperError::Transcription(
+                            "A transcription thread panicked".to_string(),
+                        ))
+                    })
+                })
+                .collect()
+        });
+
+        let mut segments = Vec::new();
+        for result in results {
+            segments.extend(result?);
+        }
+
+        if let Some(on_segment) = on_segment {
+            for segment in &segments {
+                on_segment(TranscriptionSegment {
+                    text: config.text_processing.process(&segment.text),
+                    ..segment.clone()
+                });
+            }
+        }
+
+        Ok(segments)
+    }
+
+    /// Transcribe samples on a new state
+    ///
+    /// Timestamps are shifted by `offset` seconds, the position of the
+    /// samples in the file.
+    fn decode(
+        &self,
+        samples: &[f32],
+        offset: f64,
+        config: &TranscriptionConfig,
+        progress: Option<ProgressCallback>,
+        on_segment: Option<SegmentCallback>,
+    ) -> Result<Vec<TranscriptionSegment>> {
         // Setup transcription parameters
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
 
@@ -65,31 +290,72 @@ impl SyncWhisperTranscriber {
 
         params.set_temperature(config.temperature);
 
+        if let Some(ref prompt) = config.prompt {
+            params.set_initial_prompt(&prompt.replace('\0', ""));
+        }
+
+        let word_timestamps = config.output_format.word_timestamps;
+        let full_details = config.output_format.full_details;
+        params.set_token_timestamps(word_timestamps || full_details);
+        params.set_tdrz_enable(config.speaker_turns);
+
         params.set_print_timestamps(false); // Disable whisper.cpp's internal timestamp printing
-        params.set_print_progress(false); // Disable progress output
+        params.set_print_progress(false); // Disable progress output, use the callback instead
+        if let Some(progress) = progress {
+            params.set_progress_callback_safe(move |percent: i32| {
+                progress(Progress::new(
+                    ProgressStage::Transcribing,
+                    percent as f32 / 100.0,
+                ))
+            });
+        }
+        if let Some(on_segment) = on_segment {
+            let text_processing = config.text_processing.clone();
+            params.set_segment_callback_safe_lossy(move |data: SegmentCallbackData| {
+                // Timestamps are in centiseconds
+                on_segment(TranscriptionSegment {
+                    text: text_processing.process(&data.text),
+                    start: offset + data.start_timestamp as f64 / 100.0,
+                    end: offset + data.end_timestamp as f64 / 100.0,
+                    confidence: None,
+                    words: None,
+                    no_speech_prob: None,
+                    hallucination: None,
+                    speaker_turn_next: false,
+                    tokens: None,
+                })
+            });
+        }
         params.set_print_special(false); // Disable special token printing
         params.set_print_realtime(false); // Disable real-time printing
 
+        let forced = self
+            .forced_text
+            .as_deref()
+            .map(|text| ForcedText::new(&self.context, text))
+            .transpose()?;
+        if let Some(ref forced) = forced {
+            // SAFETY: `forced` is dropped at the end of this function, after `state.full`
+            unsafe { forced.apply(&mut params) };
+        }
+
         // Create a state for processing
         let mut state = self
             .context
             .create_state()
-            .map_err(|e| WhisperError::Transcription(format!("Failed to create state: {}", e)))?;
+            .map_err(|e| WhisperError::whisper("Failed to create state", e))?;
 
         // Run transcription using state.full()
         state
-            .full(params, &audio_data.samples)
-            .map_err(|e| WhisperError::Transcription(format!("Transcription failed: {}", e)))?;
-
-        let processing_time = start_time.elapsed().as_secs_f64();
+            .full(params, samples)
+            .map_err(|e| WhisperError::whisper("Transcription failed", e))?;
 
         // Extract results from state
-        let num_segments = state.full_n_segments().map_err(|e| {
-            WhisperError::Transcription(format!("Failed to get segment count: {}", e))
-        })?;
+        let num_segments = state
+            .full_n_segments()
+            .map_err(|e| WhisperError::whisper("Failed to get segment count", e))?;
 
         let mut segments = Vec::new();
-        let mut full_text = String::new();
 
         for i in 0..num_segments {
             let text = match state.full_get_segment_text(i) {
@@ -103,48 +369,148 @@ impl SyncWhisperTranscriber {
                 }
             };
 
-            let start = state.full_get_segment_t0(i).map_err(|e| {
-                WhisperError::Transcription(format!("Failed to get segment start time: {}", e))
-            })? as f64
+            let start = state
+                .full_get_segment_t0(i)
+                .map_err(|e| WhisperError::whisper("Failed to get segment start time", e))?
+                as f64
                 / 100.0;
 
-            let end = state.full_get_segment_t1(i).map_err(|e| {
-                WhisperError::Transcription(format!("Failed to get segment end time: {}", e))
-            })? as f64
+            let end = state
+                .full_get_segment_t1(i)
+                .map_err(|e| WhisperError::whisper("Failed to get segment end time", e))?
+                as f64
                 / 100.0;
 
-            full_text.push_str(&text);
+            // Only TinyDiarize models mark speaker turns
+            let speaker_turn_next =
+                config.speaker_turns && state.full_get_segment_speaker_turn_next(i);
+
+            let words = if word_timestamps {
+                let mut words = self.segment_words(&state, i)?;
+                for word in &mut words {
+                    word.start += offset;
+                    word.end += offset;
+                }
+                Some(words)
+            } else {
+                None
+            };
+
+            let tokens = if full_details {
+                Some(self.segment_tokens(&state, i, offset)?)
+            } else {
+                None
+            };
 
             segments.push(TranscriptionSegment {
                 text,
-                start,
-                end,
+                start: offset + start,
+                end: offset + end,
                 confidence: None, // whisper-rs doesn't expose confidence scores yet
-                words: None,      // Word-level timestamps not implemented yet
+                words,
+                no_speech_prob: None,
+                hallucination: None,
+                speaker_turn_next,
+                tokens,
             });
         }
 
-        // FIXME: Implement language detection
-        let detected_language = config.language;
+        Ok(segments)
+    }
 
-        // Calculate statistics
-        let word_count = full_text.split_whitespace().count();
-        let stats = TranscriptionStats::new(
-            processing_time,
-            audio_data.duration,
-            segments.len(),
-            word_count,
-        );
+    /// Every token of a segment, including special and timestamp tokens
+    fn segment_tokens(
+        &self,
+        state: &WhisperState,
+        segment: i32,
+        offset: f64,
+    ) -> Result<Vec<TokenInfo>> {
+        let num_tokens = state
+            .full_n_tokens(segment)
+            .map_err(|e| WhisperError::whisper("Failed to get token count", e))?;
+
+        (0..num_tokens)
+            .map(|t| {
+                let data = state
+                    .full_get_token_data(segment, t)
+                    .map_err(|e| WhisperError::whisper("Failed to get token data", e))?;
+                let bytes = state
+                    .full_get_token_bytes(segment, t)
+                    .map_err(|e| WhisperError::whisper("Failed to get token text", e))?;
+
+                Ok(TokenInfo {
+                    id: data.id,
+                    text: String::from_utf8_lossy(&bytes).into_owned(),
+                    start: offset + data.t0 as f64 / 100.0,
+                    end: offset + data.t1 as f64 / 100.0,
+                    probability: data.p,
+                })
+            })
+            .collect()
+    }
 
-        Ok(SyncTranscriptionResult {
-            text: full_text,
-            language: detected_language,
-            segments,
-            processing_time,
-            audio_duration: audio_data.duration,
-            stats,
-        })
+    /// Merge the text tokens of a segment into words with timestamps
+    ///
+    /// Whisper tokens carry their own leading space, so a new word starts
+    /// whenever a token begins with whitespace.
+    fn segment_words(&self, state: &WhisperState, segment: i32) -> Result<Vec<WordTimestamp>> {
+        let token_eot = self.context.token_eot();
+        let num_tokens = state
+            .full_n_tokens(segment)
+            .map_err(|e| WhisperError::whisper("Failed to get token count", e))?;
+
+        let mut words = Vec::new();
+        let mut current: Option<(Vec<u8>, f64, f64, Vec<f32>)> = None;
+
+        for t in 0..num_tokens {
+            let data = state
+                .full_get_token_data(segment, t)
+                .map_err(|e| WhisperError::whisper("Failed to get token data", e))?;
+            if data.id >= token_eot {
+                // Special and timestamp tokens
+                continue;
+            }
+
+            let bytes = state
+                .full_get_token_bytes(segment, t)
+                .map_err(|e| WhisperError::whisper("Failed to get token text", e))?;
+            let (start, end) = (data.t0 as f64 / 100.0, data.t1 as f64 / 100.0);
+
+            match current.as_mut() {
+                Some((text, _, word_end, probs)) if !bytes.starts_with(b" ") => {
+                    text.extend_from_slice(&bytes);
+                    *word_end = end;
+                    probs.push(data.p);
+                }
+                _ => {
+                    if let Some(word) = current.take() {
+                        words.extend(finish_word(word));
+                    }
+                    current = Some((bytes, start, end, vec![data.p]));
+                }
+            }
+        }
+        if let Some(word) = current.take() {
+            words.extend(finish_word(word));
+        }
+
+        Ok(words)
+    }
+}
+
+/// Build a word from accumulated token bytes, skipping whitespace-only tokens
+fn finish_word((bytes, start, end, probs): (Vec<u8>, f64, f64, Vec<f32>)) -> Option<WordTimestamp> {
+    let word = String::from_utf8_lossy(&bytes).trim().to_string();
+    if word.is_empty() {
+        return None;
     }
+
+    Some(WordTimestamp {
+        word,
+        start,
+        end: end.max(start),
+        confidence: Some(probs.iter().sum::<f32>() / probs.len() as f32),
+    })
 }
 
 #[cfg(test)]
@@ -174,6 +540,7 @@ mod tests {
             processing_time: 1.5,
             audio_duration: 3.0,
             stats,
+            model: None,
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -0,0 +1,260 @@
+//! Model download tests against a local HTTP server
+
+use purr_core::model::{ModelChecksum, ModelSettings, RetryPolicy};
+use purr_core::{ModelManager, WhisperError, WhisperModel};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Minimal HTTP/1.1 file server with Range support
+///
+/// The first `interrupted` requests are cut off halfway through the body to
+/// simulate a dropped connection.
+struct TestServer {
+    url: String,
+    requests: Arc<AtomicUsize>,
+    ranges: Arc<Mutex<Vec<Option<u64>>>>,
+}
+
+impl TestServer {
+    async fn start(data: Vec<u8>, interrupted: usize) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ggml-test.bin", listener.local_addr().unwrap());
+        let data = Arc::new(data);
+        let requests = Arc::new(AtomicUsize::new(0));
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+
+        let (server_requests, server_ranges) = (requests.clone(), ranges.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let request_index = server_requests.fetch_add(1, Ordering::SeqCst);
+                let data = data.clone();
+                let ranges = server_ranges.clone();
+
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buffer = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        let read = socket.read(&mut buffer).await.unwrap();
+                        if read == 0 {
+                            return;
+                        }
+                        request.extend_from_slice(&buffer[..read]);
+                    }
+
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+                    let range_start = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .and_then(|range| range.trim_end_matches('-').parse::<u64>().ok());
+                    ranges.lock().unwrap().push(range_start);
+
+                    let start = range_start.unwrap_or(0) as usize;
+                    let body = &data[start.min(data.len())..];
+                    let header = match range_start {
+                        Some(start) => format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                            body.len(),
+                            start,
+                            data.len() - 1,
+                            data.len()
+                        ),
+                        None => format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        ),
+                    };
+
+                    socket.write_all(header.as_bytes()).await.unwrap();
+                    if request_index < interrupted {
+                        let _ = socket.write_all(&body[..body.len() / 2]).await;
+                        // Drop the connection before the body is complete
+                        return;
+                    }
+                    let _ = socket.write_all(body).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        Self {
+            url,
+            requests,
+            ranges,
+        }
+    }
+}
+
+fn test_data() -> Vec<u8> {
+    (0..256 * 1024).map(|i| (i % 251) as u8).collect()
+}
+
+fn manager(dir: &TempDir) -> ModelManager {
+    ModelManager::with_models_dir(dir.path()).with_retry_policy(RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+    })
+}
+
+fn sha256_of(data: &[u8]) -> ModelChecksum {
+    let digest = Sha256::digest(data);
+    ModelChecksum::Sha256(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[tokio::test]
+async fn test_download_resumes_after_interruption() {
+    let data = test_data();
+    let checksum = sha256_of(&data);
+    let server = TestServer::start(data.clone(), 1).await;
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ggml-test.bin");
+
+    let mut last_progress = (0, None);
+    manager(&dir)
+        .download_file(&server.url, &path, Some(&checksum), |done, total| {
+            last_progress = (done, total);
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert!(!path.with_extension("tmp").exists());
+    assert_eq!(last_progress, (data.len() as u64, Some(data.len() as u64)));
+
+    // Second request must continue where the first one stopped
+    let ranges = server.ranges.lock().unwrap().clone();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(ranges[0], None);
+    assert!(ranges[1].is_some_and(|start| start > 0));
+}
+
+#[tokio::test]
+async fn test_download_resumes_existing_partial_file() {
+    let data = test_data();
+    let server = TestServer::start(data.clone(), 0).await;
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ggml-test.bin");
+    std::fs::write(path.with_extension("tmp"), &data[..1000]).unwrap();
+
+    manager(&dir)
+        .download_file(&server.url, &path, None, |_, _| {})
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert_eq!(*server.ranges.lock().unwrap(), vec![Some(1000)]);
+}
+
+#[tokio::test]
+async fn test_download_rejects_checksum_mismatch() {
+    let data = test_data();
+    let server = TestServer::start(data, 0).await;
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ggml-test.bin");
+    let wrong = ModelChecksum::Sha1("0000000000000000000000000000000000000000".to_string());
+
+    let result = manager(&dir)
+        .download_file(&server.url, &path, Some(&wrong), |_, _| {})
+        .await;
+
+    assert!(result.is_err());
+    assert!(!path.exists());
+    assert!(!path.with_extension("tmp").exists());
+}
+
+#[tokio::test]
+async fn test_download_gives_up_after_max_attempts() {
+    let server = TestServer::start(test_data(), usize::MAX).await;
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ggml-test.bin");
+
+    let result = manager(&dir)
+        .download_file(&server.url, &path, None, |_, _| {})
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(server.requests.load(Ordering::SeqCst), 3);
+    // The partial file is kept so a later run can resume
+    assert!(path.with_extension("tmp").exists());
+}
+
+#[tokio::test]
+async fn test_download_from_local_mirror() {
+    let data = test_data();
+    let mirror = TempDir::new().unwrap();
+    let model = WhisperModel::TinyQ5_1;
+    std::fs::write(mirror.path().join(model.filename()), &data).unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let settings =
+        ModelSettings::default().with_mirror(format!("file://{}", mirror.path().display()));
+    let manager = manager(&dir).with_settings(settings);
+
+    let mut last_progress = (0, None);
+    let result = manager
+        .download_model_with_progress(model, |done, total| {
+            last_progress = (done, total);
+        })
+        .await;
+
+    // The test data is read from the mirror, then fails the published checksum
+    let Err(WhisperError::ChecksumMismatch { url, actual, .. }) = result else {
+        panic!("expected a checksum mismatch, got {:?}", result);
+    };
+    assert!(url.contains(&*mirror.path().to_string_lossy()));
+    let digest = Sha1::digest(&data);
+    assert_eq!(
+        actual,
+        digest
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+    assert_eq!(last_progress, (data.len() as u64, Some(data.len() as u64)));
+    assert!(!dir.path().join(model.filename()).exists());
+}
+
+#[tokio::test]
+async fn test_download_registry_model() {
+    let data = test_data();
+    let checksum = sha256_of(&data);
+    let registry = TempDir::new().unwrap();
+    std::fs::write(registry.path().join("ggml-tuned.bin"), &data).unwrap();
+    std::fs::write(
+        registry.path().join("registry.toml"),
+        format!(
+            "[[models]]\nname = \"tuned\"\nurl = \"ggml-tuned.bin\"\nsha256 = \"{}\"\n",
+            checksum.expected()
+        ),
+    )
+    .unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let settings = ModelSettings::default()
+        .with_registry(registry.path().join("registry.toml").display().to_string());
+    let manager = manager(&dir).with_settings(settings);
+
+    let model = manager.find_registry_model("tuned").await.unwrap().unwrap();
+    assert!(!manager.is_registry_model_downloaded(&model));
+
+    let path = manager
+        .download_registry_model(&model, |_, _| {})
+        .await
+        .unwrap();
+
+    assert_eq!(path, dir.path().join("ggml-tuned.bin"));
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+    assert!(manager.is_registry_model_downloaded(&model));
+    assert!(manager
+        .find_registry_model("missing")
+        .await
+        .unwrap()
+        .is_none());
+}
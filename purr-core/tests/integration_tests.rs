@@ -27,9 +27,9 @@ async fn test_missing_audio_file() {
 
     assert!(result.is_err());
     match result.unwrap_err() {
-        WhisperError::AudioProcessing(_) | WhisperError::Configuration(_) => {}
+        WhisperError::AudioNotFound { .. } | WhisperError::ModelNotFound { .. } => {}
         e => panic!(
-            "Expected AudioProcessing or Configuration error, got: {}",
+            "Expected AudioNotFound or ModelNotFound error, got: {}",
             e
         ),
     }
@@ -51,6 +51,10 @@ fn test_transcription_result_serialization() {
         end: 2.5,
         confidence: Some(0.95),
         words: None,
+        no_speech_prob: None,
+        hallucination: None,
+        speaker_turn_next: false,
+        tokens: None,
     };
 
     let stats = TranscriptionStats::new(1.5, 2.5, 1, 2);
@@ -61,6 +65,7 @@ fn test_transcription_result_serialization() {
         processing_time: 1.5,
         audio_duration: 2.5,
         stats,
+        model: None,
     };
 
     // Test JSON serialization
@@ -201,7 +206,12 @@ async fn test_transcription_error_handling(#[case] invalid_path: &str) {
 
     let error = result.unwrap_err();
     match error {
-        WhisperError::AudioProcessing(_) | WhisperError::Io(_) => {
+        WhisperError::AudioProcessing(_)
+        | WhisperError::AudioNotFound { .. }
+        | WhisperError::NoAudioStream { .. }
+        | WhisperError::UnsupportedCodec { .. }
+        | WhisperError::FFmpeg { .. }
+        | WhisperError::Io { .. } => {
             println!(
                 "✓ Correctly handled invalid file: {} -> {}",
                 invalid_path, error
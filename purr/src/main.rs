@@ -1,5 +1,6 @@
 //! Whisper UI CLI - Audio transcription command-line interface
 mod fmt;
+mod serve;
 
 use crate::fmt::MyFormatter;
 use clap::builder::{
@@ -10,12 +11,32 @@ use clap::{Parser, Subcommand};
 use const_str::format as cfmt;
 use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
 use owo_colors::OwoColorize as _;
+use purr_core::{
+    align::align_file,
+    bench::{self, BenchReport},
+    document::{paragraphs, to_html, to_markdown},
+    eval::{self, EvalReport, FileScore, Normalization, Score},
+    output::{
+        csv_row, srt_cue, to_csv, to_jsonl, to_srt, to_timestamped_text, to_tsv, to_vtt,
+        to_whisper_cpp_json, tsv_row, vtt_cue, CSV_HEADER, TSV_HEADER,
+    },
+    queue::{Job, JobQueue, JobStatus},
+    split::{split_file, write_manifest, SplitMode, SplitOptions},
+    whisper::{sync::SyncWhisperTranscriber, TranscriptionStats, WhisperTranscriber as _},
+    AudioProcessor, ClipFormat, SharedModel, TranscriptCache,
+};
 use purr_core::{
     dev::FeatureStatus, install_logging_hooks, list_devices, transcribe_file_stream,
-    transcribe_file_sync, ModelManager, TranscriptionConfig, WhisperModel,
+    transcribe_file_sync_with_progress, transcribe_file_sync_with_segments,
+    whisper::TranscriptionSegment, ConfigFile, HallucinationAction, ModelManager, Progress,
+    ProgressCallback, SegmentCallback, TranscriptionConfig, WhisperError, WhisperModel,
 };
 use purr_core::{
     math::{ByteSpeed, RoundToUnit as _},
+    model::{
+        select::select_model, GgmlHeader, GgufHeader, HardwareProfile, ModelSelection,
+        PruneOptions, Quality, QuantizationType, RegistryModel, SelectionCriteria, VerifyStatus,
+    },
     SystemInfo,
 };
 use shadow_rs::shadow;
@@ -23,6 +44,8 @@ use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::str::FromStr as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use tracing::{debug, error, info, warn, Level};
 use tracing_subscriber::EnvFilter;
 
@@ -34,15 +57,82 @@ shadow!(build);
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing subscriber
     if let Err(e) = main_impl().await {
-        error!("Application error: {}", e);
+        error!("Application error: {:#}", e);
+        let code = e
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<WhisperError>())
+            .map_or(EXIT_FAILURE, exit_code);
         drop(e);
-        process::exit(1);
+        process::exit(code);
     }
     Ok(())
 }
 
+/// Any other failure
+const EXIT_FAILURE: i32 = 1;
+/// Invalid configuration
+const EXIT_CONFIG: i32 = 3;
+/// The model is missing or unusable
+const EXIT_MODEL: i32 = 4;
+/// The audio is missing or cannot be decoded
+const EXIT_AUDIO: i32 = 5;
+/// A download failed
+const EXIT_DOWNLOAD: i32 = 6;
+/// Whisper failed to transcribe
+const EXIT_TRANSCRIPTION: i32 = 7;
+/// Cancelled by the user
+const EXIT_CANCELLED: i32 = 130;
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    Success
+  1    Other failure
+  2    Invalid command line
+  3    Invalid configuration
+  4    Model missing or unusable
+  5    Audio missing or undecodable
+  6    Download failed
+  7    Transcription failed
+  130  Cancelled";
+
+/// Exit code for a library error, so scripts can tell failures apart
+fn exit_code(error: &WhisperError) -> i32 {
+    match error {
+        WhisperError::Configuration(_) => EXIT_CONFIG,
+        WhisperError::ModelNotFound { .. } | WhisperError::InvalidModel(_) => EXIT_MODEL,
+        WhisperError::AudioNotFound { .. }
+        | WhisperError::NoAudioStream { .. }
+        | WhisperError::UnsupportedCodec { .. }
+        | WhisperError::AudioProcessing(_)
+        | WhisperError::FFmpeg { .. } => EXIT_AUDIO,
+        WhisperError::DownloadFailed { .. }
+        | WhisperError::Network { .. }
+        | WhisperError::ChecksumMismatch { .. } => EXIT_DOWNLOAD,
+        WhisperError::Whisper { .. }
+        | WhisperError::Transcription(_)
+        | WhisperError::GpuAcceleration(_) => EXIT_TRANSCRIPTION,
+        WhisperError::Cancelled => EXIT_CANCELLED,
+        _ => EXIT_FAILURE,
+    }
+}
+
+/// Exit with [`EXIT_CANCELLED`] on Ctrl-C
+///
+/// The signal is awaited on a runtime worker, so it is also handled while the
+/// main thread waits for an answer to a prompt.
+fn exit_on_ctrl_c() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let error = WhisperError::Cancelled;
+            eprintln!();
+            error!("Application error: {}", error);
+            process::exit(exit_code(&error));
+        }
+    });
+}
+
 async fn main_impl() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
     // Setup logging
     if cli.verbose {
@@ -77,9 +167,14 @@ async fn main_impl() -> anyhow::Result<()> {
     debug!("Command line arguments: {:?}", cli);
     install_logging_hooks();
 
+    // The server shuts down gracefully on Ctrl-C instead
+    if !matches!(cli.command, Some(Commands::Serve { .. })) {
+        exit_on_ctrl_c();
+    }
+
     // Handle subcommands
-    if let Some(command) = cli.command {
-        return handle_command(command, cli.verbose).await;
+    if let Some(command) = cli.command.take() {
+        return handle_command(command, &cli).await;
     }
 
     // Handle transcription (original behavior)
@@ -92,52 +187,100 @@ async fn main_impl() -> anyhow::Result<()> {
     // Validate audio file exists
     if !audio_file.exists() {
         error!("Audio file not found: {}", audio_file.display());
-        process::exit(1);
+        process::exit(EXIT_AUDIO);
     }
 
     let config = setup_config(&cli).await?;
+    let output_config = config.clone();
 
-    // Print startup info
+    // Print startup info, on stderr to keep stdout for the transcript
     if cli.verbose {
-        println!("{}", "Whisper UI - Audio Transcription".blue().bold());
+        eprintln!("{}", "Whisper UI - Audio Transcription".blue().bold());
         if config.use_gpu {
-            println!("GPU acceleration: {}", "enabled".green());
+            eprintln!("GPU acceleration: {}", "enabled".green());
         } else {
-            println!("GPU acceleration: {}", "disabled".red());
+            eprintln!("GPU acceleration: {}", "disabled".red());
         }
         if let Some(lang) = &config.language {
-            println!("Language: {}", lang);
+            eprintln!("Language: {}", lang);
         }
-        println!();
+        eprintln!();
     }
 
-    if cli.no_stream {
+    if cli.no_stream || cli.output.needs_whole_transcript() {
         info!("Transcribing audio...");
 
-        let result = match transcribe_file_sync(&audio_file, Some(config)).await {
+        let progress_bar = transcription_progress_bar();
+        let result = transcribe_file_sync_with_progress(
+            &audio_file,
+            Some(config),
+            transcription_progress(&progress_bar),
+        )
+        .await;
+        progress_bar.finish_and_clear();
+
+        let result = match result {
             Ok(result) => result,
             Err(e) => {
-                error!("Transcription failed: {}", e);
-                process::exit(1);
+                error!("Transcription failed: {}", e.report());
+                process::exit(exit_code(&e));
             }
         };
 
-        handle_output(result, &cli)?;
+        handle_output(result, &cli, &output_config)?;
+    } else if cli.incremental {
+        info!("Transcribing audio, printing segments as they are decoded...");
+
+        // Segments go to stdout as they come, a file gets the full transcript
+        let on_segment: SegmentCallback = if cli.output_file.is_none() {
+            print!("{}", cli.output.stream_header());
+            segment_printer(
+                cli.output.clone(),
+                output_config.output_format.include_timestamps,
+            )
+        } else {
+            Arc::new(|_| {})
+        };
+
+        let result =
+            match transcribe_file_sync_with_segments(&audio_file, Some(config), on_segment).await {
+                Ok(result) => result,
+                Err(e) => {
+                    error!("Transcription failed: {}", e.report());
+                    process::exit(exit_code(&e));
+                }
+            };
+
+        if cli.output_file.is_some() {
+            handle_output(result, &cli, &output_config)?;
+        } else {
+            print!("{}", cli.output.stream_footer());
+            if cli.verbose {
+                print_transcription_stats(&result, cli.output.is_machine_readable());
+            }
+        }
     } else {
         info!("Streaming transcription...");
 
+        if config.hallucinations.is_enabled() {
+            eprintln!(
+                "{} Hallucination filtering only applies with --no-stream or --incremental",
+                "Warning:".yellow().bold()
+            );
+        }
+
         // Handle streaming transcription
         let stream = match transcribe_file_stream(&audio_file, Some(config)).await {
             Ok(stream) => stream,
             Err(e) => {
                 // Check if this is a "no model found" error
-                error!("Streaming transcription failed: {}", e);
-                process::exit(1);
+                error!("Streaming transcription failed: {}", e.report());
+                process::exit(exit_code(&e));
             }
         };
 
         // Process streaming results
-        handle_streaming_output(stream, &cli).await?;
+        handle_streaming_output(stream, &cli, &output_config.output_format).await?;
     }
 
     Ok(())
@@ -149,6 +292,7 @@ const ABOUT: &str = "😸 Transcribe audio files using Whisper AI";
 #[command(about = ABOUT)]
 #[command(version = "0.1.0")]
 #[command(styles = CLAP_STYLING)]
+#[command(after_long_help = EXIT_CODES_HELP)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
@@ -158,26 +302,35 @@ struct Cli {
     audio_file: Option<PathBuf>,
 
     /// Path to the Whisper model file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     model: Option<String>,
 
     /// Language code (e.g., en, es, fr). Auto-detect if not specified
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     language: Option<String>,
 
     /// Translate to English (like whisper.cpp --translate)
-    #[arg(long)]
+    #[arg(long, global = true)]
     translate: bool,
 
     /// Disable GPU acceleration
-    #[arg(long)]
+    #[arg(long, global = true)]
     no_gpu: bool,
 
+    /// Speed/accuracy trade-off used to pick a model when --model is not given
+    #[arg(long, value_enum, default_value = "balanced", global = true)]
+    quality: QualityArg,
+
     /// Number of threads to use
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     threads: Option<usize>,
 
-    /// Output format: text, json, srt, txt
+    /// Split long files at silences and transcribe this many regions in parallel
+    /// (without streaming)
+    #[arg(long, global = true)]
+    processors: Option<usize>,
+
+    /// Output format: text, json, jsonl, json-full, srt, vtt, txt, tsv, csv, markdown, html
     #[arg(short, long, default_value = "text")]
     output: OutputFormat,
 
@@ -193,13 +346,53 @@ struct Cli {
     #[arg(long)]
     word_timestamps: bool,
 
+    /// Mark speaker turns, starting new paragraphs in documents (needs a TinyDiarize model)
+    #[arg(long)]
+    speaker_turns: bool,
+
     /// Stream transcription results in real-time
     #[arg(short = 'S', long)]
     no_stream: bool,
 
+    /// Transcribe with full context, printing segments as soon as they are decoded
+    #[arg(long, conflicts_with = "no_stream")]
+    incremental: bool,
+
+    /// Flag or drop repetition loops and text made up over silence (without streaming)
+    #[arg(long, value_enum)]
+    hallucinations: Option<HallucinationArg>,
+
+    /// Remove the leading space of each segment
+    #[arg(long)]
+    trim_text: bool,
+
+    /// Replace runs of whitespace with a single space
+    #[arg(long)]
+    collapse_whitespace: bool,
+
+    /// Write spoken numbers, amounts and percentages with digits (English)
+    #[arg(long)]
+    itn: bool,
+
+    /// Mask the words listed in this file, one per line
+    #[arg(long, value_name = "FILE")]
+    mask_words: Option<PathBuf>,
+
+    /// Reuse the result of an identical earlier transcription
+    #[arg(long, conflicts_with = "no_cache")]
+    cache: bool,
+
+    /// Do not use the transcript cache, even if enabled in the config files
+    #[arg(long)]
+    no_cache: bool,
+
     /// Temperature for sampling (0.0 = deterministic)
-    #[arg(long, default_value = "0.0")]
-    temperature: f32,
+    #[arg(long, global = true)]
+    temperature: Option<f32>,
+
+    /// Apply a named profile from the config files
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
 
     /// Verbose output
     #[arg(short, long, global = true)]
@@ -216,6 +409,128 @@ enum Commands {
     /// System commands
     #[clap(alias = "s")]
     Sys {},
+    /// Transcribe a file and export each segment as a separate audio clip
+    Split {
+        /// Audio file to split
+        #[arg(value_name = "AUDIO_FILE")]
+        audio_file: PathBuf,
+
+        /// How to cut the audio into clips
+        #[arg(long = "by", default_value = "segments")]
+        by: SplitBy,
+
+        /// Audio format of the clips
+        #[arg(long, default_value = "wav")]
+        format: ClipFormatArg,
+
+        /// Directory to write clips to (defaults to <AUDIO_FILE stem>_clips)
+        #[arg(short = 'd', long)]
+        output_dir: Option<PathBuf>,
+
+        /// Path of the JSONL manifest (defaults to manifest.jsonl in the output directory)
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+    },
+    /// Align an existing transcript to audio, producing word and segment timings
+    Align {
+        /// Audio file the transcript belongs to
+        #[arg(value_name = "AUDIO_FILE")]
+        audio_file: PathBuf,
+
+        /// Plain text transcript to align
+        #[arg(value_name = "TRANSCRIPT")]
+        transcript: PathBuf,
+    },
+    /// Serve an OpenAI-compatible transcription API over HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: std::net::IpAddr,
+
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Maximum number of transcriptions and live sessions running at the
+        /// same time (live sessions beyond it are rejected)
+        #[arg(long, default_value_t = 1)]
+        parallel: usize,
+    },
+    /// Queue audio files for batch transcription and process the queue
+    Queue {
+        /// Queue file (defaults to queue.jsonl in the purr data directory)
+        #[arg(long, value_name = "PATH")]
+        store: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: QueueCommands,
+    },
+    /// Measure load, encoder and decoder times and the real-time factor of models
+    Bench {
+        /// Audio file to transcribe (defaults to generated audio)
+        #[arg(value_name = "AUDIO_FILE")]
+        audio_file: Option<PathBuf>,
+
+        /// Models to compare, by name or path (defaults to --model or the selected model)
+        #[arg(long, value_delimiter = ',')]
+        models: Vec<String>,
+
+        /// Thread counts to compare (defaults to --threads)
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        thread_counts: Vec<usize>,
+
+        /// GPU settings to compare (defaults to the configured setting)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        gpu: Vec<GpuSetting>,
+
+        /// Length of the generated audio in seconds
+        #[arg(long, default_value_t = 60.0)]
+        duration: f32,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Transcribe a set of files and score them against reference transcripts
+    Eval {
+        /// Directory of audio files (.txt files in it are taken as references, not audio)
+        #[arg(long, value_name = "DIR")]
+        audio_dir: PathBuf,
+
+        /// Directory of reference transcripts, named after the audio files with a .txt extension
+        #[arg(long, value_name = "DIR")]
+        refs_dir: PathBuf,
+
+        /// Compare letter case
+        #[arg(long)]
+        keep_case: bool,
+
+        /// Compare punctuation
+        #[arg(long)]
+        keep_punctuation: bool,
+
+        /// Compare numbers as written instead of spelling them out
+        #[arg(long)]
+        keep_numbers: bool,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Transcript cache commands
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+    /// Configuration file commands
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -242,11 +557,60 @@ enum ModelCommands {
         #[arg(value_name = "MODEL")]
         model: String,
     },
-    /// Show model information
+    /// Show model information, read from the model file header when it is on disk
     Info {
-        /// Model to show info for (e.g., base, small, large-v3)
+        /// Model name or path to a model file (defaults to --model)
+        #[arg(value_name = "MODEL")]
+        model: Option<String>,
+    },
+    /// Import a custom or fine-tuned ggml model file
+    ///
+    /// GGUF files are recognised but rejected, since whisper.cpp only loads
+    /// ggml models.
+    Import {
+        /// Path to the ggml model file
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+
+        /// Name used to select the model with --model
+        #[arg(short, long)]
+        name: String,
+
+        /// Replace an existing custom model with the same name
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Quantize a local f16 or f32 model and register it as a custom model
+    Quantize {
+        /// Model name or path to a model file
         #[arg(value_name = "MODEL")]
         model: String,
+
+        /// Quantization format
+        #[arg(long = "type", value_enum)]
+        quantization: QuantizationArg,
+
+        /// Name of the quantized model (defaults to <model>-<type>)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// Show the disk usage of every file in the models directory
+    Du,
+    /// Delete leftovers of interrupted downloads and, optionally, unused models
+    Prune {
+        /// Also delete models not used in this many days
+        #[arg(long, value_name = "DAYS")]
+        unused_days: Option<u64>,
+
+        /// Only show what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rehash downloaded models and check them against their known checksums
+    Verify {
+        /// Model to verify (all downloaded models if omitted)
+        #[arg(value_name = "MODEL")]
+        model: Option<String>,
     },
 }
 
@@ -256,6 +620,53 @@ enum SysCommands {
     Info,
 }
 
+#[derive(Subcommand, Debug)]
+enum QueueCommands {
+    /// Add audio files to the queue
+    Add {
+        /// Audio files to transcribe
+        #[arg(value_name = "FILES", required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Transcribe the pending jobs, resuming jobs of an interrupted run
+    Run {
+        /// Number of files transcribed at the same time
+        #[arg(short, long, default_value = "1")]
+        workers: usize,
+
+        /// Number of times a failed job is retried
+        #[arg(long, default_value = "2")]
+        retries: u32,
+
+        /// Transcript format
+        #[arg(long, value_enum, default_value = "json")]
+        format: OutputFormat,
+
+        /// Directory to write transcripts to, named `<name>-<job id>` (defaults
+        /// to next to each audio file)
+        #[arg(long)]
+        output_dir: Option<PathBuf>,
+
+        /// Also retry jobs that failed in a previous run
+        #[arg(long)]
+        retry_failed: bool,
+    },
+    /// Show the jobs in the queue
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommands {
+    /// Remove all cached transcripts
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Print the configuration resolved from the config files, profile and flags
+    Show,
+}
+
 /// Output format options
 #[derive(Clone, Debug, clap::ValueEnum)]
 enum OutputFormat {
@@ -265,14 +676,200 @@ enum OutputFormat {
     Json,
     /// SRT subtitle format
     Srt,
+    /// WebVTT subtitle format
+    Vtt,
     /// Plain text output (clean, no timestamps)
     Txt,
+    /// Tab-separated start and end milliseconds and text, as openai-whisper writes
+    Tsv,
+    /// Comma-separated start and end milliseconds and quoted text
+    Csv,
+    /// One JSON object per line, for each segment or streamed chunk
+    Jsonl,
+    /// whisper.cpp's full JSON schema with tokens (without streaming)
+    JsonFull,
+    /// Markdown document in sentences and paragraphs (without streaming)
+    #[value(alias = "md")]
+    Markdown,
+    /// HTML document in sentences and paragraphs (without streaming)
+    Html,
+}
+
+impl OutputFormat {
+    /// File extension of transcripts in this format
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text | OutputFormat::Txt => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Srt => "srt",
+            OutputFormat::Vtt => "vtt",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Html => "html",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::JsonFull => "json",
+        }
+    }
+
+    /// Formats written from the whole transcript, never streamed
+    ///
+    /// Documents need all sentences to find paragraphs, and whisper.cpp's
+    /// JSON needs the tokens of each segment.
+    fn needs_whole_transcript(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Markdown | OutputFormat::Html | OutputFormat::JsonFull
+        )
+    }
+
+    /// Written before streamed chunks or segments
+    fn stream_header(&self) -> &'static str {
+        match self {
+            OutputFormat::Vtt => "WEBVTT\n\n",
+            OutputFormat::Tsv => TSV_HEADER,
+            OutputFormat::Csv => CSV_HEADER,
+            // Streamed objects form one array
+            OutputFormat::Json => "[\n",
+            _ => "",
+        }
+    }
+
+    /// Written after streamed chunks or segments
+    fn stream_footer(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "\n]\n",
+            _ => "",
+        }
+    }
+
+    /// Data formats end each streamed item themselves and are not spaced out
+    fn is_data(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Tsv | OutputFormat::Csv
+        )
+    }
+
+    /// Formats read by programs, where statistics go to stderr to keep stdout parseable
+    fn is_machine_readable(&self) -> bool {
+        self.is_data() || matches!(self, OutputFormat::JsonFull)
+    }
+}
+
+/// Speed/accuracy trade-off for automatic model selection
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum QualityArg {
+    /// Favor speed
+    Fast,
+    /// Largest model the hardware handles comfortably
+    Balanced,
+    /// Most accurate model the hardware can run
+    Best,
+}
+
+impl From<QualityArg> for Quality {
+    fn from(value: QualityArg) -> Self {
+        match value {
+            QualityArg::Fast => Quality::Fast,
+            QualityArg::Balanced => Quality::Balanced,
+            QualityArg::Best => Quality::Best,
+        }
+    }
+}
+
+/// What to do with hallucinated segments
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum HallucinationArg {
+    /// Keep segments as transcribed
+    Off,
+    /// Keep segments, marking them in JSON output
+    Flag,
+    /// Remove segments and repetition loops
+    Drop,
+}
+
+impl From<HallucinationArg> for HallucinationAction {
+    fn from(value: HallucinationArg) -> Self {
+        match value {
+            HallucinationArg::Off => HallucinationAction::Off,
+            HallucinationArg::Flag => HallucinationAction::Flag,
+            HallucinationArg::Drop => HallucinationAction::Drop,
+        }
+    }
+}
+
+/// GPU settings compared by `bench`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum GpuSetting {
+    /// GPU acceleration enabled
+    On,
+    /// CPU only
+    Off,
+}
+
+/// Quantization formats for `models quantize`
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum QuantizationArg {
+    #[value(name = "q5_0")]
+    Q5_0,
+    #[value(name = "q5_1")]
+    Q5_1,
+    #[value(name = "q8_0")]
+    Q8_0,
+}
+
+impl From<QuantizationArg> for QuantizationType {
+    fn from(value: QuantizationArg) -> Self {
+        match value {
+            QuantizationArg::Q5_0 => QuantizationType::Q5_0,
+            QuantizationArg::Q5_1 => QuantizationType::Q5_1,
+            QuantizationArg::Q8_0 => QuantizationType::Q8_0,
+        }
+    }
+}
+
+/// How `split` cuts audio into clips
+#[derive(Clone, Debug, clap::ValueEnum)]
+enum SplitBy {
+    /// One clip per transcription segment
+    Segments,
+    /// One clip per region of detected speech
+    Vad,
+}
+
+impl From<SplitBy> for SplitMode {
+    fn from(value: SplitBy) -> Self {
+        match value {
+            SplitBy::Segments => SplitMode::Segments,
+            SplitBy::Vad => SplitMode::Vad,
+        }
+    }
+}
+
+/// Audio format of exported clips
+#[derive(Clone, Debug, clap::ValueEnum)]
+enum ClipFormatArg {
+    /// 16-bit PCM WAV
+    Wav,
+    /// Lossless FLAC
+    Flac,
+}
+
+impl From<ClipFormatArg> for ClipFormat {
+    fn from(value: ClipFormatArg) -> Self {
+        match value {
+            ClipFormatArg::Wav => ClipFormat::Wav,
+            ClipFormatArg::Flac => ClipFormat::Flac,
+        }
+    }
 }
 
 /// Handle streaming transcription output
 async fn handle_streaming_output(
     mut stream: purr_core::StreamingTranscriptionResult,
     cli: &Cli,
+    options: &purr_core::config::OutputFormat,
 ) -> anyhow::Result<()> {
     use std::fs;
 
@@ -282,62 +879,85 @@ async fn handle_streaming_output(
 
     use futures::StreamExt;
 
+    // WebVTT, TSV and CSV files start with a header, JSON with an array
+    if cli.output_file.is_some() {
+        output_buffer.push_str(cli.output.stream_header());
+    } else {
+        write!(stdout, "{}", cli.output.stream_header())?;
+    }
+
+    let mut final_stats = None;
     while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result?;
+        let chunk = match chunk_result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // Close what was printed so far, a JSON array stays valid
+                if cli.output_file.is_none() {
+                    write!(stdout, "{}", cli.output.stream_footer())?;
+                    stdout.flush()?;
+                }
+                return Err(e.into());
+            }
+        };
         all_chunks.push(chunk.clone());
 
+        // Report gaps on stderr so they do not end up in the transcript
+        if chunk.is_failed() {
+            eprintln!(
+                "{} No transcript for {:.2}s -> {:.2}s: {}",
+                "Warning:".yellow().bold(),
+                chunk.start,
+                chunk.end,
+                chunk.error.as_deref().unwrap_or("transcription failed")
+            );
+        }
+
         // Format the chunk for real-time output
         let chunk_text = match cli.output {
             OutputFormat::Text => {
-                if cli.timestamps {
+                if options.include_timestamps {
                     format!("[{:.2}s -> {:.2}s] {}", chunk.start, chunk.end, chunk.text)
                 } else {
                     chunk.text.clone()
                 }
             }
-            OutputFormat::Json => serde_json::to_string(&chunk)?,
+            OutputFormat::Json => {
+                let separator = if all_chunks.len() > 1 { ",\n" } else { "" };
+                format!("{}{}", separator, serde_json::to_string(&chunk)?)
+            }
+            OutputFormat::Jsonl => format!("{}\n", serde_json::to_string(&chunk)?),
+            OutputFormat::Tsv => tsv_row(chunk.start, chunk.end, &chunk.text),
+            OutputFormat::Csv => csv_row(chunk.start, chunk.end, &chunk.text),
             OutputFormat::Srt => {
-                format!(
-                    "{}\n{} --> {}\n{}\n",
-                    chunk.chunk_index + 1,
-                    format_srt_time(chunk.start),
-                    format_srt_time(chunk.end),
-                    chunk.text
-                )
+                srt_cue(chunk.chunk_index + 1, chunk.start, chunk.end, &chunk.text)
             }
-            OutputFormat::Txt => chunk.text.clone(),
+            OutputFormat::Vtt => vtt_cue(chunk.start, chunk.end, &chunk.text),
+            OutputFormat::Txt
+            | OutputFormat::Markdown
+            | OutputFormat::Html
+            | OutputFormat::JsonFull => chunk.text.clone(),
         };
 
-        // Check for final statistics
-        if let Some(ref stats) = chunk.final_stats {
-            // Display statistics after processing is complete
-            if cli.verbose {
-                println!();
-                println!("{}", "Streaming Transcription Statistics:".green().bold());
-                println!("Audio duration: {:.2}s", stats.audio_duration);
-                println!("Processing time: {:.2}s", stats.processing_time);
-                println!("Real-time factor: {:.2}x", stats.real_time_factor);
-                println!("Segments: {}", stats.segment_count);
-                println!("Average segment length: {:.2}s", stats.avg_segment_length);
-                println!("Words: {}", stats.word_count);
-                println!("Words per minute: {:.1}", stats.words_per_minute);
-            }
+        // Statistics are printed once the output is complete
+        if chunk.final_stats.is_some() {
+            final_stats = chunk.final_stats.clone();
         }
 
         // Print to stdout or accumulate for file output
         if cli.output_file.is_some() {
             output_buffer.push_str(&chunk_text);
-            if !matches!(cli.output, OutputFormat::Txt) {
+            if !cli.output.is_data() && !matches!(cli.output, OutputFormat::Txt) {
                 output_buffer.push('\n');
             }
         } else {
             // IMMEDIATE real-time output to stdout
-            if matches!(cli.output, OutputFormat::Json) {
+            if cli.output.is_data() {
                 write!(stdout, "{}", chunk_text)?;
+                stdout.flush()?;
             } else {
                 write!(stdout, "{}", chunk_text)?;
                 if !chunk.text.is_empty() && !chunk.text.ends_with('\n') {
-                    if matches!(cli.output, OutputFormat::Srt) {
+                    if matches!(cli.output, OutputFormat::Srt | OutputFormat::Vtt) {
                         writeln!(stdout)?;
                     } else {
                         write!(stdout, " ")?;
@@ -351,6 +971,7 @@ async fn handle_streaming_output(
 
     // Write to file if specified
     if let Some(output_file) = &cli.output_file {
+        output_buffer.push_str(cli.output.stream_footer());
         fs::write(output_file, &output_buffer)?;
         if cli.verbose {
             info!(
@@ -359,21 +980,41 @@ async fn handle_streaming_output(
                 output_file.display()
             );
         }
+    } else if cli.output.is_data() {
+        write!(stdout, "{}", cli.output.stream_footer())?;
     } else {
         println!(); // Final newline for stdout
     }
 
     if cli.verbose {
+        if let Some(ref stats) = final_stats {
+            print_stats(
+                "Streaming Transcription Statistics:",
+                stats,
+                None,
+                cli.output.is_machine_readable(),
+            );
+        }
         debug!("Processed {} chunks", all_chunks.len());
     }
 
+    let failed = all_chunks.iter().filter(|chunk| chunk.is_failed()).count();
+    if failed > 0 {
+        eprintln!(
+            "{} {} of {} chunks could not be transcribed, the transcript has gaps",
+            "Warning:".yellow().bold(),
+            failed,
+            all_chunks.len()
+        );
+    }
+
     Ok(())
 }
 
 /// Prompt user to download base model when none is found
-async fn prompt_for_model_download(
-    model: Option<WhisperModel>,
-) -> anyhow::Result<Option<WhisperModel>> {
+///
+/// Declining returns [`WhisperError::Cancelled`].
+async fn prompt_for_model_download(model: Option<WhisperModel>) -> anyhow::Result<WhisperModel> {
     if let Some(model) = model {
         println!();
         println!(
@@ -403,7 +1044,10 @@ async fn prompt_for_model_download(
     );
     println!();
 
-    print!("Would you like to download the base model now? [Y/n]: ");
+    print!(
+        "Would you like to download the {} model now? [Y/n]: ",
+        model.as_str()
+    );
     io::stdout().flush()?;
 
     let mut input = String::new();
@@ -415,7 +1059,11 @@ async fn prompt_for_model_download(
 
     if should_download {
         println!();
-        println!("{} Downloading base model...", "Info:".blue().bold());
+        println!(
+            "{} Downloading {} model...",
+            "Info:".blue().bold(),
+            model.as_str()
+        );
 
         let model_manager = ModelManager::new()?;
         match model_manager.download_model(model).await {
@@ -426,10 +1074,10 @@ async fn prompt_for_model_download(
                     model_path.display()
                 );
                 println!();
-                Ok(Some(model))
+                Ok(model)
             }
             Err(e) => {
-                error!("Failed to download model: {}", e);
+                error!("Failed to download model: {}", e.report());
                 Err(e.into())
             }
         }
@@ -441,95 +1089,1034 @@ async fn prompt_for_model_download(
             env!("CARGO_PKG_NAME").cyan(),
             " models download <model>".cyan()
         );
-        Ok(None)
+        Err(WhisperError::Cancelled.into())
     }
 }
 
 /// Handle subcommands
-async fn handle_command(command: Commands, verbose: bool) -> anyhow::Result<()> {
+async fn handle_command(command: Commands, cli: &Cli) -> anyhow::Result<()> {
     match command {
-        Commands::Models { command } => handle_model_command(command, verbose).await,
-        Commands::Sys {} => handle_sys_command(verbose).await,
+        Commands::Models { command } => handle_model_command(command, cli).await,
+        Commands::Sys {} => handle_sys_command(cli.verbose).await,
+        Commands::Split {
+            audio_file,
+            by,
+            format,
+            output_dir,
+            manifest,
+        } => handle_split_command(audio_file, by, format, output_dir, manifest, cli).await,
+        Commands::Align {
+            audio_file,
+            transcript,
+        } => handle_align_command(audio_file, transcript, cli).await,
+        Commands::Serve {
+            host,
+            port,
+            parallel,
+        } => {
+            let config = setup_config(cli).await?;
+            let options = serve::ServeOptions {
+                addr: std::net::SocketAddr::new(host, port),
+                parallel,
+            };
+            serve::run(config, options).await
+        }
+        Commands::Queue { store, command } => handle_queue_command(store, command, cli).await,
+        Commands::Bench {
+            audio_file,
+            models,
+            thread_counts,
+            gpu,
+            duration,
+            json,
+        } => {
+            handle_bench_command(audio_file, models, thread_counts, gpu, duration, json, cli).await
+        }
+        Commands::Eval {
+            audio_dir,
+            refs_dir,
+            keep_case,
+            keep_punctuation,
+            keep_numbers,
+            json,
+        } => {
+            let normalization = Normalization {
+                lowercase: !keep_case,
+                remove_punctuation: !keep_punctuation,
+                spell_numbers: !keep_numbers,
+            };
+            handle_eval_command(audio_dir, refs_dir, normalization, json, cli).await
+        }
+        Commands::Cache { command } => handle_cache_command(command),
+        Commands::Config { command } => handle_config_command(command, cli),
     }
 }
 
-/// Handle model management subcommands
-async fn handle_model_command(command: ModelCommands, verbose: bool) -> anyhow::Result<()> {
-    let model_manager = ModelManager::new()?;
+/// Values in the order they first appear, without duplicates
+fn unique<T: PartialEq>(values: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut unique = Vec::new();
+    for value in values {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    unique
+}
 
+/// Handle the bench command
+async fn handle_bench_command(
+    audio_file: Option<PathBuf>,
+    models: Vec<String>,
+    thread_counts: Vec<usize>,
+    gpu: Vec<GpuSetting>,
+    duration: f32,
+    json: bool,
+    cli: &Cli,
+) -> anyhow::Result<()> {
+    let (base, model_paths) = if models.is_empty() {
+        let config = setup_config(cli).await?;
+        let model_path = config
+            .model_path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No model to benchmark"))?;
+        (config, vec![model_path])
+    } else {
+        let config = resolve_config(&ConfigFile::load()?, cli)?;
+        let model_manager = ModelManager::new()?;
+        let mut model_paths = Vec::new();
+        for model in &models {
+            model_paths.push(find_downloaded_model(&model_manager, model).await?);
+        }
+        (config, model_paths)
+    };
+
+    let thread_counts = if thread_counts.is_empty() {
+        vec![base.num_threads.unwrap_or_else(num_cpus::get)]
+    } else {
+        unique(thread_counts)
+    };
+    let gpu_settings = if gpu.is_empty() {
+        vec![base.use_gpu]
+    } else {
+        unique(gpu.iter().map(|setting| *setting == GpuSetting::On))
+    };
+
+    let (audio, synthetic) = match audio_file {
+        Some(path) => (AudioProcessor::new()?.load_audio(&path).await?, false),
+        None => (bench::synthetic_audio(duration), true),
+    };
+
+    let mut results = Vec::new();
+    for model_path in &model_paths {
+        for &threads in &thread_counts {
+            for &use_gpu in &gpu_settings {
+                let config = base
+                    .clone()
+                    .with_model_path(model_path)
+                    .with_threads(threads)
+                    .with_gpu(use_gpu);
+
+                let spinner = ProgressBar::new_spinner();
+                spinner.set_message(format!(
+                    "Benchmarking {} ({} threads, GPU {})...",
+                    model_label(model_path),
+                    threads,
+                    if use_gpu { "on" } else { "off" }
+                ));
+                spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+                let result = bench::run(&config, &audio).await;
+                spinner.finish_and_clear();
+
+                results.push(result?);
+            }
+        }
+    }
+
+    let report = BenchReport {
+        system: SystemInfo::get(),
+        audio_duration: audio.duration,
+        synthetic,
+        results,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_bench_report(&report);
+    }
+
+    Ok(())
+}
+
+/// Find the file of a downloaded model given by name or path
+async fn find_downloaded_model(
+    model_manager: &ModelManager,
+    model: &str,
+) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(model);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let mut searched = vec![path];
+    if let Ok(whisper_model) = WhisperModel::from_str(model) {
+        if model_manager.is_model_downloaded(whisper_model).await {
+            return Ok(model_manager.get_model_path(whisper_model));
+        }
+        searched.push(model_manager.get_model_path(whisper_model));
+    } else if model_manager.custom_model_path(model).exists() {
+        return Ok(model_manager.custom_model_path(model));
+    } else if let Some(registry_model) = model_manager.find_registry_model(model).await? {
+        if model_manager.is_registry_model_downloaded(&registry_model) {
+            return Ok(model_manager.registry_model_path(&registry_model));
+        }
+        searched.push(model_manager.registry_model_path(&registry_model));
+    } else {
+        searched.push(model_manager.custom_model_path(model));
+    }
+
+    print_download_hint(model);
+    Err(WhisperError::ModelNotFound { searched }.into())
+}
+
+/// Tell the user how to download a missing model
+fn print_download_hint(model: &str) {
+    eprintln!(
+        "{} Run '{} models download {}' to download it.",
+        "Notice:".yellow().bold(),
+        env!("CARGO_PKG_NAME"),
+        model
+    );
+}
+
+/// Short name of a model file
+fn model_label(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    stem.strip_prefix("ggml-")
+        .map(str::to_string)
+        .unwrap_or(stem)
+}
+
+/// Print benchmark results as a table
+fn print_bench_report(report: &BenchReport) {
+    let gpus: Vec<&str> = report
+        .system
+        .devices
+        .iter()
+        .map(|device| device.description.as_str())
+        .collect();
+    println!(
+        "{} {:.1}s of {} audio, GPU devices: {}",
+        "Info:".blue().bold(),
+        report.audio_duration,
+        if report.synthetic {
+            "generated"
+        } else {
+            "recorded"
+        },
+        if gpus.is_empty() {
+            "none".to_string()
+        } else {
+            gpus.join(", ")
+        }
+    );
+    println!();
+    println!(
+        "{}",
+        format!(
+            "{:<20} {:>7} {:>4} {:>9} {:>8} {:>10} {:>9} {:>9} {:>7}",
+            "Model", "Threads", "GPU", "Load", "Mel", "Encode", "Decode", "Batch", "RTF"
+        )
+        .bold()
+    );
+    for result in &report.results {
+        println!(
+            "{:<20} {:>7} {:>4} {:>7.0}ms {:>6.1}ms {:>8.1}ms {:>5.2}ms/t {:>5.2}ms/t {:>6.2}x",
+            model_label(&result.model),
+            result.threads,
+            if result.use_gpu { "on" } else { "off" },
+            result.load_ms,
+            result.mel_ms,
+            result.encode_ms,
+            result.decode_ms,
+            result.batch_decode_ms,
+            result.stats.real_time_factor,
+        );
+    }
+    println!();
+    println!("Mel and encode times are per 30 second window, decode times are per token.");
+}
+
+/// Extension of the reference transcripts of `eval`
+const REFERENCE_EXTENSION: &str = "txt";
+
+/// Pair each audio file with the reference of the same name, sorted by file name
+///
+/// References may sit next to the audio files, so files with the reference
+/// extension are not taken as audio.
+fn reference_pairs(audio_dir: &Path, refs_dir: &Path) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(audio_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .is_none_or(|extension| extension != REFERENCE_EXTENSION)
+        })
+        .collect();
+    entries.sort();
+
+    let mut pairs = Vec::new();
+    for audio in entries {
+        let Some(stem) = audio.file_stem() else {
+            continue;
+        };
+        if stem.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        // Only the last extension goes, "talk.part1.wav" pairs with "talk.part1.txt"
+        let reference = refs_dir.join(format!(
+            "{}.{}",
+            stem.to_string_lossy(),
+            REFERENCE_EXTENSION
+        ));
+        if reference.exists() {
+            pairs.push((audio, reference));
+        } else {
+            warn!(
+                "No reference for {}, expected {}",
+                audio.display(),
+                reference.display()
+            );
+        }
+    }
+    Ok(pairs)
+}
+
+/// Handle the eval command
+async fn handle_eval_command(
+    audio_dir: PathBuf,
+    refs_dir: PathBuf,
+    normalization: Normalization,
+    json: bool,
+    cli: &Cli,
+) -> anyhow::Result<()> {
+    let pairs = reference_pairs(&audio_dir, &refs_dir)?;
+    if pairs.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No audio file in {} has a reference transcript in {}",
+            audio_dir.display(),
+            refs_dir.display()
+        ));
+    }
+
+    let config = setup_config(cli).await?;
+    let model = SharedModel::load(&config).await?;
+
+    if !json {
+        println!(
+            "{}",
+            format!(
+                "{:<32} {:>6} {:>7} {:>7} {:>5} {:>5} {:>5}",
+                "File", "Words", "WER", "CER", "Sub", "Del", "Ins"
+            )
+            .bold()
+        );
+    }
+
+    let mut files = Vec::new();
+    let mut failed = 0;
+    for (audio, reference) in pairs {
+        let result = async {
+            let expected = std::fs::read_to_string(&reference)?;
+            let samples = AudioProcessor::new()?.load_audio(&audio).await?;
+            let result = SyncWhisperTranscriber::with_model(&model, config.clone())
+                .transcribe(samples)
+                .await?;
+            anyhow::Ok(eval::score(&expected, &result.text, &normalization))
+        }
+        .await;
+
+        match result {
+            Ok(score) => {
+                if !json {
+                    print_score_row(&display_name(&audio), &score);
+                }
+                files.push(FileScore {
+                    audio,
+                    reference,
+                    score,
+                });
+            }
+            Err(e) => {
+                failed += 1;
+                error!("Failed to evaluate {}: {:#}", audio.display(), e);
+            }
+        }
+    }
+
+    let report = EvalReport::new(normalization, files);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!();
+        print_score_row("Total", &report.total);
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} files could not be evaluated", failed));
+    }
+    Ok(())
+}
+
+/// File name of a path, for tables
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Print one line of the eval table
+fn print_score_row(name: &str, score: &Score) {
+    println!(
+        "{:<32} {:>6} {:>6.2}% {:>6.2}% {:>5} {:>5} {:>5}",
+        name,
+        score.words.reference_len(),
+        score.wer() * 100.0,
+        score.cer() * 100.0,
+        score.words.substitutions,
+        score.words.deletions,
+        score.words.insertions
+    );
+}
+
+/// Handle cache subcommands
+fn handle_cache_command(command: CacheCommands) -> anyhow::Result<()> {
     match command {
-        ModelCommands::Download { model, force } => {
-            let whisper_model = WhisperModel::from_str(&model).map_err(|e| {
-                anyhow::anyhow!(
-                    "Unknown model: {}. Use 'models list' to see available models. Error: {}",
-                    model,
-                    e
-                )
+        CacheCommands::Clear => {
+            let cache = TranscriptCache::new()?;
+            let (removed, freed) = cache.clear()?;
+            println!(
+                "{} Removed {} cached transcript(s), freed {}",
+                "Success:".green().bold(),
+                removed,
+                HumanBytes(freed)
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Handle queue subcommands
+async fn handle_queue_command(
+    store: Option<PathBuf>,
+    command: QueueCommands,
+    cli: &Cli,
+) -> anyhow::Result<()> {
+    let store = store
+        .or_else(JobQueue::default_path)
+        .ok_or_else(|| anyhow::anyhow!("Failed to find the purr data directory"))?;
+
+    match command {
+        QueueCommands::Add { files } => {
+            let mut inputs = Vec::with_capacity(files.len());
+            for file in files {
+                if !file.exists() {
+                    return Err(WhisperError::AudioNotFound { path: file }.into());
+                }
+                // Workers may run from another directory
+                inputs.push(file.canonicalize()?);
+            }
+
+            let ids = JobQueue::update(&store, |queue| {
+                inputs
+                    .iter()
+                    .map(|file| queue.add(file))
+                    .collect::<Vec<_>>()
             })?;
+            let mut added = 0;
+            for (file, id) in inputs.iter().zip(ids) {
+                match id {
+                    Some(id) => {
+                        debug!("Queued job {} for {}", id, file.display());
+                        added += 1;
+                    }
+                    None => println!(
+                        "{} {} is already queued.",
+                        "Info:".blue().bold(),
+                        file.display()
+                    ),
+                }
+            }
 
-            // check if it is already downloaded
-            if !force && model_manager.is_model_downloaded(whisper_model).await {
+            println!(
+                "{} Added {} job(s) to {}",
+                "Success:".green().bold(),
+                added,
+                store.display()
+            );
+        }
+
+        QueueCommands::Run {
+            workers,
+            retries,
+            format,
+            output_dir,
+            retry_failed,
+        } => {
+            let Some(_runner) = JobQueue::lock_runner(&store)? else {
+                return Err(anyhow::anyhow!(
+                    "Another 'queue run' is already processing {}",
+                    store.display()
+                ));
+            };
+
+            let (recovered, pending) = JobQueue::update(&store, |queue| {
+                let recovered = queue.recover();
+                if retry_failed {
+                    queue.retry_failed();
+                }
+                (recovered, queue.counts().pending)
+            })?;
+            if recovered > 0 {
                 println!(
-                    "{} Model {} is already downloaded.",
+                    "{} Resuming {} interrupted job(s)",
                     "Info:".blue().bold(),
-                    whisper_model.as_str()
+                    recovered
                 );
+            }
+
+            if pending == 0 {
+                println!("{} No pending jobs.", "Info:".blue().bold());
                 return Ok(());
             }
+            if let Some(ref output_dir) = output_dir {
+                std::fs::create_dir_all(output_dir)?;
+            }
 
+            // The model is loaded once and shared by all workers
+            let mut config = setup_config(cli).await?;
+            if matches!(format, OutputFormat::JsonFull) {
+                config.output_format.full_details = true;
+            }
+            let model = SharedModel::load(&config).await?;
+
+            let workers = workers.clamp(1, pending);
             println!(
-                "{} Downloading model: {} ({})",
+                "{} Processing {} job(s) with {} worker(s)",
                 "Info:".blue().bold(),
-                whisper_model.as_str(),
-                whisper_model.description()
+                pending,
+                workers
+            );
+
+            let worker = QueueWorker {
+                store: store.clone(),
+                model,
+                config,
+                format,
+                output_dir,
+                max_attempts: retries + 1,
+            };
+            let handles: Vec<_> = (0..workers)
+                .map(|_| tokio::spawn(worker.clone().run()))
+                .collect();
+            // Every worker finishes its current job before an error is reported
+            let mut first_error = None;
+            for handle in handles {
+                let result = handle.await.map_err(anyhow::Error::from).and_then(|r| r);
+                if let Err(e) = result {
+                    first_error.get_or_insert(e);
+                }
+            }
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+
+            let counts = JobQueue::open(&store)?.counts();
+            println!(
+                "{} {} done, {} failed, {} pending",
+                "Success:".green().bold(),
+                counts.done,
+                counts.failed,
+                counts.pending
             );
+        }
 
-            // Create progress bar
-            let progress_bar = ProgressBar::new(0);
-            progress_bar.set_style(
-                ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
-                    .unwrap()
-                    .progress_chars("#>-")
+        QueueCommands::Status => {
+            let queue = JobQueue::open(&store)?;
+            let counts = queue.counts();
+            println!("Queue: {}", store.display());
+            println!(
+                "{} pending, {} running, {} done, {} failed",
+                counts.pending.to_string().yellow(),
+                counts.running.to_string().blue(),
+                counts.done.to_string().green(),
+                counts.failed.to_string().red()
             );
+            if !queue.jobs().is_empty() {
+                println!();
+            }
 
-            model_manager.download_model_with_progress(whisper_model, |downloaded, total| {
-                if let Some(total) = total {
-                    if progress_bar.length().unwrap_or(0) != total {
-                        progress_bar.set_length(total);
+            for job in queue.jobs() {
+                let status = match job.status {
+                    JobStatus::Pending => job.status.yellow().to_string(),
+                    JobStatus::Running => job.status.blue().to_string(),
+                    JobStatus::Done => job.status.green().to_string(),
+                    JobStatus::Failed => job.status.red().to_string(),
+                };
+                println!(
+                    "{:>4}  {:<7}  {}{}",
+                    job.id,
+                    status,
+                    job.input.display(),
+                    if job.attempts > 1 {
+                        format!(" ({} attempts)", job.attempts).dimmed().to_string()
+                    } else {
+                        String::new()
                     }
-                    progress_bar.set_position(downloaded);
-                } else {
-                    // If total size is unknown, show as spinner with downloaded bytes
-                    progress_bar.set_style(
-                        ProgressStyle::default_spinner()
-                            .template("{spinner:.green} [{elapsed_precise}] {bytes} downloaded... {msg}")
-                            .unwrap()
+                );
+                if let Some(ref output) = job.output {
+                    println!("        {} {}", "→".dimmed(), output.display());
+                }
+                if let Some(ref stats) = job.stats {
+                    println!(
+                        "        {}",
+                        format!(
+                            "{:.1}s of audio in {:.1}s ({:.2}x), {} words",
+                            stats.audio_duration,
+                            stats.processing_time,
+                            stats.real_time_factor,
+                            stats.word_count
+                        )
+                        .dimmed()
                     );
-                    progress_bar.set_position(downloaded);
                 }
-            }).await?;
+                if let Some(ref error) = job.error {
+                    println!("        {}", error.red());
+                }
+            }
+        }
+    }
 
-            let per_sec = progress_bar
-                .length()
-                .map(|len| len as f64 / progress_bar.elapsed().as_secs_f64());
-            let elapsed = progress_bar.elapsed();
-            progress_bar.finish_and_clear();
+    Ok(())
+}
 
-            println!(
-                "{} Model downloaded in {:#}{}.",
-                "Success:".green().bold(),
-                HumanDuration(elapsed).cyan(),
-                if let Some(per_sec) = per_sec {
-                    format!(
-                        " ({}{} avg)",
-                        HumanBytes(per_sec as u64).cyan(),
-                        "/s".cyan()
-                    )
-                } else {
-                    "".to_string()
+/// A worker of `queue run`, taking jobs until none are pending
+///
+/// Every change goes through [`JobQueue::update`], so jobs added while the
+/// queue runs are picked up too.
+#[derive(Clone)]
+struct QueueWorker {
+    store: PathBuf,
+    model: SharedModel,
+    config: TranscriptionConfig,
+    format: OutputFormat,
+    output_dir: Option<PathBuf>,
+    max_attempts: u32,
+}
+
+impl QueueWorker {
+    async fn run(self) -> anyhow::Result<()> {
+        loop {
+            // Save the claim so an interrupted job is resumed by the next run
+            let Some(job) = JobQueue::update(&self.store, |queue| queue.claim())? else {
+                return Ok(());
+            };
+            info!("[{}] Transcribing {}", job.id, job.input.display());
+
+            let result = self.transcribe(&job).await;
+
+            match result {
+                Ok((output, stats)) => {
+                    println!(
+                        "{} [{}] {} {} {}",
+                        "Success:".green().bold(),
+                        job.id,
+                        job.input.display(),
+                        "→".dimmed(),
+                        output.display()
+                    );
+                    JobQueue::update(&self.store, |queue| queue.complete(job.id, output, stats))?;
+                }
+                Err(e) => {
+                    let status = JobQueue::update(&self.store, |queue| {
+                        queue.fail(job.id, format!("{:#}", e), self.max_attempts)
+                    })?;
+                    match status {
+                        Some(JobStatus::Pending) => {
+                            warn!(
+                                "[{}] Attempt {} failed, retrying: {:#}",
+                                job.id, job.attempts, e
+                            )
+                        }
+                        _ => error!("[{}] Failed: {:#}", job.id, e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Transcribe a job and write its transcript
+    async fn transcribe(&self, job: &Job) -> anyhow::Result<(PathBuf, TranscriptionStats)> {
+        let audio = AudioProcessor::new()?.load_audio(&job.input).await?;
+        let result = SyncWhisperTranscriber::with_model(&self.model, self.config.clone())
+            .transcribe(audio)
+            .await?;
+
+        let extension = self.format.extension();
+        let output = match self.output_dir {
+            // Inputs from different directories can share a name, the job id keeps them apart
+            Some(ref output_dir) => {
+                let mut file_name = job
+                    .input
+                    .file_stem()
+                    .map(|stem| stem.to_os_string())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid audio file name: {}", job.input.display())
+                    })?;
+                file_name.push(format!("-{}.{}", job.id, extension));
+                output_dir.join(file_name)
+            }
+            None => job.input.with_extension(extension),
+        };
+
+        let contents = render_output(&result, &self.format, &self.config)?;
+        std::fs::write(&output, contents)?;
+
+        Ok((output, result.stats))
+    }
+}
+
+/// Handle the align subcommand
+async fn handle_align_command(
+    audio_file: PathBuf,
+    transcript: PathBuf,
+    cli: &Cli,
+) -> anyhow::Result<()> {
+    if !audio_file.exists() {
+        return Err(WhisperError::AudioNotFound { path: audio_file }.into());
+    }
+
+    let transcript_text = std::fs::read_to_string(&transcript).map_err(|e| {
+        anyhow::anyhow!("Failed to read transcript {}: {}", transcript.display(), e)
+    })?;
+    if transcript_text.trim().is_empty() {
+        return Err(anyhow::anyhow!(
+            "Transcript is empty: {}",
+            transcript.display()
+        ));
+    }
+
+    let config = setup_config(cli).await?;
+    let output_config = config.clone();
+
+    info!("Aligning transcript...");
+    let result = align_file(&audio_file, &transcript_text, Some(config)).await?;
+
+    handle_output(result, cli, &output_config)
+}
+
+/// Handle config subcommands
+fn handle_config_command(command: ConfigCommands, cli: &Cli) -> anyhow::Result<()> {
+    match command {
+        ConfigCommands::Show => {
+            let config_file = ConfigFile::load()?;
+            let config = resolve_config(&config_file, cli)?;
+
+            // Print sources as comments so the output is itself a valid config file
+            if config_file.sources().is_empty() {
+                println!("# No config file found");
+            } else {
+                println!("# Loaded from:");
+                for source in config_file.sources() {
+                    println!("#   {}", source.display());
                 }
+            }
+            if let Some(ref profile) = cli.profile {
+                println!("# Profile: {}", profile);
+            }
+            let profiles = config_file.profiles();
+            if !profiles.is_empty() {
+                println!("# Available profiles: {}", profiles.join(", "));
+            }
+            println!();
+            print!("{}", toml::to_string_pretty(&config)?);
+
+            Ok(())
+        }
+    }
+}
+
+/// Handle the split subcommand
+async fn handle_split_command(
+    audio_file: PathBuf,
+    by: SplitBy,
+    format: ClipFormatArg,
+    output_dir: Option<PathBuf>,
+    manifest: Option<PathBuf>,
+    cli: &Cli,
+) -> anyhow::Result<()> {
+    if !audio_file.exists() {
+        return Err(WhisperError::AudioNotFound { path: audio_file }.into());
+    }
+
+    let output_dir = output_dir.unwrap_or_else(|| {
+        let stem = audio_file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "audio".to_string());
+        audio_file.with_file_name(format!("{}_clips", stem))
+    });
+    let manifest = manifest.unwrap_or_else(|| output_dir.join("manifest.jsonl"));
+
+    let config = setup_config(cli).await?;
+    let options = SplitOptions::new(&output_dir)
+        .with_mode(by.into())
+        .with_format(format.into());
+
+    info!("Splitting audio...");
+    let entries = split_file(&audio_file, Some(config), &options).await?;
+    write_manifest(&manifest, &entries)?;
+
+    println!(
+        "{} Wrote {} clips to {}",
+        "Success:".green().bold(),
+        entries.len(),
+        output_dir.display()
+    );
+    println!("Manifest: {}", manifest.display());
+
+    Ok(())
+}
+
+/// Download a model listed in the configured registry
+async fn download_registry_model(
+    model_manager: &ModelManager,
+    model: &RegistryModel,
+    force: bool,
+) -> anyhow::Result<()> {
+    if !force && model_manager.is_registry_model_downloaded(model) {
+        println!(
+            "{} Model {} is already downloaded.",
+            "Info:".blue().bold(),
+            model.name
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Downloading registry model: {} from {}",
+        "Info:".blue().bold(),
+        model.name,
+        model.url.dimmed()
+    );
+
+    let progress_bar = download_progress_bar();
+    model_manager
+        .download_registry_model(model, |downloaded, total| {
+            update_download_progress(&progress_bar, downloaded, total)
+        })
+        .await?;
+    finish_download_progress(progress_bar);
+
+    Ok(())
+}
+
+/// Print the size, header and memory estimate of a model file on disk
+fn print_model_file_info(path: &Path) {
+    let size = std::fs::metadata(path).map(|metadata| metadata.len()).ok();
+    if let Some(size) = size {
+        println!("Size: {}", format_file_size(size).yellow());
+    }
+
+    match GgmlHeader::read(path) {
+        Ok(header) => {
+            println!();
+            print_ggml_header(&header);
+            if let Some(size) = size {
+                println!(
+                    "Estimated memory: ~{}",
+                    HumanBytes(header.estimated_memory(size).round_to_unit(1024)).yellow()
+                );
+            }
+        }
+        Err(e) => match GgufHeader::read(path) {
+            Ok(gguf) => {
+                println!();
+                println!("Format: {}", gguf.to_string().yellow());
+                println!(
+                    "{} whisper.cpp only loads ggml models, convert this file to ggml first",
+                    "Warning:".yellow().bold()
+                );
+            }
+            Err(_) => println!("{} {}", "Warning:".yellow().bold(), e.report()),
+        },
+    }
+}
+
+/// Print the hyperparameters read from a ggml model header
+fn print_ggml_header(header: &GgmlHeader) {
+    println!("Type: {}", header.model_type().green());
+    println!(
+        "Multilingual: {}",
+        if header.is_multilingual() {
+            "yes".green().to_string()
+        } else {
+            "no (English-only)".yellow().to_string()
+        }
+    );
+    println!(
+        "Quantization: {}{}",
+        header.quantization().yellow(),
+        if header.is_quantized() {
+            format!(" (version {})", header.quantization_version())
+        } else {
+            String::new()
+        }
+    );
+    println!("Vocabulary: {} tokens", header.n_vocab);
+    println!("Mel bands: {}", header.n_mels);
+    println!(
+        "Encoder: {} layers, {} heads, {} state, {} context",
+        header.n_audio_layer, header.n_audio_head, header.n_audio_state, header.n_audio_ctx
+    );
+    println!(
+        "Decoder: {} layers, {} heads, {} state, {} context",
+        header.n_text_layer, header.n_text_head, header.n_text_state, header.n_text_ctx
+    );
+}
+
+fn download_progress_bar() -> ProgressBar {
+    let progress_bar = ProgressBar::new(0);
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+            .unwrap()
+            .progress_chars("#>-")
+    );
+    progress_bar
+}
+
+/// Progress bar for the decoding and transcription of a file
+fn transcription_progress_bar() -> ProgressBar {
+    let progress_bar = ProgressBar::new(100);
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg:12} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos:>3}% ({eta})")
+            .unwrap()
+            .progress_chars("#>-")
+    );
+    progress_bar
+}
+
+/// Progress callback driving a transcription progress bar
+///
+/// The bar restarts at each stage so the ETA only covers the current one.
+fn transcription_progress(progress_bar: &ProgressBar) -> ProgressCallback {
+    let progress_bar = progress_bar.clone();
+    Arc::new(move |progress: Progress| {
+        let stage = progress.stage.to_string();
+        if progress_bar.message() != stage {
+            progress_bar.reset();
+            progress_bar.set_message(stage);
+        }
+        progress_bar.set_position(progress.percent() as u64);
+    })
+}
+
+fn update_download_progress(progress_bar: &ProgressBar, downloaded: u64, total: Option<u64>) {
+    if let Some(total) = total {
+        if progress_bar.length().unwrap_or(0) != total {
+            progress_bar.set_length(total);
+        }
+        progress_bar.set_position(downloaded);
+    } else {
+        // If total size is unknown, show as spinner with downloaded bytes
+        progress_bar.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{elapsed_precise}] {bytes} downloaded... {msg}")
+                .unwrap(),
+        );
+        progress_bar.set_position(downloaded);
+    }
+}
+
+fn finish_download_progress(progress_bar: ProgressBar) {
+    let per_sec = progress_bar
+        .length()
+        .map(|len| len as f64 / progress_bar.elapsed().as_secs_f64());
+    let elapsed = progress_bar.elapsed();
+    progress_bar.finish_and_clear();
+
+    println!(
+        "{} Model downloaded in {:#}{}.",
+        "Success:".green().bold(),
+        HumanDuration(elapsed).cyan(),
+        if let Some(per_sec) = per_sec {
+            format!(
+                " ({}{} avg)",
+                HumanBytes(per_sec as u64).cyan(),
+                "/s".cyan()
+            )
+        } else {
+            "".to_string()
+        }
+    );
+}
+
+/// Handle model management subcommands
+async fn handle_model_command(command: ModelCommands, cli: &Cli) -> anyhow::Result<()> {
+    let verbose = cli.verbose;
+    let model_manager = ModelManager::new()?;
+
+    match command {
+        ModelCommands::Download { model, force } => {
+            let whisper_model = match WhisperModel::from_str(&model) {
+                Ok(whisper_model) => whisper_model,
+                Err(e) => {
+                    // Not a built-in model, try the configured registry
+                    let Some(registry_model) = model_manager.find_registry_model(&model).await?
+                    else {
+                        return Err(anyhow::anyhow!(
+                            "Unknown model: {}. Use 'models list' to see available models. Error: {}",
+                            model,
+                            e
+                        ));
+                    };
+                    return download_registry_model(&model_manager, &registry_model, force).await;
+                }
+            };
+
+            // check if it is already downloaded
+            if !force && model_manager.is_model_downloaded(whisper_model).await {
+                println!(
+                    "{} Model {} is already downloaded.",
+                    "Info:".blue().bold(),
+                    whisper_model.as_str()
+                );
+                return Ok(());
+            }
+
+            println!(
+                "{} Downloading model: {} ({})",
+                "Info:".blue().bold(),
+                whisper_model.as_str(),
+                whisper_model.description()
             );
+
+            if let Some(mirror) = &model_manager.settings().mirror {
+                println!("{} Using mirror: {}", "Info:".blue().bold(), mirror);
+            }
+
+            let progress_bar = download_progress_bar();
+            model_manager
+                .download_model_with_progress(whisper_model, |downloaded, total| {
+                    update_download_progress(&progress_bar, downloaded, total)
+                })
+                .await?;
+            finish_download_progress(progress_bar);
         }
 
         ModelCommands::List { available } => {
@@ -541,6 +2128,34 @@ async fn handle_model_command(command: ModelCommands, verbose: bool) -> anyhow::
                 // Group models by base type and show quantized variants together
                 print_model_groups();
 
+                match model_manager.registry_models().await {
+                    Ok(registry_models) if !registry_models.is_empty() => {
+                        println!();
+                        println!("{}", "Registry Models:".blue().bold());
+                        for model in registry_models {
+                            println!(
+                                "  {} - {}{}",
+                                model.name.green(),
+                                model
+                                    .description
+                                    .as_deref()
+                                    .unwrap_or("no description")
+                                    .dimmed(),
+                                model
+                                    .size
+                                    .map(|size| format!(" ({})", format_file_size(size)))
+                                    .unwrap_or_default()
+                                    .yellow()
+                            );
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        println!();
+                        println!("{} {}", "Warning:".yellow().bold(), e.report());
+                    }
+                }
+
                 println!();
                 println!(
                     "{}{}{}",
@@ -557,8 +2172,20 @@ async fn handle_model_command(command: ModelCommands, verbose: bool) -> anyhow::
             } else {
                 // List downloaded models (default behavior)
                 let downloaded = model_manager.list_downloaded_models().await?;
+                let registry_downloaded: Vec<_> = match model_manager.registry_models().await {
+                    Ok(models) => models
+                        .into_iter()
+                        .filter(|model| model_manager.is_registry_model_downloaded(model))
+                        .collect(),
+                    Err(e) => {
+                        println!("{} {}", "Warning:".yellow().bold(), e.report());
+                        Vec::new()
+                    }
+                };
 
-                if downloaded.is_empty() {
+                let custom = model_manager.list_custom_models().await?;
+
+                if downloaded.is_empty() && registry_downloaded.is_empty() && custom.is_empty() {
                     println!("{} No models downloaded yet.", "Info:".blue().bold());
                     println!(
                         "Use {}{} models download <model> to download a model.",
@@ -589,23 +2216,92 @@ async fn handle_model_command(command: ModelCommands, verbose: bool) -> anyhow::
                         }
                     }
 
+                    for model in registry_downloaded {
+                        let path = model_manager.registry_model_path(&model);
+                        let size = if let Ok(metadata) = std::fs::metadata(&path) {
+                            format_file_size(metadata.len())
+                        } else {
+                            "unknown size".to_string()
+                        };
+
+                        println!(
+                            "  {} - {} ({})",
+                            model.name.green(),
+                            model
+                                .description
+                                .as_deref()
+                                .unwrap_or("registry model")
+                                .dimmed(),
+                            size.yellow()
+                        );
+
+                        if verbose {
+                            println!("    Path: {}", path.display().to_string().dimmed());
+                        }
+                    }
+
+                    for model in custom {
+                        println!(
+                            "  {} - custom {} model, {}{} ({})",
+                            model.name.green(),
+                            model.header.model_type(),
+                            model.header.quantization(),
+                            if model.header.is_multilingual() {
+                                ""
+                            } else {
+                                ", English-only"
+                            },
+                            format_file_size(model.size).yellow()
+                        );
+
+                        if verbose {
+                            println!(
+                                "    Path: {}",
+                                model_manager
+                                    .custom_model_path(&model.name)
+                                    .display()
+                                    .to_string()
+                                    .dimmed()
+                            );
+                        }
+                    }
+
                     println!();
                     println!(
                         "XDG data directory: {}",
                         model_manager.models_dir().display().to_string().dimmed()
                     );
+                    if let Some(mirror) = &model_manager.settings().mirror {
+                        println!("Mirror: {}", mirror.dimmed());
+                    }
+                    if let Some(registry) = &model_manager.settings().registry {
+                        println!("Registry: {}", registry.dimmed());
+                    }
                 }
             }
         }
 
         ModelCommands::Delete { model } => {
-            let whisper_model = WhisperModel::from_str(&model).map_err(|e| {
-                anyhow::anyhow!(
-                    "Unknown model: {}. Use 'models list' to see available models. Error: {}",
-                    model,
-                    e
-                )
-            })?;
+            let whisper_model = match WhisperModel::from_str(&model) {
+                Ok(whisper_model) => whisper_model,
+                Err(e) => {
+                    if model_manager.find_custom_model(&model).await?.is_none() {
+                        return Err(anyhow::anyhow!(
+                            "Unknown model: {}. Use 'models list' to see available models. Error: {}",
+                            model,
+                            e
+                        ));
+                    }
+
+                    model_manager.delete_custom_model(&model).await?;
+                    println!(
+                        "{} Custom model {} deleted successfully.",
+                        "Success:".green().bold(),
+                        model
+                    );
+                    return Ok(());
+                }
+            };
 
             if !model_manager.is_model_downloaded(whisper_model).await {
                 println!(
@@ -626,38 +2322,307 @@ async fn handle_model_command(command: ModelCommands, verbose: bool) -> anyhow::
         }
 
         ModelCommands::Info { model } => {
-            let whisper_model = WhisperModel::from_str(&model).map_err(|e| {
-                anyhow::anyhow!(
-                    "Unknown model: {}. Use 'models list' to see available models. Error: {}",
-                    model,
-                    e
-                )
+            let model = model.or_else(|| cli.model.clone()).ok_or_else(|| {
+                anyhow::anyhow!("No model given. Pass a model name or the path to a model file.")
             })?;
 
             println!("{} Model Information", "Info:".blue().bold());
             println!();
-            println!("Name: {}", whisper_model.as_str().green().bold());
-            println!("Description: {}", whisper_model.description());
-            println!("Filename: {}", whisper_model.filename().yellow());
 
-            let is_downloaded = model_manager.is_model_downloaded(whisper_model).await;
-            println!(
-                "Downloaded: {}",
+            if let Ok(whisper_model) = WhisperModel::from_str(&model) {
+                println!("Name: {}", whisper_model.as_str().green().bold());
+                println!("Description: {}", whisper_model.description());
+                println!("Filename: {}", whisper_model.filename().yellow());
+
+                let is_downloaded = model_manager.is_model_downloaded(whisper_model).await;
+                println!(
+                    "Downloaded: {}",
+                    if is_downloaded {
+                        "yes".green().to_string()
+                    } else {
+                        "no".red().to_string()
+                    }
+                );
+
+                if let Some(checksum) = whisper_model.checksum() {
+                    println!("Checksum: {}", checksum.to_string().dimmed());
+                }
+
                 if is_downloaded {
-                    "yes".green().to_string()
+                    let path = model_manager.get_model_path(whisper_model);
+                    println!("Path: {}", path.display());
+                    print_model_file_info(&path);
+                }
+            } else if let Some(custom) = model_manager.find_custom_model(&model).await? {
+                let path = model_manager.custom_model_path(&custom.name);
+                println!("Name: {}", custom.name.green().bold());
+                println!("Description: custom model");
+                println!("Imported from: {}", custom.source.display());
+                println!("Path: {}", path.display());
+                print_model_file_info(&path);
+            } else if let Some(path) = model_manager.find_model_file(&model).await {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| model.clone());
+                println!("Name: {}", name.green().bold());
+                println!("Path: {}", path.display());
+                print_model_file_info(&path);
+            } else {
+                return Err(anyhow::anyhow!(
+                    "Unknown model: {}. Use 'models list' to see available models, or pass the path to a model file.",
+                    model
+                ));
+            }
+        }
+
+        ModelCommands::Import { path, name, force } => {
+            if force && model_manager.find_custom_model(&name).await?.is_some() {
+                model_manager.delete_custom_model(&name).await?;
+            }
+
+            println!(
+                "{} Importing {} as {}",
+                "Info:".blue().bold(),
+                path.display(),
+                name.green()
+            );
+
+            let progress_bar = download_progress_bar();
+            let model = model_manager
+                .import_custom_model(&path, &name, |copied, total| {
+                    update_download_progress(&progress_bar, copied, total)
+                })
+                .await;
+            progress_bar.finish_and_clear();
+            let model = model?;
+
+            println!(
+                "{} Imported custom model {}. Use it with --model {}",
+                "Success:".green().bold(),
+                model.name.green(),
+                model.name.cyan()
+            );
+            println!();
+            print_ggml_header(&model.header);
+        }
+
+        ModelCommands::Quantize {
+            model,
+            quantization,
+            name,
+        } => {
+            let target = QuantizationType::from(quantization);
+            let source = model_manager.find_model_file(&model).await.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Model {} not found. Download or import it first, or pass the path to a model file.",
+                    model
+                )
+            })?;
+
+            let name = name.unwrap_or_else(|| {
+                let base = if WhisperModel::from_str(&model).is_ok() {
+                    model.clone()
                 } else {
-                    "no".red().to_string()
+                    source
+                        .file_stem()
+                        .map(|stem| {
+                            stem.to_string_lossy()
+                                .trim_start_matches("ggml-")
+                                .to_string()
+                        })
+                        .unwrap_or_else(|| model.clone())
+                };
+                let name = format!("{}-{}", base, target);
+                // Published quantized variants keep their built-in names
+                if WhisperModel::from_str(&name).is_ok() {
+                    format!("{}-local", name)
+                } else {
+                    name
                 }
+            });
+
+            let spinner = ProgressBar::new_spinner();
+            spinner.set_message(format!("Quantizing {} to {}...", source.display(), target));
+            spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+            let result = model_manager.quantize_model(&source, &name, target).await;
+            spinner.finish_and_clear();
+            let (quantized, stats) = result?;
+
+            println!(
+                "{} Quantized {} tensors ({} kept), {} -> {}. Use it with --model {}",
+                "Success:".green().bold(),
+                stats.quantized_tensors,
+                stats.kept_tensors,
+                format_file_size(stats.original_size).yellow(),
+                format_file_size(stats.quantized_size).yellow(),
+                quantized.name.cyan()
             );
+        }
 
-            if is_downloaded {
-                let path = model_manager.get_model_path(whisper_model);
-                println!("Path: {}", path.display());
+        ModelCommands::Du => {
+            let mut files = model_manager.disk_usage().await?;
+            if files.is_empty() {
+                println!("{} The models directory is empty.", "Info:".blue().bold());
+                return Ok(());
+            }
+            files.sort_by_key(|file| std::cmp::Reverse(file.size));
+
+            let now = std::time::SystemTime::now();
+            for file in &files {
+                let last_used = match file.last_used {
+                    Some(time) => {
+                        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(time);
+                        let age = now.duration_since(time).unwrap_or_default();
+                        format!("used {} ago", HumanDuration(age))
+                    }
+                    None if file.kind.is_model() => "never used".to_string(),
+                    None => String::new(),
+                };
+                let kind = format!("{:?}", file.kind).to_lowercase();
+
+                println!(
+                    "{:>10}  {:<9} {:<20} {}",
+                    format_file_size(file.size).yellow(),
+                    if file.is_garbage() {
+                        kind.red().to_string()
+                    } else {
+                        kind.dimmed().to_string()
+                    },
+                    last_used.dimmed(),
+                    file.key
+                );
+            }
+
+            let total: u64 = files.iter().map(|file| file.size).sum();
+            let garbage: u64 = files
+                .iter()
+                .filter(|file| file.is_garbage())
+                .map(|file| file.size)
+                .sum();
+            println!();
+            println!(
+                "Total: {} in {}",
+                format_file_size(total).yellow().bold(),
+                model_manager.models_dir().display().to_string().dimmed()
+            );
+            if garbage > 0 {
+                println!(
+                    "{} {} can be reclaimed with '{} models prune'",
+                    "Info:".blue().bold(),
+                    format_file_size(garbage).yellow(),
+                    env!("CARGO_PKG_NAME")
+                );
+            }
+        }
+
+        ModelCommands::Prune {
+            unused_days,
+            dry_run,
+        } => {
+            let options = PruneOptions {
+                unused_for: unused_days
+                    .map(|days| std::time::Duration::from_secs(days * 24 * 60 * 60)),
+                dry_run,
+            };
+            let removed = model_manager.prune(&options).await?;
+
+            if removed.is_empty() {
+                println!("{} Nothing to prune.", "Info:".blue().bold());
+                return Ok(());
+            }
+
+            for file in &removed {
+                println!(
+                    "  {} {} ({})",
+                    if dry_run { "would delete" } else { "deleted" }.red(),
+                    file.key,
+                    format_file_size(file.size).yellow()
+                );
+            }
+
+            let freed: u64 = removed.iter().map(|file| file.size).sum();
+            println!();
+            if dry_run {
+                println!(
+                    "{} {} would be freed. Run without --dry-run to delete.",
+                    "Info:".blue().bold(),
+                    format_file_size(freed).yellow()
+                );
+            } else {
+                println!(
+                    "{} Freed {}.",
+                    "Success:".green().bold(),
+                    format_file_size(freed).yellow()
+                );
+            }
+        }
+
+        ModelCommands::Verify { model } => {
+            let models = match model {
+                Some(model) => {
+                    let whisper_model = WhisperModel::from_str(&model).map_err(|e| {
+                        anyhow::anyhow!(
+                            "Unknown model: {}. Use 'models list' to see available models. Error: {}",
+                            model,
+                            e
+                        )
+                    })?;
+                    if !model_manager.is_model_downloaded(whisper_model).await {
+                        return Err(anyhow::anyhow!(
+                            "Model {} is not downloaded",
+                            whisper_model.as_str()
+                        ));
+                    }
+                    vec![whisper_model]
+                }
+                None => model_manager.list_downloaded_models().await?,
+            };
+
+            if models.is_empty() {
+                println!("{} No models downloaded yet.", "Info:".blue().bold());
+                return Ok(());
+            }
 
-                if let Ok(metadata) = std::fs::metadata(&path) {
-                    println!("Size: {}", format_file_size(metadata.len()).yellow());
+            let mut failed = 0;
+            for model in models {
+                let spinner = ProgressBar::new_spinner();
+                spinner.set_message(format!("Hashing {}...", model.as_str()));
+                spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+                let status = model_manager.verify_model(model).await;
+                spinner.finish_and_clear();
+
+                match status? {
+                    VerifyStatus::Valid => {
+                        println!("  {} {}", "✓".green().bold(), model.as_str().green());
+                    }
+                    VerifyStatus::Mismatch { expected, actual } => {
+                        failed += 1;
+                        println!(
+                            "  {} {} - checksum mismatch (expected {}, got {})",
+                            "✗".red().bold(),
+                            model.as_str().red(),
+                            expected.dimmed(),
+                            actual.yellow()
+                        );
+                    }
+                    VerifyStatus::Unknown => {
+                        println!(
+                            "  {} {} - no known checksum",
+                            "?".yellow().bold(),
+                            model.as_str()
+                        );
+                    }
                 }
             }
+
+            if failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} model(s) failed verification. Re-download them with '{} models download <model> --force'",
+                    failed,
+                    env!("CARGO_PKG_NAME")
+                ));
+            }
         }
     }
 
@@ -757,16 +2722,6 @@ fn format_file_size(size: u64) -> String {
     }
 }
 
-/// Format time for SRT subtitles (HH:MM:SS,mmm)
-fn format_srt_time(seconds: f64) -> String {
-    let hours = (seconds / 3600.0) as u32;
-    let minutes = ((seconds % 3600.0) / 60.0) as u32;
-    let secs = (seconds % 60.0) as u32;
-    let millis = ((seconds % 1.0) * 1000.0) as u32;
-
-    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
-}
-
 /// Print grouped model information with quantized variants
 fn print_model_groups() {
     // Define model groups with their base models and quantized variants
@@ -903,22 +2858,115 @@ fn print_model_groups() {
     }
 }
 
+/// Explain an automatic model choice on stderr, keeping stdout for the transcript
+fn print_model_selection(selection: &ModelSelection) {
+    eprintln!(
+        "{} Using model {}",
+        "Info:".blue().bold(),
+        selection.model.as_str().green()
+    );
+    for reason in &selection.reasons {
+        eprintln!("  {} {}", "•".dimmed(), reason.dimmed());
+    }
+}
+
+/// Combine the config files, the selected profile and the command line flags
+///
+/// Flags only override file values when given. The model is left as written
+/// (a name or a path) and resolved by [`setup_config`].
+fn resolve_config(config_file: &ConfigFile, cli: &Cli) -> anyhow::Result<TranscriptionConfig> {
+    // Timestamps are opt-in on the command line
+    let mut defaults = TranscriptionConfig::new();
+    defaults.output_format.include_timestamps = false;
+
+    let mut config = config_file.resolve(defaults, cli.profile.as_deref())?;
+
+    if let Some(ref model) = cli.model {
+        config.model_path = Some(PathBuf::from(model));
+    }
+    if let Some(ref language) = cli.language {
+        config = config.with_language(language);
+    }
+    if cli.translate {
+        config = config.with_translate(true);
+    }
+    if cli.no_gpu {
+        config = config.with_gpu(false);
+    }
+    if let Some(threads) = cli.threads {
+        config = config.with_threads(threads);
+    }
+    if let Some(processors) = cli.processors {
+        config = config.with_processors(processors);
+    }
+    if let Some(temperature) = cli.temperature {
+        config.temperature = temperature;
+    }
+    if let Some(hallucinations) = cli.hallucinations {
+        config = config.with_hallucinations(hallucinations.into());
+    }
+    if cli.trim_text {
+        config.text_processing.trim = true;
+    }
+    if cli.collapse_whitespace {
+        config.text_processing.collapse_whitespace = true;
+    }
+    if cli.itn {
+        config.text_processing.inverse_normalization = true;
+    }
+    if let Some(ref path) = cli.mask_words {
+        let words = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read word list {}: {}", path.display(), e))?;
+        config.text_processing.masked_words = words
+            .lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty() && !word.starts_with('#'))
+            .map(String::from)
+            .collect();
+    }
+    if cli.timestamps {
+        config.output_format.include_timestamps = true;
+    }
+    if cli.word_timestamps {
+        config.output_format.word_timestamps = true;
+    }
+    if cli.speaker_turns {
+        config.speaker_turns = true;
+    }
+    if matches!(cli.output, OutputFormat::JsonFull) {
+        config.output_format.full_details = true;
+    }
+    if cli.cache {
+        config = config.with_cache(true);
+    }
+    if cli.no_cache {
+        config = config.with_cache(false);
+    }
+    if cli.verbose {
+        config = config.with_verbose(true);
+    }
+
+    Ok(config)
+}
+
 async fn setup_config(cli: &Cli) -> anyhow::Result<TranscriptionConfig> {
     // Build transcription config
-    let mut config = TranscriptionConfig::new()
-        .with_gpu(!cli.no_gpu)
-        .with_sample_rate(16000); // Whisper's preferred sample rate
+    let mut config = resolve_config(&ConfigFile::load()?, cli)?;
 
     let model_manager = ModelManager::new()?;
-    if let Some(ref model_string) = cli.model {
+    let model_spec = config
+        .model_path
+        .take()
+        .map(|path| path.to_string_lossy().into_owned());
+    if let Some(ref model_string) = model_spec {
         let model_path = Path::new(model_string);
         if model_path.is_absolute() {
             // If absolute path, use it directly
             if !model_path.exists() {
-                return Err(anyhow::anyhow!(
-                    "Model file not found at: {}",
-                    model_path.display()
-                ));
+                return Err(WhisperError::ModelNotFound {
+                    searched: vec![model_path.to_path_buf()],
+                }
+                .into());
             }
             config = config.with_model_path(model_path);
         } else {
@@ -928,90 +2976,131 @@ async fn setup_config(cli: &Cli) -> anyhow::Result<TranscriptionConfig> {
                 config = config.with_model_path(model_path);
             } else {
                 // intelligently check if the model is downloaded
-                let model = WhisperModel::from_str(model_string)?;
-                if model_manager.is_model_downloaded(model).await {
-                    config = config.with_model_path(model_manager.get_model_path(model));
-                } else {
-                    // If not downloaded, prompt user to download
-                    if let Some(model) = prompt_for_model_download(Some(model)).await? {
+                match WhisperModel::from_str(model_string) {
+                    Ok(model) if model_manager.is_model_downloaded(model).await => {
+                        config = config.with_model_path(model_manager.get_model_path(model));
+                    }
+                    Ok(model) => {
+                        // If not downloaded, prompt user to download
+                        let model = prompt_for_model_download(Some(model)).await?;
                         model_manager.assign_model_path(&mut config, model);
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "No model specified and no downloaded models found."
-                        ));
+                    }
+                    Err(_) if model_manager.custom_model_path(model_string).exists() => {
+                        config =
+                            config.with_model_path(model_manager.custom_model_path(model_string));
+                    }
+                    Err(e) => {
+                        // Fall back to models from the configured registry
+                        let Some(registry_model) =
+                            model_manager.find_registry_model(model_string).await?
+                        else {
+                            return Err(e.into());
+                        };
+                        if !model_manager.is_registry_model_downloaded(&registry_model) {
+                            print_download_hint(&registry_model.name);
+                            return Err(WhisperError::ModelNotFound {
+                                searched: vec![
+                                    model_path,
+                                    model_manager.registry_model_path(&registry_model),
+                                ],
+                            }
+                            .into());
+                        }
+                        config = config
+                            .with_model_path(model_manager.registry_model_path(&registry_model));
                     }
                 }
             }
         }
     } else {
-        // No model specified, check if any downloaded models exist
-        if let Some(model) = model_manager.find_first_available_model().await {
-            config = config.with_model_path(model);
+        // No model specified, pick the best downloaded model for this machine
+        let hardware =
+            HardwareProfile::from_system_info(&SystemInfo::get(), config.use_gpu, num_cpus::get());
+        let criteria = SelectionCriteria {
+            language: config.language.clone(),
+            quality: cli.quality.into(),
+        };
+
+        if let Some(selection) = model_manager.select_model(&hardware, &criteria).await? {
+            print_model_selection(&selection);
+            model_manager.assign_model_path(&mut config, selection.model);
+        } else if !model_manager.list_downloaded_models().await?.is_empty() {
+            // Models are installed, but none can handle the requested language
+            let recommended =
+                select_model(WhisperModel::all_models(), &hardware, &criteria).map(|s| s.model);
+            eprintln!(
+                "{} None of the downloaded models support language '{}'.",
+                "Notice:".yellow().bold(),
+                config.language.as_deref().unwrap_or_default()
+            );
+            let model = prompt_for_model_download(recommended).await?;
+            model_manager.assign_model_path(&mut config, model);
         } else {
             // Prompt user to download the base model
-            if let Some(model) = prompt_for_model_download(None).await? {
-                // If user agrees, download the base model
-                model_manager.assign_model_path(&mut config, model);
-            } else {
-                return Err(anyhow::anyhow!(
-                    "No model specified and no downloaded models found."
-                ));
-            }
+            let model = prompt_for_model_download(None).await?;
+            model_manager.assign_model_path(&mut config, model);
         }
     }
 
-    if let Some(ref language) = cli.language {
-        config = config.with_language(language);
+    // Parallel regions share the cores
+    if config.num_threads.is_none() {
+        let threads = (num_cpus::get() / config.processors.max(1)).max(1);
+        config = config.with_threads(threads);
     }
 
-    config = config.with_translate(cli.translate);
-    config = config.with_threads(cli.threads.unwrap_or_else(num_cpus::get));
-    config.temperature = cli.temperature;
-    config.output_format.include_timestamps = cli.timestamps;
-    config.output_format.word_timestamps = cli.word_timestamps;
-    config = config.with_verbose(cli.verbose);
-
     Ok(config)
 }
 
-fn handle_output(result: purr_core::SyncTranscriptionResult, cli: &Cli) -> anyhow::Result<()> {
-    // Prepare output content
-    let output_content = match cli.output {
+/// Format a transcription result
+fn render_output(
+    result: &purr_core::SyncTranscriptionResult,
+    format: &OutputFormat,
+    config: &TranscriptionConfig,
+) -> anyhow::Result<String> {
+    let options = &config.output_format;
+    Ok(match format {
         OutputFormat::Text => {
-            if cli.timestamps {
+            if options.word_timestamps {
                 result
                     .segments
                     .iter()
-                    .map(|segment| {
-                        format!(
-                            "[{:.2}s -> {:.2}s] {}",
-                            segment.start, segment.end, segment.text
-                        )
-                    })
+                    .flat_map(|segment| segment.words.iter().flatten())
+                    .map(|word| format!("[{:.2}s -> {:.2}s] {}", word.start, word.end, word.word))
                     .collect::<Vec<_>>()
                     .join("\n")
+            } else if options.include_timestamps {
+                to_timestamped_text(&result.segments)
             } else {
                 result.text.clone()
             }
         }
-        OutputFormat::Json => serde_json::to_string_pretty(&result)?,
-        OutputFormat::Srt => result
-            .segments
-            .iter()
-            .enumerate()
-            .map(|(i, segment)| {
-                format!(
-                    "{}\n{} --> {}\n{}\n",
-                    i + 1,
-                    format_srt_time(segment.start),
-                    format_srt_time(segment.end),
-                    segment.text
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n"),
+        OutputFormat::Json => serde_json::to_string_pretty(result)?,
+        OutputFormat::Jsonl => to_jsonl(&result.segments)?,
+        OutputFormat::JsonFull => to_whisper_cpp_json(result, config)?,
+        OutputFormat::Tsv => to_tsv(&result.segments),
+        OutputFormat::Csv => to_csv(&result.segments),
+        OutputFormat::Srt => to_srt(&result.segments),
+        OutputFormat::Vtt => to_vtt(&result.segments),
         OutputFormat::Txt => result.text.clone(),
-    };
+        OutputFormat::Markdown => to_markdown(
+            &paragraphs(&result.segments, &options.paragraphs),
+            options.include_timestamps,
+        ),
+        OutputFormat::Html => to_html(
+            &paragraphs(&result.segments, &options.paragraphs),
+            "Transcript",
+            options.include_timestamps,
+        ),
+    })
+}
+
+fn handle_output(
+    result: purr_core::SyncTranscriptionResult,
+    cli: &Cli,
+    config: &TranscriptionConfig,
+) -> anyhow::Result<()> {
+    // Prepare output content
+    let output_content = render_output(&result, &cli.output, config)?;
 
     // Write output to file or stdout
     if let Some(output_file) = &cli.output_file {
@@ -1028,28 +3117,92 @@ fn handle_output(result: purr_core::SyncTranscriptionResult, cli: &Cli) -> anyho
         print!("{}", output_content);
     }
 
-    // Print statistics
     if cli.verbose {
-        println!();
-        println!("{}", "Transcription Statistics:".green().bold());
-        println!("Audio duration: {:.2}s", result.stats.audio_duration);
-        println!("Processing time: {:.2}s", result.stats.processing_time);
-        println!("Real-time factor: {:.2}x", result.stats.real_time_factor);
-        println!("Segments: {}", result.stats.segment_count);
-        println!(
-            "Average segment length: {:.2}s",
-            result.stats.avg_segment_length
-        );
-        println!("Words: {}", result.stats.word_count);
-        println!("Words per minute: {:.1}", result.stats.words_per_minute);
-        if let Some(lang) = &result.language {
-            println!("Detected language: {}", lang);
-        }
+        print_transcription_stats(&result, cli.output.is_machine_readable());
     }
 
     Ok(())
 }
 
+/// Print the statistics of a finished transcription
+///
+/// They go to stderr when stdout holds machine-readable output.
+fn print_transcription_stats(result: &purr_core::SyncTranscriptionResult, to_stderr: bool) {
+    print_stats(
+        "Transcription Statistics:",
+        &result.stats,
+        result.language.as_deref(),
+        to_stderr,
+    );
+}
+
+/// Print transcription statistics under a title, to stdout or stderr
+fn print_stats(title: &str, stats: &TranscriptionStats, language: Option<&str>, to_stderr: bool) {
+    let mut lines = vec![
+        String::new(),
+        title.green().bold().to_string(),
+        format!("Audio duration: {:.2}s", stats.audio_duration),
+        format!("Processing time: {:.2}s", stats.processing_time),
+        format!("Real-time factor: {:.2}x", stats.real_time_factor),
+        format!("Segments: {}", stats.segment_count),
+        format!("Average segment length: {:.2}s", stats.avg_segment_length),
+        format!("Words: {}", stats.word_count),
+        format!("Words per minute: {:.1}", stats.words_per_minute),
+    ];
+    if let Some(language) = language {
+        lines.push(format!("Detected language: {}", language));
+    }
+
+    for line in lines {
+        if to_stderr {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Segment callback printing each segment to stdout in the given format
+fn segment_printer(format: OutputFormat, include_timestamps: bool) -> SegmentCallback {
+    let cue = AtomicUsize::new(1);
+    Arc::new(move |segment: TranscriptionSegment| {
+        let text = segment.text.trim();
+        let index = cue.fetch_add(1, Ordering::Relaxed);
+        let output = match format {
+            OutputFormat::Text if include_timestamps => {
+                format!("[{:.2}s -> {:.2}s] {}\n", segment.start, segment.end, text)
+            }
+            OutputFormat::Text
+            | OutputFormat::Txt
+            | OutputFormat::Markdown
+            | OutputFormat::Html
+            | OutputFormat::JsonFull => format!("{}\n", text),
+            OutputFormat::Json | OutputFormat::Jsonl => match serde_json::to_string(&segment) {
+                // Segments of a JSON array are separated by commas
+                Ok(json) if matches!(format, OutputFormat::Json) && index > 1 => {
+                    format!(",\n{}", json)
+                }
+                Ok(json) if matches!(format, OutputFormat::Json) => json,
+                Ok(json) => format!("{}\n", json),
+                Err(e) => {
+                    warn!("Failed to serialize segment: {}", e);
+                    return;
+                }
+            },
+            OutputFormat::Tsv => tsv_row(segment.start, segment.end, text),
+            OutputFormat::Csv => csv_row(segment.start, segment.end, text),
+            OutputFormat::Srt => {
+                format!("{}\n", srt_cue(index, segment.start, segment.end, text))
+            }
+            OutputFormat::Vtt => format!("{}\n", vtt_cue(segment.start, segment.end, text)),
+        };
+
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(output.as_bytes());
+        let _ = stdout.flush();
+    })
+}
+
 /// Helper struct for organizing model information
 struct ModelGroup {
     name: &'static str,
@@ -1102,11 +3255,75 @@ pub const CLAP_STYLING: Styles = Styles::styled()
 #[cfg(test)]
 mod tests {
     use super::*;
+    use purr_core::output::srt_timestamp;
 
     #[test]
     fn test_srt_time_formatting() {
-        assert_eq!(format_srt_time(0.0), "00:00:00,000");
-        assert_eq!(format_srt_time(61.5), "00:01:01,500");
-        assert_eq!(format_srt_time(3661.123), "01:01:01,123");
+        assert_eq!(srt_timestamp(0.0), "00:00:00,000");
+        assert_eq!(srt_timestamp(61.5), "00:01:01,500");
+        assert_eq!(srt_timestamp(3661.123), "01:01:01,123");
+    }
+
+    #[test]
+    fn test_unique() {
+        assert_eq!(unique([true, false, true]), vec![true, false]);
+        assert_eq!(unique([4, 8, 4, 2]), vec![4, 8, 2]);
+    }
+
+    #[test]
+    fn test_reference_pairs() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "a.wav",
+            "a.txt",
+            "talk.part1.wav",
+            "talk.part1.txt",
+            "talk.txt",
+            "orphan.mp3",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let pairs = reference_pairs(dir.path(), dir.path()).unwrap();
+        let names: Vec<_> = pairs
+            .iter()
+            .map(|(audio, reference)| {
+                (
+                    audio.file_name().unwrap().to_string_lossy().into_owned(),
+                    reference
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("a.wav".to_string(), "a.txt".to_string()),
+                ("talk.part1.wav".to_string(), "talk.part1.txt".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(
+            exit_code(&WhisperError::ModelNotFound { searched: vec![] }),
+            EXIT_MODEL
+        );
+        assert_eq!(
+            exit_code(&WhisperError::DownloadFailed {
+                status: 404,
+                url: "https://example.com".to_string()
+            }),
+            EXIT_DOWNLOAD
+        );
+        assert_eq!(exit_code(&WhisperError::Cancelled), EXIT_CANCELLED);
+        assert_eq!(
+            exit_code(&WhisperError::Unknown("?".to_string())),
+            EXIT_FAILURE
+        );
     }
 }
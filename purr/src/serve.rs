@@ -0,0 +1,812 @@
+//! HTTP transcription server exposing the OpenAI audio API
+//!
+//! Serves `POST /v1/audio/transcriptions` and `POST /v1/audio/translations`
+//! with the same multipart fields and response formats as the OpenAI API, so
+//! existing clients only need their base URL changed. The model is loaded once
+//! at startup and shared by all requests; the `model` field of requests is
+//! accepted but ignored.
+//!
+//! `GET /v1/audio/stream` opens a WebSocket for live transcription. The client
+//! sends a `start` message, then binary audio frames, then optionally `stop`:
+//!
+//! ```json
+//! {"type": "start", "language": "en", "translate": false, "prompt": null, "encoding": "pcm_s16le"}
+//! ```
+//!
+//! Frames are 16 kHz mono PCM (`pcm_s16le` or `pcm_f32le`) or one raw Opus
+//! packet each (`opus`). The server answers `{"type": "ready"}`, then sends
+//! `{"type": "transcript", ...}` messages holding a [`StreamingChunk`], partial
+//! ones while a window fills up and a final one per window. The last chunk
+//! carries `final_stats` and is followed by a close frame.
+
+use axum::{
+    body::Bytes,
+    extract::{
+        multipart::MultipartError,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Multipart, State,
+    },
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures::{stream, Stream, StreamExt};
+use owo_colors::OwoColorize as _;
+use purr_core::{
+    live::{LiveDecoder, LiveEncoding},
+    output::{to_srt, to_vtt},
+    whisper::streaming::{LiveOptions, StreamWhisperTranscriber},
+    whisper::sync::SyncWhisperTranscriber,
+    whisper::{TranscriptionSegment, WhisperTranscriber as _},
+    AudioProcessor, SharedModel, StreamingChunk, SyncTranscriptionResult, TranscriptionConfig,
+    WhisperError,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{error, info};
+
+/// Largest accepted upload
+const MAX_UPLOAD_SIZE: usize = 512 * 1024 * 1024;
+
+/// Decoded audio frames of a live session waiting for the transcriber
+const LIVE_AUDIO_FRAMES: usize = 64;
+
+/// Options of the `serve` command
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    /// Address to listen on
+    pub addr: SocketAddr,
+
+    /// Maximum number of transcriptions and live sessions running at the same time
+    pub parallel: usize,
+}
+
+#[derive(Clone)]
+struct AppState {
+    model: SharedModel,
+    config: TranscriptionConfig,
+    permits: Arc<Semaphore>,
+}
+
+/// Whether a request transcribes or translates to English
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Task {
+    Transcribe,
+    Translate,
+}
+
+impl Task {
+    fn as_str(self) -> &'static str {
+        match self {
+            Task::Transcribe => "transcribe",
+            Task::Translate => "translate",
+        }
+    }
+}
+
+/// Format of the response body (`response_format` field)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum ResponseFormat {
+    #[default]
+    Json,
+    Text,
+    Srt,
+    VerboseJson,
+    Vtt,
+}
+
+impl FromStr for ResponseFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ResponseFormat::Json),
+            "text" => Ok(ResponseFormat::Text),
+            "srt" => Ok(ResponseFormat::Srt),
+            "verbose_json" => Ok(ResponseFormat::VerboseJson),
+            "vtt" => Ok(ResponseFormat::Vtt),
+            other => Err(format!(
+                "Unsupported response_format '{}', expected one of json, text, srt, verbose_json, vtt",
+                other
+            )),
+        }
+    }
+}
+
+/// Fields of a transcription or translation request
+#[derive(Debug, Default)]
+struct AudioRequest {
+    /// Uploaded file name and contents
+    file: Option<(String, Bytes)>,
+    language: Option<String>,
+    prompt: Option<String>,
+    response_format: ResponseFormat,
+    temperature: Option<f32>,
+    word_timestamps: bool,
+    stream: bool,
+}
+
+impl AudioRequest {
+    async fn from_multipart(mut multipart: Multipart) -> Result<Self, ApiError> {
+        let mut request = Self::default();
+
+        while let Some(field) = multipart.next_field().await? {
+            let name = field.name().unwrap_or_default().to_string();
+            match name.as_str() {
+                "file" => {
+                    let file_name = field.file_name().unwrap_or("audio").to_string();
+                    request.file = Some((file_name, field.bytes().await?));
+                }
+                "language" => request.language = non_empty(field.text().await?),
+                "prompt" => request.prompt = non_empty(field.text().await?),
+                "response_format" => {
+                    request.response_format = field
+                        .text()
+                        .await?
+                        .trim()
+                        .parse()
+                        .map_err(ApiError::bad_request)?;
+                }
+                "temperature" => {
+                    let value = field.text().await?;
+                    let temperature = value.trim().parse().map_err(|_| {
+                        ApiError::bad_request(format!("Invalid temperature '{}'", value))
+                    })?;
+                    request.temperature = Some(temperature);
+                }
+                "timestamp_granularities[]" | "timestamp_granularities" => {
+                    request.word_timestamps |= field.text().await?.trim() == "word";
+                }
+                "stream" => {
+                    let value = field.text().await?;
+                    request.stream = matches!(value.trim(), "true" | "1");
+                }
+                // `model` and unknown fields are ignored
+                _ => {}
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Transcription config for this request, based on the server config
+    fn config(&self, base: &TranscriptionConfig, task: Task) -> TranscriptionConfig {
+        let mut config = base.clone().with_translate(task == Task::Translate);
+        if let Some(ref language) = self.language {
+            config = config.with_language(language);
+        }
+        if let Some(ref prompt) = self.prompt {
+            config = config.with_prompt(prompt);
+        }
+        if let Some(temperature) = self.temperature {
+            config.temperature = temperature;
+        }
+        config.output_format.word_timestamps = self.word_timestamps;
+        config
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Error returned in the OpenAI error format
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request<S: Into<String>>(message: S) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    fn internal<S: Into<String>>(message: S) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: message.into(),
+        }
+    }
+
+    fn body(&self) -> serde_json::Value {
+        let kind = if self.status.is_client_error() {
+            "invalid_request_error"
+        } else {
+            "server_error"
+        };
+        json!({
+            "error": {
+                "message": self.message,
+                "type": kind,
+                "param": null,
+                "code": null,
+            }
+        })
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body())).into_response()
+    }
+}
+
+impl From<MultipartError> for ApiError {
+    fn from(e: MultipartError) -> Self {
+        Self {
+            status: e.status(),
+            message: format!("Invalid multipart body: {}", e.body_text()),
+        }
+    }
+}
+
+impl From<WhisperError> for ApiError {
+    fn from(e: WhisperError) -> Self {
+        match e {
+            // Undecodable uploads are the client's fault
+            WhisperError::AudioProcessing(_)
+            | WhisperError::NoAudioStream { .. }
+            | WhisperError::UnsupportedCodec { .. }
+            | WhisperError::FFmpeg { .. } => Self::bad_request(e.report()),
+            _ => Self::internal(e.report()),
+        }
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(e: std::io::Error) -> Self {
+        Self::internal(format!("Failed to store upload: {}", e))
+    }
+}
+
+/// `verbose_json` response body
+#[derive(Debug, Serialize)]
+struct VerboseTranscription {
+    task: &'static str,
+    language: Option<String>,
+    duration: f32,
+    text: String,
+    segments: Vec<VerboseSegment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<Vec<VerboseWord>>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerboseSegment {
+    id: usize,
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VerboseWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+impl VerboseTranscription {
+    fn new(result: &SyncTranscriptionResult, task: Task, word_timestamps: bool) -> Self {
+        let words = word_timestamps.then(|| {
+            result
+                .segments
+                .iter()
+                .flat_map(|segment| segment.words.iter().flatten())
+                .map(|word| VerboseWord {
+                    word: word.word.clone(),
+                    start: word.start,
+                    end: word.end,
+                })
+                .collect()
+        });
+
+        Self {
+            task: task.as_str(),
+            language: result.language.clone(),
+            duration: result.audio_duration,
+            text: result.text.trim().to_string(),
+            segments: result
+                .segments
+                .iter()
+                .enumerate()
+                .map(|(id, segment)| VerboseSegment {
+                    id,
+                    start: segment.start,
+                    end: segment.end,
+                    text: segment.text.trim().to_string(),
+                })
+                .collect(),
+            words,
+        }
+    }
+}
+
+/// Render a finished transcription in the requested format
+fn render(
+    result: &SyncTranscriptionResult,
+    format: ResponseFormat,
+    task: Task,
+    word_timestamps: bool,
+) -> Response {
+    let text = |body: String, content_type: &'static str| {
+        ([(header::CONTENT_TYPE, content_type)], body).into_response()
+    };
+    let segments: &[TranscriptionSegment] = &result.segments;
+
+    match format {
+        ResponseFormat::Json => Json(json!({ "text": result.text.trim() })).into_response(),
+        ResponseFormat::Text => text(
+            format!("{}\n", result.text.trim()),
+            "text/plain; charset=utf-8",
+        ),
+        ResponseFormat::Srt => text(to_srt(segments), "text/plain; charset=utf-8"),
+        ResponseFormat::Vtt => text(to_vtt(segments), "text/vtt; charset=utf-8"),
+        ResponseFormat::VerboseJson => {
+            Json(VerboseTranscription::new(result, task, word_timestamps)).into_response()
+        }
+    }
+}
+
+async fn transcriptions(
+    State(state): State<AppState>,
+    multipart: Multipart,
+) -> Result<Response, ApiError> {
+    handle_audio_request(state, multipart, Task::Transcribe).await
+}
+
+async fn translations(
+    State(state): State<AppState>,
+    multipart: Multipart,
+) -> Result<Response, ApiError> {
+    handle_audio_request(state, multipart, Task::Translate).await
+}
+
+async fn handle_audio_request(
+    state: AppState,
+    multipart: Multipart,
+    task: Task,
+) -> Result<Response, ApiError> {
+    let request = AudioRequest::from_multipart(multipart).await?;
+    let Some((file_name, contents)) = request.file.as_ref() else {
+        return Err(ApiError::bad_request("Missing 'file' field"));
+    };
+    info!(
+        "{} request for {} ({} bytes)",
+        task.as_str(),
+        file_name,
+        contents.len()
+    );
+
+    // ffmpeg reads from disk, keep the original extension as a format hint
+    let extension = std::path::Path::new(file_name)
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let upload = tempfile::Builder::new()
+        .prefix("purr-upload-")
+        .suffix(&extension)
+        .tempfile()?;
+    tokio::fs::write(upload.path(), contents).await?;
+
+    let config = request.config(&state.config, task);
+    let permit = state
+        .permits
+        .clone()
+        .acquire_owned()
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+
+    if request.stream {
+        let audio_stream = AudioProcessor::stream(upload.path()).await?;
+        let chunks = StreamWhisperTranscriber::with_model(&state.model, config)
+            .transcribe(audio_stream)
+            .await?;
+
+        // The upload and the permit must live as long as the stream
+        let events = sse_events(chunks, (upload, permit));
+        return Ok(Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response());
+    }
+
+    let audio = AudioProcessor::new()?.load_audio(upload.path()).await?;
+    let result = SyncWhisperTranscriber::with_model(&state.model, config)
+        .transcribe(audio)
+        .await?;
+    drop(permit);
+
+    Ok(render(
+        &result,
+        request.response_format,
+        task,
+        request.word_timestamps,
+    ))
+}
+
+/// Server-sent events for a streaming transcription
+///
+/// Sends a `transcript.text.delta` event per chunk and a final
+/// `transcript.text.done` event with the full text, like the OpenAI API.
+/// Chunks that could not be transcribed are reported with a
+/// `transcript.chunk.failed` event and the stream goes on.
+fn sse_events<S, G>(
+    chunks: S,
+    guard: G,
+) -> impl Stream<Item = Result<Event, axum::Error>> + Send + 'static
+where
+    S: Stream<Item = purr_core::Result<purr_core::StreamingChunk>> + Send + Unpin + 'static,
+    G: Send + 'static,
+{
+    stream::unfold(
+        (chunks, String::new(), false, guard),
+        |(mut chunks, mut text, done, guard)| async move {
+            if done {
+                return None;
+            }
+
+            let (event, done) = match chunks.next().await {
+                Some(Ok(chunk)) if chunk.is_failed() => {
+                    let event = json!({
+                        "type": "transcript.chunk.failed",
+                        "chunk_index": chunk.chunk_index,
+                        "start": chunk.start,
+                        "end": chunk.end,
+                        "error": { "message": chunk.error },
+                    });
+                    (event, false)
+                }
+                Some(Ok(chunk)) => {
+                    text.push_str(&chunk.text);
+                    let event = json!({
+                        "type": "transcript.text.delta",
+                        "delta": chunk.text,
+                    });
+                    (event, false)
+                }
+                Some(Err(e)) => {
+                    error!("Streaming transcription failed: {}", e.report());
+                    let event = json!({
+                        "type": "error",
+                        "error": { "message": e.report() },
+                    });
+                    (event, true)
+                }
+                None => {
+                    let event = json!({
+                        "type": "transcript.text.done",
+                        "text": text.trim(),
+                    });
+                    (event, true)
+                }
+            };
+
+            Some((
+                Event::default().json_data(event),
+                (chunks, text, done, guard),
+            ))
+        },
+    )
+}
+
+/// Client messages of a live session
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Open the session
+    Start(SessionOptions),
+    /// No more audio will be sent
+    Stop,
+}
+
+/// Options sent with the `start` message
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SessionOptions {
+    language: Option<String>,
+    translate: bool,
+    prompt: Option<String>,
+    encoding: LiveEncoding,
+}
+
+impl SessionOptions {
+    /// Transcription config for this session, based on the server config
+    fn config(&self, base: &TranscriptionConfig) -> TranscriptionConfig {
+        let mut config = base.clone().with_translate(self.translate);
+        if let Some(ref language) = self.language {
+            config = config.with_language(language);
+        }
+        if let Some(ref prompt) = self.prompt {
+            config = config.with_prompt(prompt);
+        }
+        config
+    }
+}
+
+/// Server messages of a live session
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    /// The session is open, audio frames can be sent
+    Ready,
+    /// Partial or final transcription of the current window
+    Transcript(StreamingChunk),
+    /// The session failed
+    Error { message: String },
+}
+
+impl ServerMessage {
+    fn to_message(&self) -> Message {
+        Message::Text(serde_json::to_string(self).unwrap_or_default().into())
+    }
+}
+
+async fn live(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| live_session(socket, state))
+}
+
+async fn live_session(mut socket: WebSocket, state: AppState) {
+    info!("Live session opened");
+    if let Err(message) = run_live_session(&mut socket, &state).await {
+        error!("Live session failed: {}", message);
+        let _ = socket
+            .send(ServerMessage::Error { message }.to_message())
+            .await;
+    }
+    let _ = socket.send(Message::Close(None)).await;
+    info!("Live session closed");
+}
+
+async fn run_live_session(socket: &mut WebSocket, state: &AppState) -> Result<(), String> {
+    let options = match socket.recv().await {
+        Some(Ok(Message::Text(text))) => match serde_json::from_str(text.as_str()) {
+            Ok(ClientMessage::Start(options)) => options,
+            Ok(ClientMessage::Stop) => return Ok(()),
+            Err(e) => return Err(format!("Invalid start message: {}", e)),
+        },
+        Some(Ok(_)) => return Err("Expected a start message".to_string()),
+        Some(Err(e)) => return Err(e.to_string()),
+        None => return Ok(()),
+    };
+
+    // Live sessions share the transcription slots of uploads, for as long as they last
+    let _permit = state
+        .permits
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| "Server busy: all transcription slots are in use".to_string())?;
+
+    let mut decoder = LiveDecoder::new(options.encoding).map_err(|e| e.report())?;
+    let (audio_tx, audio_rx) = mpsc::channel(LIVE_AUDIO_FRAMES);
+    let mut audio_tx = Some(audio_tx);
+    let mut results =
+        StreamWhisperTranscriber::with_model(&state.model, options.config(&state.config))
+            .transcribe_live(audio_rx, LiveOptions::default());
+
+    socket
+        .send(ServerMessage::Ready.to_message())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        tokio::select! {
+            message = socket.recv(), if audio_tx.is_some() => match message {
+                Some(Ok(Message::Binary(frame))) => {
+                    let samples = decoder.decode(&frame).map_err(|e| e.report())?;
+                    // Waits while transcription falls behind, which stops
+                    // reading the socket and slows the client down
+                    if let Some(ref audio_tx) = audio_tx {
+                        let _ = audio_tx.send(samples).await;
+                    }
+                }
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(text.as_str()) {
+                    // Dropping the sender flushes the last window
+                    Ok(ClientMessage::Stop) => audio_tx = None,
+                    Ok(ClientMessage::Start(_)) => {
+                        return Err("Session already started".to_string())
+                    }
+                    Err(e) => return Err(format!("Invalid message: {}", e)),
+                },
+                // The client went away, nobody is left to read results
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.to_string()),
+            },
+            chunk = results.next() => match chunk {
+                Some(Ok(chunk)) => {
+                    let is_last = chunk.final_stats.is_some();
+                    socket
+                        .send(ServerMessage::Transcript(chunk).to_message())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    if is_last {
+                        return Ok(());
+                    }
+                }
+                Some(Err(e)) => return Err(e.report()),
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/v1/audio/transcriptions", post(transcriptions))
+        .route("/v1/audio/translations", post(translations))
+        .route("/v1/audio/stream", get(live))
+        .layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE))
+        .with_state(state)
+}
+
+/// Load the model and serve requests until interrupted
+pub async fn run(config: TranscriptionConfig, options: ServeOptions) -> anyhow::Result<()> {
+    let model = SharedModel::load(&config).await?;
+    let state = AppState {
+        model,
+        config,
+        permits: Arc::new(Semaphore::new(options.parallel.max(1))),
+    };
+
+    let listener = tokio::net::TcpListener::bind(options.addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", options.addr, e))?;
+
+    eprintln!(
+        "{} Listening on {}",
+        "Info:".blue().bold(),
+        format!("http://{}", listener.local_addr()?).green()
+    );
+    eprintln!("  POST /v1/audio/transcriptions");
+    eprintln!("  POST /v1/audio/translations");
+    eprintln!("  GET  /v1/audio/stream (WebSocket)");
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use purr_core::whisper::{TranscriptionStats, WordTimestamp};
+    use purr_core::ChunkStatus;
+
+    fn result() -> SyncTranscriptionResult {
+        SyncTranscriptionResult {
+            text: " Hello there.".to_string(),
+            language: Some("en".to_string()),
+            segments: vec![TranscriptionSegment {
+                text: " Hello there.".to_string(),
+                start: 0.0,
+                end: 1.5,
+                confidence: None,
+                words: Some(vec![WordTimestamp {
+                    word: "Hello".to_string(),
+                    start: 0.0,
+                    end: 0.6,
+                    confidence: Some(0.9),
+                }]),
+                no_speech_prob: None,
+                hallucination: None,
+                speaker_turn_next: false,
+                tokens: None,
+            }],
+            processing_time: 0.5,
+            audio_duration: 1.5,
+            stats: TranscriptionStats::new(0.5, 1.5, 1, 2),
+            model: None,
+        }
+    }
+
+    #[test]
+    fn test_response_format() {
+        assert_eq!("json".parse(), Ok(ResponseFormat::Json));
+        assert_eq!("verbose_json".parse(), Ok(ResponseFormat::VerboseJson));
+        assert_eq!("vtt".parse(), Ok(ResponseFormat::Vtt));
+        assert!("xml".parse::<ResponseFormat>().is_err());
+    }
+
+    #[test]
+    fn test_verbose_json() {
+        let verbose = VerboseTranscription::new(&result(), Task::Translate, true);
+        let value = serde_json::to_value(&verbose).unwrap();
+
+        assert_eq!(value["task"], "translate");
+        assert_eq!(value["text"], "Hello there.");
+        assert_eq!(value["segments"][0]["id"], 0);
+        assert_eq!(value["segments"][0]["end"], 1.5);
+        assert_eq!(value["words"][0]["word"], "Hello");
+
+        let verbose = VerboseTranscription::new(&result(), Task::Transcribe, false);
+        let value = serde_json::to_value(&verbose).unwrap();
+        assert!(value.get("words").is_none());
+    }
+
+    #[test]
+    fn test_request_config() {
+        let request = AudioRequest {
+            language: Some("de".to_string()),
+            prompt: Some("Glossary: purr".to_string()),
+            temperature: Some(0.4),
+            word_timestamps: true,
+            ..Default::default()
+        };
+        let base = TranscriptionConfig::new().with_threads(4);
+
+        let config = request.config(&base, Task::Translate);
+        assert!(config.translate);
+        assert_eq!(config.language.as_deref(), Some("de"));
+        assert_eq!(config.prompt.as_deref(), Some("Glossary: purr"));
+        assert_eq!(config.temperature, 0.4);
+        assert_eq!(config.num_threads, Some(4));
+        assert!(config.output_format.word_timestamps);
+    }
+
+    #[test]
+    fn test_live_messages() {
+        let start = r#"{"type": "start", "language": "fr", "translate": true, "encoding": "opus"}"#;
+        let ClientMessage::Start(options) = serde_json::from_str(start).unwrap() else {
+            panic!("expected a start message");
+        };
+        assert_eq!(options.encoding, LiveEncoding::Opus);
+
+        let config = options.config(&TranscriptionConfig::new());
+        assert!(config.translate);
+        assert_eq!(config.language.as_deref(), Some("fr"));
+        assert_eq!(config.prompt, None);
+
+        let options: ClientMessage = serde_json::from_str(r#"{"type": "start"}"#).unwrap();
+        assert!(matches!(
+            options,
+            ClientMessage::Start(SessionOptions {
+                encoding: LiveEncoding::PcmS16le,
+                ..
+            })
+        ));
+
+        let chunk = StreamingChunk {
+            text: " Hello".to_string(),
+            start: 0.0,
+            end: 1.0,
+            is_final: false,
+            chunk_index: 0,
+            final_stats: None,
+            status: ChunkStatus::Ok,
+            error: None,
+        };
+        let value = serde_json::to_value(ServerMessage::Transcript(chunk)).unwrap();
+        assert_eq!(value["type"], "transcript");
+        assert_eq!(value["is_final"], false);
+        assert_eq!(value["text"], " Hello");
+        assert_eq!(value["status"], "ok");
+        assert!(value.get("error").is_none());
+    }
+
+    #[test]
+    fn test_error_body() {
+        let body = ApiError::bad_request("Missing 'file' field").body();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["message"], "Missing 'file' field");
+
+        let error: ApiError = WhisperError::Transcription("boom".to_string()).into();
+        assert_eq!(error.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}